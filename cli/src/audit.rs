@@ -0,0 +1,327 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use jiff::{civil::Date, tz::TimeZone, Timestamp};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{config::Config, duration::HumanDuration, models::Decision};
+
+/// How a permission request ended, as recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditDecision {
+    Allow,
+    Deny,
+    Dismiss,
+    /// No answer before the hook gave up; Claude Code asked at the terminal
+    Timeout,
+    /// The request could not be delivered or polled
+    Error,
+    /// Answered at the terminal first; the phone prompt was retracted
+    Terminal,
+}
+
+impl AuditDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditDecision::Allow => "allow",
+            AuditDecision::Deny => "deny",
+            AuditDecision::Dismiss => "dismiss",
+            AuditDecision::Timeout => "timeout",
+            AuditDecision::Error => "error",
+            AuditDecision::Terminal => "terminal",
+        }
+    }
+}
+
+impl From<Decision> for AuditDecision {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Allow => AuditDecision::Allow,
+            Decision::Deny => AuditDecision::Deny,
+            Decision::Dismiss => AuditDecision::Dismiss,
+        }
+    }
+}
+
+/// One remotely handled permission request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339 UTC timestamp of when the request was answered or gave up
+    pub time: Timestamp,
+    pub session_id: String,
+    #[serde(default)]
+    pub session: Option<String>,
+    pub cwd: String,
+    pub tool: String,
+    /// Tool input with secrets and the home directory redacted
+    pub input: Value,
+    pub decision: AuditDecision,
+    /// "notification" or "details", as reported by the backend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Device that answered, e.g. "Chrome on Android"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    pub transport: String,
+    pub backend: String,
+    /// Time from sending the notification to the outcome
+    pub latency_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_use_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// The part of the tool input worth showing in a one-line listing
+    pub fn summary(&self) -> String {
+        let text = ["command", "file_path", "path", "url", "pattern"]
+            .iter()
+            .find_map(|key| self.input.get(key).and_then(Value::as_str))
+            .map(String::from)
+            .unwrap_or_else(|| self.input.to_string());
+        let first_line = text.lines().next().unwrap_or_default();
+        if first_line.chars().count() > 80 {
+            format!("{}…", first_line.chars().take(79).collect::<String>())
+        } else {
+            first_line.to_string()
+        }
+    }
+}
+
+/// Filters for `claude-afk history`
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub since: Option<Timestamp>,
+    pub tool: Option<String>,
+    pub decision: Option<AuditDecision>,
+    /// Matches a session id prefix or part of the session label
+    pub session: Option<String>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.since.is_none_or(|since| entry.time >= since)
+            && self
+                .tool
+                .as_ref()
+                .is_none_or(|tool| entry.tool.eq_ignore_ascii_case(tool))
+            && self.decision.is_none_or(|d| entry.decision == d)
+            && self.session.as_ref().is_none_or(|session| {
+                entry.session_id.starts_with(session.as_str())
+                    || entry
+                        .session
+                        .as_ref()
+                        .is_some_and(|label| label.to_lowercase().contains(&session.to_lowercase()))
+            })
+    }
+}
+
+/// Append-only JSONL log of every remote decision, for reviewing who approved
+/// what on shared machines
+pub struct AuditLog;
+
+impl AuditLog {
+    pub fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(Config::dir()?.join("audit.jsonl"))
+    }
+
+    /// Append one entry. A single write of a whole line keeps concurrent hook
+    /// processes from interleaving entries.
+    pub fn append(path: &Path, entry: &AuditEntry) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Entries matching `filter`, oldest first. Lines that don't parse (e.g.
+    /// from a newer version) are skipped.
+    pub fn read(
+        path: &Path,
+        filter: &AuditFilter,
+    ) -> Result<Vec<AuditEntry>, Box<dyn std::error::Error>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Could not read {}: {}", path.display(), e).into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+            .filter(|entry| filter.matches(entry))
+            .collect())
+    }
+
+    /// Parse `--since`: a relative age such as "30m", "12h", "7d" or "2w", a
+    /// local date ("2026-03-01"), or an RFC 3339 timestamp
+    pub fn parse_since(value: &str, now: Timestamp) -> Result<Timestamp, String> {
+        let value = value.trim();
+        let invalid = || {
+            format!(
+                "Invalid --since value '{}'. Use an age like 30m, 12h, 7d or 2w, a date like 2026-03-01, or an RFC 3339 timestamp",
+                value
+            )
+        };
+
+        if let Some(age) = HumanDuration::parse(value) {
+            return jiff::SignedDuration::try_from(age)
+                .ok()
+                .and_then(|age| now.checked_sub(age).ok())
+                .ok_or_else(invalid);
+        }
+
+        if let Ok(timestamp) = value.parse::<Timestamp>() {
+            return Ok(timestamp);
+        }
+        value
+            .parse::<Date>()
+            .and_then(|date| date.to_zoned(TimeZone::system()))
+            .map(|zoned| zoned.timestamp())
+            .map_err(|_| invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(tool: &str, decision: AuditDecision, time: &str) -> AuditEntry {
+        AuditEntry {
+            time: time.parse().unwrap(),
+            session_id: "5f0c8a52-2d4b".to_string(),
+            session: Some("webapp (main) · laptop".to_string()),
+            cwd: "~/webapp".to_string(),
+            tool: tool.to_string(),
+            input: json!({"command": "npm test", "description": "Run tests"}),
+            decision,
+            source: Some("notification".to_string()),
+            device: Some("Chrome on Android".to_string()),
+            transport: "web-push".to_string(),
+            backend: "claude-afk.dev".to_string(),
+            latency_ms: 1200,
+            tool_use_id: Some("toolu_1".to_string()),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_read_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("claude-afk-audit-{}", std::process::id()))
+            .join("audit.jsonl");
+        let first = entry("Bash", AuditDecision::Allow, "2026-03-01T10:00:00Z");
+        let second = entry("Write", AuditDecision::Deny, "2026-03-02T10:00:00Z");
+
+        AuditLog::append(&path, &first).unwrap();
+        AuditLog::append(&path, &second).unwrap();
+        // Unparseable lines are skipped rather than failing the whole read
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"truncated\n").unwrap();
+
+        let all = AuditLog::read(&path, &AuditFilter::default()).unwrap();
+        let raw = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(all, vec![first, second]);
+        assert!(raw.starts_with("{\"time\":\"2026-03-01T10:00:00Z\""));
+    }
+
+    #[test]
+    fn test_read_missing_log_is_empty() {
+        let path = Path::new("/nonexistent/claude-afk/audit.jsonl");
+        assert!(AuditLog::read(path, &AuditFilter::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_filters() {
+        let e = entry("Bash", AuditDecision::Allow, "2026-03-01T10:00:00Z");
+
+        let since = |t: &str| AuditFilter {
+            since: Some(t.parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(since("2026-03-01T09:00:00Z").matches(&e));
+        assert!(!since("2026-03-01T11:00:00Z").matches(&e));
+
+        let tool = |t: &str| AuditFilter {
+            tool: Some(t.to_string()),
+            ..Default::default()
+        };
+        assert!(tool("bash").matches(&e));
+        assert!(!tool("Write").matches(&e));
+
+        let decision = |d| AuditFilter {
+            decision: Some(d),
+            ..Default::default()
+        };
+        assert!(decision(AuditDecision::Allow).matches(&e));
+        assert!(!decision(AuditDecision::Timeout).matches(&e));
+
+        let session = |s: &str| AuditFilter {
+            session: Some(s.to_string()),
+            ..Default::default()
+        };
+        assert!(session("5f0c").matches(&e));
+        assert!(session("WebApp").matches(&e));
+        assert!(!session("billing").matches(&e));
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now: Timestamp = "2026-03-10T12:00:00Z".parse().unwrap();
+
+        assert_eq!(
+            AuditLog::parse_since("2h", now).unwrap(),
+            "2026-03-10T10:00:00Z".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            AuditLog::parse_since("7d", now).unwrap(),
+            "2026-03-03T12:00:00Z".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            AuditLog::parse_since("2026-03-01T08:30:00Z", now).unwrap(),
+            "2026-03-01T08:30:00Z".parse::<Timestamp>().unwrap()
+        );
+        assert!(AuditLog::parse_since("2026-03-01", now).is_ok());
+        assert!(AuditLog::parse_since("5y", now).is_err());
+        assert!(AuditLog::parse_since("yesterday", now).is_err());
+    }
+
+    #[test]
+    fn test_summary_prefers_command_or_path() {
+        let mut e = entry("Bash", AuditDecision::Allow, "2026-03-01T10:00:00Z");
+        assert_eq!(e.summary(), "npm test");
+
+        e.input = json!({"file_path": "~/webapp/src/main.rs", "content": "fn main() {}"});
+        assert_eq!(e.summary(), "~/webapp/src/main.rs");
+
+        e.input = json!({"query": "x".repeat(200)});
+        assert_eq!(e.summary().chars().count(), 80);
+    }
+
+    #[test]
+    fn test_decision_serializes_lowercase() {
+        assert_eq!(
+            serde_json::to_string(&AuditDecision::Timeout).unwrap(),
+            "\"timeout\""
+        );
+        assert_eq!(
+            AuditDecision::from(Decision::Dismiss),
+            AuditDecision::Dismiss
+        );
+    }
+}
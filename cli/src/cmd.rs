@@ -1,121 +1,322 @@
-use std::{fs, io::Read, path::PathBuf, thread, time::Instant};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
 
 use colored::Colorize;
+use jiff::Timestamp;
 use nanoid::nanoid;
-use qrcode::{render::unicode::Dense1x2, QrCode};
 
 use crate::{
-    config::Config,
+    audit::{AuditDecision, AuditEntry, AuditFilter, AuditLog},
+    config::{ActiveMode, Config},
     constants::{
-        DECISION_POLL_INTERVAL, DECISION_TIMEOUT, DEFAULT_API_URL, POLL_INTERVAL, SETUP_TIMEOUT,
+        APP_NAME, DECISION_POLL_INTERVAL, DECISION_TIMEOUT_ENV, DEFAULT_API_URL,
+        HEALTH_CHECK_TIMEOUT, LOG_ENV, LOG_FOLLOW_INTERVAL, POLL_INTERVAL, POLL_INTERVAL_ENV,
+        SETUP_TIMEOUT, SETUP_TIMEOUT_ENV, TOKEN_REJECTED_MESSAGE,
     },
-    logger::Logger,
+    error::{DecisionError, HookError, PairError},
+    logger::{LogLevel, Logger},
     models::{
-        DecisionStatusResponse, GenericHookInput, HookOutput, NotificationInput, NotifyPayload,
-        NotifyResponse, PairingInitResponse, PairingStatusResponse, PermissionRequestInput,
-        SimpleNotifyPayload, ToolInfo,
+        BackendHealth, Decision, DecisionOutcome, DecisionStatusResponse, DeviceStatusResponse,
+        GenericHookInput, NotificationInput, NotifyPayload, NotifyResponse, PairingInitResponse,
+        PairingStatusResponse, PermissionRequestInput, PostToolUseInput, RemoteDecision,
+        SimpleNotifyPayload, StatusReport, TestTool, TokenRotateResponse, ToolInfo,
     },
+    presence::Presence,
+    qr::{Qr, QrFormat},
+    redact::Redact,
+    registry::{PendingPrompt, SessionRecord, SessionRegistry},
+    replay::Fixtures,
+    schedule::ScheduleCheck,
+    session::SessionInfo,
+    settings::{ClaudeSettings, HookEvent, HookKind, HookScope},
+    templates::Templates,
+    transcript::Transcript,
 };
 
 pub struct Cmd;
 
+/// Result of a single `doctor` check, with a fix-it hint on failure
+enum DoctorOutcome {
+    Pass {
+        name: String,
+        detail: String,
+    },
+    Warn {
+        name: String,
+        detail: String,
+        hint: String,
+    },
+    Fail {
+        name: String,
+        detail: String,
+        hint: String,
+    },
+}
+
+impl DoctorOutcome {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        DoctorOutcome::Pass {
+            name: name.into(),
+            detail: detail.into(),
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        DoctorOutcome::Warn {
+            name: name.into(),
+            detail: detail.into(),
+            hint: hint.into(),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        DoctorOutcome::Fail {
+            name: name.into(),
+            detail: detail.into(),
+            hint: hint.into(),
+        }
+    }
+
+    fn print(&self) {
+        match self {
+            DoctorOutcome::Pass { name, detail } => {
+                println!("  {} {:<26} {}", "✓".green(), name, detail.dimmed());
+            }
+            DoctorOutcome::Warn { name, detail, hint } => {
+                println!("  {} {:<26} {}", "○".yellow(), name, detail.yellow());
+                println!("    {} {}", "→".dimmed(), hint);
+            }
+            DoctorOutcome::Fail { name, detail, hint } => {
+                println!("  {} {:<26} {}", "✗".red(), name, detail.red());
+                println!("    {} {}", "→".dimmed(), hint);
+            }
+        }
+    }
+}
+
 impl Cmd {
-    pub fn pair() -> Result<(), Box<dyn std::error::Error>> {
+    pub fn pair(
+        qr_format: Option<QrFormat>,
+        qr_output: Option<PathBuf>,
+        json: bool,
+        wait: Option<String>,
+        profile: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let mut config = Config::load()?;
-
-        let backend_url = Self::get_backend_url();
+        if let Some(profile) = &profile {
+            config.apply_profile(profile, "--profile")?;
+        }
+        let backend_url = Self::get_backend_url(&config);
         let backend_url = backend_url.trim_end_matches('/');
 
+        // Finish a pairing started earlier with `pair --json`
+        if let Some(pairing_id) = wait {
+            return Self::finish_pairing(backend_url, &pairing_id, json, profile.as_deref());
+        }
+
+        let qr_format = qr_format
+            .or_else(|| {
+                qr_output
+                    .as_deref()
+                    .and_then(Self::qr_format_from_extension)
+            })
+            .unwrap_or_else(QrFormat::detect);
+        if qr_format.is_image() && qr_output.is_none() {
+            return Err("--qr-format svg and png require --qr-output <path>".into());
+        }
+
+        if json {
+            let response =
+                Self::initiate_pairing(backend_url).inspect_err(Self::print_pair_json_error)?;
+            let pairing_url = format!("{}/pair/{}", backend_url, response.pairing_token);
+            if let Some(path) = &qr_output {
+                Qr::save(&pairing_url, qr_format, path)?;
+            }
+
+            let line = serde_json::json!({
+                "status": "pending",
+                "pairing_id": response.pairing_id,
+                "pairing_url": pairing_url,
+                "pairing_code": response.pairing_code,
+                "expires_in": response
+                    .pairing_expires_at
+                    .map(|at| at.saturating_sub(Self::unix_now()))
+                    .unwrap_or(Self::timing(SETUP_TIMEOUT_ENV, SETUP_TIMEOUT).as_secs()),
+            });
+            println!("{}", line);
+            return Ok(());
+        }
+
         // Initiate pairing
         println!();
         println!("  {} {}", "◆".cyan(), "Claude AFK Pairing".bold());
         println!("  {} {}", "→".dimmed(), backend_url.dimmed());
         println!();
 
-        let response: PairingInitResponse =
-            ureq::post(&format!("{}/api/pairing/initiate", backend_url))
-                .send_empty()?
-                .into_body()
-                .read_json()?;
-
+        let response = Self::initiate_pairing(backend_url)?;
         let pairing_url = format!("{}/pair/{}", backend_url, response.pairing_token);
 
-        println!("  📱 Scan this QR code with your phone:");
-        println!();
-        Self::render_qr(&pairing_url)?;
+        if let Some(path) = &qr_output {
+            Qr::save(&pairing_url, qr_format, path)?;
+            println!(
+                "  📱 QR code saved to {}",
+                path.display().to_string().cyan()
+            );
+            println!("     Open it and scan it with your phone.");
+        } else {
+            println!("  📱 Scan this QR code with your phone:");
+            println!();
+            println!("{}", Qr::render_text(&pairing_url, qr_format)?);
+        }
         println!();
         println!(
             "  {} {}",
             "Or open:".dimmed(),
             pairing_url.cyan().underline()
         );
+        println!();
+        if let Some(code) = &response.pairing_code {
+            println!(
+                "  {} {} {} {}",
+                "Or enter code".dimmed(),
+                Self::format_pairing_code(code).bold(),
+                "at".dimmed(),
+                format!("{}/pair", backend_url).cyan().underline()
+            );
+            println!();
+        }
+
+        Self::finish_pairing(backend_url, &response.pairing_id, false, profile.as_deref())
+    }
+
+    fn initiate_pairing(backend_url: &str) -> Result<PairingInitResponse, PairError> {
+        ureq::post(&format!("{}/api/pairing/initiate", backend_url))
+            .send_empty()?
+            .into_body()
+            .read_json()
+            .map_err(PairError::from)
+    }
+
+    /// Poll until the phone completes pairing, then store the device token
+    fn finish_pairing(
+        backend_url: &str,
+        pairing_id: &str,
+        json: bool,
+        profile: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = Config::load()?;
+
+        if !json {
+            println!(
+                "  {} Waiting for pairing... {}",
+                "◌".yellow(),
+                "(press Ctrl+C to cancel)".dimmed()
+            );
+        }
+
+        let status = match Self::poll_pairing(backend_url, pairing_id) {
+            Ok(status) => status,
+            Err(e) => {
+                if json {
+                    Self::print_pair_json_error(&e);
+                } else {
+                    println!();
+                    let message = match &e {
+                        PairError::Timeout => "Pairing timed out after 5 minutes".to_string(),
+                        other => other.to_string(),
+                    };
+                    println!("  {} {}", "✗".red(), message.red());
+                }
+                return Err(e.into());
+            }
+        };
+
+        config.set_device_token(
+            profile,
+            status.device_token,
+            status.expires_at,
+            status.rotate_after,
+        )?;
+        // A profile keeps the default device's backend and mode untouched
+        if profile.is_none() {
+            config.backend_url = backend_url.to_string();
+            config.active = ActiveMode::On;
+        }
+        Config::save(&config)?;
+
+        if json {
+            println!("{}", serde_json::json!({ "status": "paired" }));
+            return Ok(());
+        }
+
         println!();
         println!(
-            "  {} Waiting for pairing... {}",
-            "◌".yellow(),
-            "(press Ctrl+C to cancel)".dimmed()
+            "  {} {}",
+            "✓".green().bold(),
+            "Pairing successful!".green().bold()
         );
+        println!(
+            "    {} Notifications are now {}",
+            "→".dimmed(),
+            "enabled".green()
+        );
+        println!();
+        Ok(())
+    }
 
-        // Poll for completion
+    fn poll_pairing(
+        backend_url: &str,
+        pairing_id: &str,
+    ) -> Result<PairingStatusResponse, PairError> {
+        let timeout = Self::timing(SETUP_TIMEOUT_ENV, SETUP_TIMEOUT);
+        let poll_interval = Self::timing(POLL_INTERVAL_ENV, POLL_INTERVAL);
         let start = Instant::now();
         loop {
-            if start.elapsed() > SETUP_TIMEOUT {
-                println!();
-                println!(
-                    "  {} {}",
-                    "✗".red(),
-                    "Pairing timed out after 5 minutes".red()
-                );
-                return Err("Pairing timed out".into());
-            }
-
-            thread::sleep(POLL_INTERVAL);
+            thread::sleep(poll_interval);
 
             let status: PairingStatusResponse = ureq::get(&format!(
                 "{}/api/pairing/{}/status",
-                backend_url, response.pairing_id
+                backend_url, pairing_id
             ))
             .call()?
             .into_body()
             .read_json()?;
 
             if status.complete {
-                if let Some(device_token) = status.device_token {
-                    config.device_token = Some(device_token);
-                    config.backend_url = backend_url.to_string();
-                    config.active = true;
-                    Config::save(&config)?;
-
-                    println!();
-                    println!(
-                        "  {} {}",
-                        "✓".green().bold(),
-                        "Pairing successful!".green().bold()
-                    );
-                    println!(
-                        "    {} Notifications are now {}",
-                        "→".dimmed(),
-                        "enabled".green()
-                    );
-                    println!();
-                    return Ok(());
-                } else {
-                    return Err("Pairing completed but no device token received".into());
+                if status.device_token.is_none() {
+                    return Err(PairError::Network(
+                        "pairing completed but no device token received".into(),
+                    ));
                 }
+                return Ok(status);
+            }
+
+            // `pair --wait` may resume a pairing created long before, so the
+            // backend's expiry counts, not just how long this process waited
+            let expired = status
+                .pairing_expires_at
+                .is_some_and(|at| Self::unix_now() >= at);
+            if expired || start.elapsed() > timeout {
+                return Err(PairError::Timeout);
             }
         }
     }
 
-    pub fn notify(json_arg: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
-        let config = Config::load()?;
-
-        // If not configured or not active, fall back to asking user normally
-        if config.device_token.is_none() || !config.active {
-            std::process::exit(0);
-        }
+    fn print_pair_json_error(e: &PairError) {
+        let line = serde_json::json!({
+            "status": "error",
+            "error": e.kind(),
+            "message": e.to_string(),
+        });
+        println!("{}", line);
+    }
 
-        let device_token = config.device_token.unwrap();
-        let backend_url = Self::get_backend_url();
+    pub fn notify(json_arg: Option<String>) -> Result<(), HookError> {
+        let mut config = Config::load().map_err(|e| HookError::Failed(e.to_string()))?;
 
         // Use JSON from argument if provided, otherwise read from stdin
         let input = match json_arg {
@@ -127,101 +328,270 @@ impl Cmd {
             }
         };
 
-        // First, determine the hook type
-        let generic_input: GenericHookInput = match serde_json::from_str(&input) {
-            Ok(v) => v,
-            Err(e) => {
-                eprintln!("{}", &format!("Failed to parse hook input: {}", e));
-                std::process::exit(1);
+        // Recording is best-effort and happens even when notifications are off
+        if let Some(dir) = &config.record_dir {
+            match Fixtures::record(dir, &input) {
+                Ok(path) => Logger::debug(&format!("Recorded hook input to {}", path.display())),
+                Err(e) => Logger::warn(&format!("Failed to record hook input: {}", e)),
+            }
+        }
+
+        // Session tracking is best-effort too, and also independent of `active`
+        Self::track_session(&input, &config);
+
+        // The project's profile decides the backend, device and mode
+        let value = serde_json::from_str::<serde_json::Value>(&input).ok();
+        let field = |key: &str| value.as_ref()?.get(key)?.as_str().map(String::from);
+        if let Some(cwd) = field("cwd").map(PathBuf::from) {
+            if let Err(e) = config.apply_project(&cwd) {
+                return Err(HookError::Fallback(Some(e)));
+            }
+        }
+        if let Some(profile) = &config.profile {
+            Logger::debug(&format!(
+                "Using profile {} (from {})",
+                profile.name, profile.source
+            ));
+        }
+
+        // If not configured or not active, fall back to asking user normally
+        let Some(device_token) = config.device_token.clone() else {
+            return Err(HookError::Fallback(None));
+        };
+        let backend_url = Self::get_backend_url(&config);
+
+        // Retracting doesn't depend on notifications being on right now: being
+        // back at the keyboard is exactly when prompts get answered there
+        match field("hook_event_name").as_deref() {
+            Some("PostToolUse" | "PostToolUseFailure") => {
+                return Self::handle_post_tool_use(&input, &device_token, &backend_url);
             }
+            Some("UserPromptSubmit") => {
+                return Self::handle_user_prompt_submit(&input, &device_token, &backend_url);
+            }
+            _ => {}
+        }
+        if !Self::notifications_enabled(&config) {
+            return Err(HookError::Fallback(None));
+        }
+
+        // Rotation is best-effort: the current token stays valid until it expires
+        let device_token = if Self::token_rotation_due(&config) {
+            match Self::rotate_device_token(&mut config, &backend_url) {
+                Ok(()) => config.device_token.clone().unwrap_or(device_token),
+                Err(e) => {
+                    Logger::warn(&format!("Device token rotation failed: {}", e));
+                    device_token
+                }
+            }
+        } else {
+            device_token
         };
 
+        Self::handle_hook_input(&input, &config, &device_token, &backend_url)
+    }
+
+    /// Dispatch a raw hook payload to the handler for its event
+    fn handle_hook_input(
+        input: &str,
+        config: &Config,
+        device_token: &str,
+        backend_url: &str,
+    ) -> Result<(), HookError> {
+        // First, determine the hook type
+        let generic_input: GenericHookInput = serde_json::from_str(input)
+            .map_err(|e| HookError::Failed(format!("Failed to parse hook input: {}", e)))?;
+
+        Logger::debug(&format!(
+            "{} hook for session {}",
+            generic_input.hook_event_name, generic_input.session_id
+        ));
+
         // Handle based on hook type
         match generic_input.hook_event_name.as_str() {
-            "Notification" => Self::handle_notification(&input, &device_token, &backend_url),
+            "Notification" => Self::handle_notification(input, config, device_token, backend_url),
             "PermissionRequest" => {
-                Self::handle_permission_request(&input, &device_token, &backend_url)
+                Self::handle_permission_request(input, config, device_token, backend_url)
             }
-            _ => {
-                eprintln!("Unknown hook event: {}", generic_input.hook_event_name);
-                std::process::exit(1);
+            "PostToolUse" | "PostToolUseFailure" => {
+                Self::handle_post_tool_use(input, device_token, backend_url)
             }
+            "UserPromptSubmit" => Self::handle_user_prompt_submit(input, device_token, backend_url),
+            other => Err(HookError::Failed(format!("Unknown hook event: {}", other))),
         }
     }
 
     fn handle_notification(
         input: &str,
+        config: &Config,
         device_token: &str,
         backend_url: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let notification: NotificationInput = match serde_json::from_str(input) {
-            Ok(v) => v,
-            Err(e) => {
-                eprintln!("{}", &format!("Failed to parse Notification input: {}", e));
-                std::process::exit(1);
-            }
-        };
+    ) -> Result<(), HookError> {
+        let notification: NotificationInput = serde_json::from_str(input)
+            .map_err(|e| HookError::Failed(format!("Failed to parse Notification input: {}", e)))?;
 
         // Only handle idle_prompt notifications
         if notification.notification_type != "idle_prompt" {
-            // For other notification types, exit silently
-            std::process::exit(0);
+            return Err(HookError::Fallback(None));
         }
 
         // Use simple notification endpoint - no decision tracking needed
+        let session =
+            SessionInfo::detect(&notification.session_id, &notification.cwd, &config.labels);
+        let (title, message) = Self::idle_text(&notification, &session, config);
         let payload = SimpleNotifyPayload {
-            title: "Claude is waiting".to_string(),
-            message: notification.message.clone(),
+            title,
+            message,
+            session: Some(session),
         };
 
-        // Send notification and exit immediately (no decision polling for notifications)
+        // Send notification and exit immediately (no decision polling for notifications).
+        // Delivery failures are only noted: nothing is waiting on them. A
+        // rejected token is an error like on the permission path, since Claude
+        // Code doesn't show stderr for exit 0 and the pairing needs fixing.
         match ureq::post(&format!("{}/api/notify/simple", backend_url))
             .header("Authorization", &format!("Bearer {}", device_token))
             .send_json(&payload)
         {
             Ok(_) => {
                 Logger::debug("Notification sent successfully");
+                Ok(())
             }
-            Err(e) => {
-                eprintln!("{}", &format!("Failed to send notification: {}", e));
+            Err(ureq::Error::StatusCode(401)) => {
+                Err(HookError::Failed(TOKEN_REJECTED_MESSAGE.to_string()))
             }
+            Err(e) => Err(HookError::Fallback(Some(format!(
+                "Failed to send notification: {}",
+                e
+            )))),
         }
-
-        // Exit silently - notifications don't need any output
-        std::process::exit(0);
     }
 
     fn handle_permission_request(
         input: &str,
+        config: &Config,
         device_token: &str,
         backend_url: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let pre_tool_use: PermissionRequestInput = match serde_json::from_str(input) {
-            Ok(v) => v,
-            Err(e) => {
-                eprintln!(
-                    "{}",
-                    &format!("Failed to parse PermissionRequest input: {}", e)
-                );
-                std::process::exit(1);
+    ) -> Result<(), HookError> {
+        let pre_tool_use: PermissionRequestInput = serde_json::from_str(input).map_err(|e| {
+            HookError::Failed(format!("Failed to parse PermissionRequest input: {}", e))
+        })?;
+
+        let decision = Self::request_decision(&pre_tool_use, config, device_token, backend_url)?;
+
+        // Dismissed: let Claude Code ask in the terminal
+        let output = decision.hook_output().ok_or(HookError::Fallback(None))?;
+        let output = serde_json::to_string(&output)
+            .map_err(|e| HookError::Failed(format!("Failed to serialize hook output: {}", e)))?;
+        println!("{}", output);
+        Ok(())
+    }
+
+    /// A tool call went ahead, or ran and failed. If its permission prompt is
+    /// still waiting on the phone, it was answered at the terminal: cancel it
+    /// so the notification goes away and the blocked PermissionRequest hook exits.
+    fn handle_post_tool_use(
+        input: &str,
+        device_token: &str,
+        backend_url: &str,
+    ) -> Result<(), HookError> {
+        let post_tool_use: PostToolUseInput = serde_json::from_str(input)
+            .map_err(|e| HookError::Failed(format!("Failed to parse PostToolUse input: {}", e)))?;
+
+        let mut retracted = Vec::new();
+        Self::update_registry(|registry| {
+            retracted = registry.retract(
+                &post_tool_use.session_id,
+                post_tool_use.tool_use_id.as_deref(),
+                &post_tool_use.tool_name,
+                &post_tool_use.tool_input,
+                SessionRegistry::now(),
+            )
+        });
+        Self::cancel_decisions(&retracted, device_token, backend_url);
+        Ok(())
+    }
+
+    /// The user typed a new prompt, so anything the session was still asking
+    /// on the phone was answered at the terminal, typically with a denial that
+    /// no tool hook ever sees
+    fn handle_user_prompt_submit(
+        input: &str,
+        device_token: &str,
+        backend_url: &str,
+    ) -> Result<(), HookError> {
+        let generic_input: GenericHookInput = serde_json::from_str(input)
+            .map_err(|e| HookError::Failed(format!("Failed to parse hook input: {}", e)))?;
+
+        let mut retracted = Vec::new();
+        Self::update_registry(|registry| {
+            retracted = registry.retract_all(&generic_input.session_id, SessionRegistry::now())
+        });
+        Self::cancel_decisions(&retracted, device_token, backend_url);
+        Ok(())
+    }
+
+    /// Withdraw prompts from the phone; the blocked hooks see them cancelled
+    fn cancel_decisions(decision_ids: &[String], device_token: &str, backend_url: &str) {
+        for decision_id in decision_ids {
+            match ureq::post(&format!(
+                "{}/api/decision/{}/cancel",
+                backend_url, decision_id
+            ))
+            .header("Authorization", &format!("Bearer {}", device_token))
+            .send_empty()
+            {
+                Ok(_) => Logger::info(&format!(
+                    "Permission request {} answered at the terminal, retracted",
+                    decision_id
+                )),
+                // The phone prompt just stays until it expires; nothing to tell Claude Code
+                Err(e) => Logger::warn(&format!(
+                    "Failed to retract permission request {}: {}",
+                    decision_id, e
+                )),
             }
-        };
+        }
+    }
 
+    /// Push the permission request to the phone and wait for the decision
+    fn request_decision(
+        pre_tool_use: &PermissionRequestInput,
+        config: &Config,
+        device_token: &str,
+        backend_url: &str,
+    ) -> Result<Decision, DecisionError> {
         // Generate or use provided tool_use_id
         let tool_use_id = pre_tool_use
             .tool_use_id
             .clone()
             .unwrap_or_else(|| nanoid!(21));
 
-        // Parse tool-specific information
-        let tool_info = ToolInfo::from_pre_tool_use(&pre_tool_use);
-        let (title, message) = tool_info.format_for_notification();
+        let session =
+            SessionInfo::detect(&pre_tool_use.session_id, &pre_tool_use.cwd, &config.labels);
+        let (title, message) = Self::permission_text(pre_tool_use, &session, config);
+        let timeout = Self::timing(DECISION_TIMEOUT_ENV, config.decision_timeout());
 
         let payload = NotifyPayload {
             title,
             message,
             tool_use_id: tool_use_id.clone(),
             session_id: pre_tool_use.session_id.clone(),
+            session,
+            context: Transcript::context(Path::new(&pre_tool_use.transcript_path)),
+            timeout: timeout.as_secs_f64().ceil() as u64,
+        };
+
+        let since = SessionRegistry::now();
+        let pending = PendingPrompt {
+            decision_id: String::new(),
+            tool_use_id: pre_tool_use.tool_use_id.clone(),
+            tool_name: pre_tool_use.tool_name.clone(),
+            tool_input: pre_tool_use.tool_input.clone(),
+            title: payload.title.clone(),
+            message: payload.message.clone(),
+            since,
+            deadline: Some(since + payload.timeout),
         };
 
         // Send notification to backend and get decision ID
@@ -230,31 +600,86 @@ impl Cmd {
                 .header("Authorization", &format!("Bearer {}", device_token))
                 .send_json(&payload)
             {
-                Ok(resp) => match resp.into_body().read_json() {
-                    Ok(r) => r,
-                    Err(e) => {
-                        eprintln!("{}", &format!("Failed to parse notify response: {}", e));
-                        std::process::exit(1);
-                    }
-                },
+                Ok(resp) => resp.into_body().read_json().map_err(|e| {
+                    DecisionError::Protocol(format!("Failed to parse notify response: {}", e))
+                })?,
+                Err(ureq::Error::StatusCode(401)) => return Err(DecisionError::TokenRejected),
                 Err(e) => {
-                    eprintln!("{}", &format!("Failed to send notification: {}", e));
-                    std::process::exit(1);
+                    return Err(DecisionError::Network(format!(
+                        "Failed to send notification: {}",
+                        e
+                    )))
                 }
             };
 
-        // Poll for decision
         let decision_id = notify_response.decision_id;
+        let session_id = &pre_tool_use.session_id;
+        Logger::info(&format!(
+            "Permission request {} sent for {}",
+            decision_id, pre_tool_use.tool_name
+        ));
+        Self::update_registry(|registry| {
+            registry.add_pending(
+                session_id,
+                PendingPrompt {
+                    decision_id: decision_id.clone(),
+                    ..pending
+                },
+            )
+        });
+
+        let sent_at = Instant::now();
+        let outcome = Self::poll_decision(&decision_id, device_token, backend_url, timeout);
+        Self::audit(
+            pre_tool_use,
+            &payload.session,
+            &outcome,
+            sent_at,
+            backend_url,
+        );
+
+        let decision = match outcome {
+            Ok(DecisionOutcome::Decided(remote)) => Ok(remote.decision),
+            Ok(DecisionOutcome::TimedOut) => Err(DecisionError::TimedOut),
+            Ok(DecisionOutcome::Cancelled) => Err(DecisionError::Cancelled),
+            Err(e) => Err(e),
+        };
+        match &decision {
+            Ok(decision) => Logger::info(&format!(
+                "Permission request {} answered: {:?}",
+                decision_id, decision
+            )),
+            Err(e) => Logger::warn(&format!("Permission request {}: {}", decision_id, e)),
+        }
+        Self::update_registry(|registry| {
+            registry.resolve(
+                session_id,
+                &decision_id,
+                decision.as_ref().ok().copied(),
+                SessionRegistry::now(),
+            )
+        });
+        decision
+    }
+
+    /// Poll the backend until the phone answers, the prompt is answered at the
+    /// terminal, the request expires or we time out
+    fn poll_decision(
+        decision_id: &str,
+        device_token: &str,
+        backend_url: &str,
+        timeout: Duration,
+    ) -> Result<DecisionOutcome, DecisionError> {
+        let poll_interval = Self::timing(POLL_INTERVAL_ENV, DECISION_POLL_INTERVAL);
         let start = Instant::now();
 
         loop {
-            if start.elapsed() > DECISION_TIMEOUT {
+            if start.elapsed() > timeout {
                 // Timeout - fall back to ask
-                eprintln!("Decision timed out");
-                std::process::exit(1);
+                return Ok(DecisionOutcome::TimedOut);
             }
 
-            thread::sleep(DECISION_POLL_INTERVAL);
+            thread::sleep(poll_interval);
 
             let status_response: DecisionStatusResponse = match ureq::get(&format!(
                 "{}/api/decision/{}/status",
@@ -263,120 +688,667 @@ impl Cmd {
             .header("Authorization", &format!("Bearer {}", device_token))
             .call()
             {
-                Ok(resp) => match resp.into_body().read_json() {
-                    Ok(r) => r,
-                    Err(e) => {
-                        eprintln!("{}", &format!("Failed to parse decision status: {}", e));
-                        std::process::exit(1);
-                    }
-                },
+                Ok(resp) => resp.into_body().read_json().map_err(|e| {
+                    DecisionError::Protocol(format!("Failed to parse decision status: {}", e))
+                })?,
+                Err(ureq::Error::StatusCode(401)) => return Err(DecisionError::TokenRejected),
                 Err(e) => {
-                    eprintln!("{}", &format!("Failed to poll decision status: {}", e));
-                    std::process::exit(1);
+                    return Err(DecisionError::Network(format!(
+                        "Failed to poll decision status: {}",
+                        e
+                    )))
                 }
             };
 
             match status_response.status.as_str() {
                 "decided" => {
-                    match status_response.decision.as_deref() {
-                        Some("allow") => {
-                            let output = HookOutput::allow();
-                            println!("{}", serde_json::to_string(&output)?);
-                            return Ok(());
-                        }
-                        Some("deny") => {
-                            let output = HookOutput::deny(None);
-                            println!("{}", serde_json::to_string(&output)?);
-                            return Ok(());
-                        }
-                        Some("dismiss") => {
-                            // Dismissed decision - exit silently
-                            std::process::exit(0);
-                        }
-                        _ => {
-                            // Unknown decision - exit silently
-                            eprintln!("Unknown decision");
-                            std::process::exit(1);
-                        }
-                    }
+                    let decision = match status_response.decision.as_deref() {
+                        Some("allow") => Decision::Allow,
+                        Some("deny") => Decision::Deny,
+                        Some("dismiss") => Decision::Dismiss,
+                        // Unknown decision - fall back to asking user normally
+                        _ => return Err(DecisionError::Protocol("Unknown decision".into())),
+                    };
+                    return Ok(DecisionOutcome::Decided(RemoteDecision {
+                        decision,
+                        source: status_response.source,
+                        device: status_response.device,
+                    }));
                 }
+                "cancelled" => return Ok(DecisionOutcome::Cancelled),
+                // The backend gave up on the prompt before we did
+                "expired" => return Ok(DecisionOutcome::TimedOut),
                 "pending" => {
                     // Continue polling
                     Logger::debug("Decision pending, continuing to poll");
                 }
                 _ => {
                     // Unknown status - fall back to asking user normally
-                    eprintln!("Unknown decision status, falling back to asking user normally");
-                    std::process::exit(1);
+                    return Err(DecisionError::Protocol(
+                        "Unknown decision status, falling back to asking user normally".into(),
+                    ));
                 }
             }
         }
     }
 
-    pub fn status() -> Result<(), Box<dyn std::error::Error>> {
-        let config = Config::load()?;
-
-        let device_paired = config.device_token.is_some();
-        let notifications_active = config.active;
-        let hooks_installed = Self::hooks_installed();
-
-        println!();
-        println!("  {} {}", "◆".cyan(), "Claude AFK Status".bold());
-        println!();
-
-        // Device pairing status
-        let (pair_icon, pair_status) = if device_paired {
-            ("✓".green(), "Paired".green())
-        } else {
-            ("✗".red(), "Not paired".red())
-        };
-        println!("  {} Device          {}", pair_icon, pair_status);
-
-        // Notifications status
-        let (notif_icon, notif_status) = if notifications_active {
-            ("✓".green(), "Active".green())
-        } else {
-            ("○".yellow(), "Inactive".yellow())
-        };
-        println!("  {} Notifications   {}", notif_icon, notif_status);
-
-        // Hooks status
-        let (hooks_icon, hooks_status) = if hooks_installed {
-            ("✓".green(), "Installed".green())
-        } else {
-            ("○".yellow(), "Not installed".yellow())
-        };
-        println!("  {} Hooks           {}", hooks_icon, hooks_status);
+    pub fn test(tool: TestTool) -> Result<(), Box<dyn std::error::Error>> {
+        let config = Self::load_config_here()?;
 
-        // Helpful hints
-        if !device_paired {
+        let Some(device_token) = config.device_token.as_deref() else {
             println!();
+            println!("  {} {}", "✗".red(), "No device paired".red());
             println!(
-                "  {} Run {} to set up notifications",
-                "Tip:".dimmed(),
+                "    {} Run {} first",
+                "→".dimmed(),
                 "claude-afk pair".cyan()
             );
-        } else if !hooks_installed {
             println!();
+            return Err("No device paired".into());
+        };
+        let backend_url = Self::get_backend_url(&config);
+
+        let input = Self::sample_permission_request(tool);
+        let session = SessionInfo::detect(&input.session_id, &input.cwd, &config.labels);
+        let (title, message) = Self::permission_text(&input, &session, &config);
+
+        println!();
+        println!("  {} {}", "◆".cyan(), "Claude AFK Test".bold());
+        println!();
+        match config.mode_at(Self::unix_now()) {
+            ActiveMode::On => {}
+            ActiveMode::Off => {
+                println!(
+                    "  {} Notifications are inactive - real hooks would fall back to the terminal",
+                    "○".yellow()
+                );
+                println!();
+            }
+            ActiveMode::Auto => {
+                println!(
+                    "  {} Auto mode - real hooks only notify while you're away",
+                    "○".dimmed()
+                );
+                println!();
+            }
+        }
+        if let Some(hold) = Self::describe_schedule(&config.schedule.check(Timestamp::now())) {
             println!(
-                "  {} Run {} to install Claude Code hooks",
-                "Tip:".dimmed(),
-                "claude-afk install-hook".cyan()
+                "  {} Schedule: {} - real hooks would fall back to the terminal",
+                "○".yellow(),
+                hold
             );
-        } else if !notifications_active {
             println!();
-            println!(
-                "  {} Run {} to enable notifications",
-                "Tip:".dimmed(),
-                "claude-afk activate".cyan()
-            );
+        }
+        println!("  {} Sending permission request:", "→".dimmed());
+        println!("    {}", title.bold());
+        for line in message.lines() {
+            println!("    {}", line.dimmed());
         }
         println!();
+        println!(
+            "  {} Waiting for a decision on your phone... {}",
+            "◌".yellow(),
+            "(press Ctrl+C to cancel)".dimmed()
+        );
 
-        Ok(())
-    }
+        let decision = match Self::request_decision(&input, &config, device_token, &backend_url) {
+            Ok(decision) => decision,
+            Err(e) => {
+                println!();
+                println!("  {} {}", "✗".red(), e.to_string().red());
+                println!(
+                    "    {} Claude Code would ask for permission at the terminal",
+                    "→".dimmed()
+                );
+                println!();
+                return Err(e.into());
+            }
+        };
+
+        println!();
+        println!(
+            "  {} Decision: {}",
+            "✓".green().bold(),
+            decision.as_str().bold()
+        );
+        match decision.hook_output() {
+            Some(output) => {
+                println!("  {} Hook output returned to Claude Code:", "→".dimmed());
+                println!("    {}", serde_json::to_string(&output)?);
+            }
+            None => {
+                println!(
+                    "  {} No hook output - Claude Code would ask at the terminal",
+                    "→".dimmed()
+                );
+            }
+        }
+        println!();
+        Ok(())
+    }
+
+    /// Build a realistic PermissionRequest hook input for `test`
+    fn sample_permission_request(tool: TestTool) -> PermissionRequestInput {
+        let (tool_name, tool_input) = match tool {
+            TestTool::Bash => (
+                "Bash",
+                serde_json::json!({
+                    "command": "echo \"Hello from claude-afk\"",
+                    "description": "Test command from claude-afk test"
+                }),
+            ),
+            TestTool::Write => (
+                "Write",
+                serde_json::json!({
+                    "file_path": "/tmp/claude-afk-test.txt",
+                    "content": "Hello from claude-afk"
+                }),
+            ),
+            TestTool::Edit => (
+                "Edit",
+                serde_json::json!({
+                    "file_path": "/tmp/claude-afk-test.txt",
+                    "old_string": "Hello",
+                    "new_string": "Goodbye"
+                }),
+            ),
+            TestTool::Read => (
+                "Read",
+                serde_json::json!({ "file_path": "/tmp/claude-afk-test.txt" }),
+            ),
+            TestTool::Unknown => (
+                "mcp__example__lookup",
+                serde_json::json!({ "query": "claude-afk test" }),
+            ),
+        };
+
+        PermissionRequestInput {
+            session_id: "claude-afk-test".to_string(),
+            transcript_path: String::new(),
+            cwd: std::env::current_dir()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            permission_mode: "default".to_string(),
+            hook_event_name: "PermissionRequest".to_string(),
+            tool_name: tool_name.to_string(),
+            tool_input,
+            tool_use_id: Some(format!("test-{}", nanoid!(16))),
+        }
+    }
+
+    /// Show the notification text the templates produce for a sample request,
+    /// an idle notification or a recorded hook payload, without sending it
+    pub fn preview(
+        tool: TestTool,
+        idle: bool,
+        file: Option<PathBuf>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = Config::load()?;
+
+        enum Sample {
+            Permission(PermissionRequestInput),
+            Idle(NotificationInput),
+        }
+        let sample = match &file {
+            Some(file) => {
+                let input = std::fs::read_to_string(file)
+                    .map_err(|e| format!("Could not read {}: {}", file.display(), e))?;
+                let generic: GenericHookInput = serde_json::from_str(&input)?;
+                match generic.hook_event_name.as_str() {
+                    "PermissionRequest" => Sample::Permission(serde_json::from_str(&input)?),
+                    "Notification" => Sample::Idle(serde_json::from_str(&input)?),
+                    other => return Err(format!("{} hooks don't send notifications", other).into()),
+                }
+            }
+            None if idle => Sample::Idle(Self::sample_idle_notification()),
+            None => Sample::Permission(Self::sample_permission_request(tool)),
+        };
+        let cwd = match &sample {
+            Sample::Permission(request) => &request.cwd,
+            Sample::Idle(notification) => &notification.cwd,
+        };
+        config.apply_project(Path::new(cwd))?;
+
+        let (heading, template, fields, (title, message)) = match &sample {
+            Sample::Permission(request) => {
+                let session = SessionInfo::detect(&request.session_id, cwd, &config.labels);
+                (
+                    format!("{} permission request", request.tool_name),
+                    config
+                        .templates
+                        .for_tool(&request.tool_name)
+                        .map(|(key, _)| format!("[templates.permission.\"{}\"]", key)),
+                    Templates::permission_fields(request, &session),
+                    Self::permission_text(request, &session, &config),
+                )
+            }
+            Sample::Idle(notification) => {
+                let session = SessionInfo::detect(&notification.session_id, cwd, &config.labels);
+                (
+                    "Idle notification".to_string(),
+                    config
+                        .templates
+                        .idle
+                        .as_ref()
+                        .map(|_| "[templates.idle]".to_string()),
+                    Templates::idle_fields(notification, &session),
+                    Self::idle_text(notification, &session, &config),
+                )
+            }
+        };
+
+        println!();
+        println!("  {} {}", "◆".cyan(), "Claude AFK Preview".bold());
+        println!();
+        println!(
+            "  {} {} {}",
+            "→".dimmed(),
+            heading,
+            format!(
+                "({})",
+                template.as_deref().unwrap_or("built-in text, no template")
+            )
+            .dimmed()
+        );
+        if let Some(profile) = &config.profile {
+            println!(
+                "  {} Profile {} (from {})",
+                "→".dimmed(),
+                profile.name,
+                profile.source
+            );
+        }
+        println!();
+        println!("    {}", title.bold());
+        for line in message.lines() {
+            println!("    {}", line.dimmed());
+        }
+        println!();
+        println!("  Fields:");
+        for (name, value) in &fields {
+            let value = value.lines().next().unwrap_or_default();
+            println!(
+                "    {:<14} {}",
+                format!("{{{}}}", name).cyan(),
+                value.dimmed()
+            );
+        }
+        println!();
+        println!(
+            "  {} Set templates under {} or {} in the config file",
+            "Tip:".dimmed(),
+            "[templates.permission.<Tool>]".cyan(),
+            "[templates.idle]".cyan()
+        );
+        println!();
+        Ok(())
+    }
+
+    /// Build an idle_prompt Notification hook input for `preview --idle`
+    fn sample_idle_notification() -> NotificationInput {
+        NotificationInput {
+            session_id: "claude-afk-test".to_string(),
+            transcript_path: String::new(),
+            cwd: std::env::current_dir()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            permission_mode: "default".to_string(),
+            hook_event_name: "Notification".to_string(),
+            message: "Claude is waiting for your input".to_string(),
+            notification_type: "idle_prompt".to_string(),
+        }
+    }
+
+    pub fn status(json: bool, check: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let config = Self::load_config_here()?;
+
+        let device_paired = config.device_token.is_some();
+        let token_expired = config
+            .token_expires_at
+            .is_some_and(|expires_at| expires_at <= Self::unix_now());
+        let threshold = config.idle_threshold();
+        let now = Timestamp::now();
+        let mode = config.mode_at(Self::unix_now());
+        let afk_until = config.afk_until.filter(|_| mode != config.active);
+        let presence = match mode {
+            ActiveMode::Auto => Presence::detect(),
+            ActiveMode::On | ActiveMode::Off => None,
+        };
+        let schedule_check = config.schedule.check(now);
+        let schedule_hold = Self::describe_schedule(&schedule_check);
+        let notifications_active =
+            Self::mode_allows(mode, presence.as_ref(), threshold) && schedule_hold.is_none();
+        // Claude Code merges hooks from every scope, so each event only needs
+        // to be covered by one of them
+        let hook_scopes = Self::hook_scopes();
+        let hook_events = Self::hook_events();
+        let hooks_installed = HookEvent::REQUIRED
+            .iter()
+            .all(|event| hook_events.contains(event));
+        let hooks_outdated = ClaudeSettings::read_all()
+            .iter()
+            .any(|(_, settings)| !ClaudeSettings::outdated_hook_commands(settings).is_empty());
+        let backend_url = Self::get_backend_url(&config);
+
+        let health =
+            check.then(|| Self::check_backend(&backend_url, config.device_token.as_deref()));
+        let check_failed = health.as_ref().is_some_and(|h| !h.is_healthy());
+
+        if json {
+            let report = StatusReport {
+                paired: device_paired,
+                token_expired,
+                token_expires_at: config.token_expires_at,
+                active: notifications_active,
+                mode: config.active,
+                afk_until,
+                profile: config.profile.as_ref().map(|p| p.name.clone()),
+                presence: presence.clone(),
+                schedule_hold: schedule_hold.clone(),
+                hooks_installed,
+                hook_scopes: hook_scopes.clone(),
+                hook_events: hook_events.clone(),
+                hooks_outdated,
+                backend_url,
+                check: health,
+            };
+            println!("{}", serde_json::to_string(&report)?);
+            if check_failed {
+                return Err("Backend health check failed".into());
+            }
+            return Ok(());
+        }
+
+        let token_rejected = health
+            .as_ref()
+            .is_some_and(|h| h.token_valid == Some(false));
+
+        println!();
+        println!("  {} {}", "◆".cyan(), "Claude AFK Status".bold());
+        println!();
+
+        // Device pairing status
+        let (pair_icon, pair_status) = if device_paired && (token_expired || token_rejected) {
+            ("✗".red(), "Pairing revoked or expired".red())
+        } else if device_paired {
+            ("✓".green(), "Paired".green())
+        } else {
+            ("✗".red(), "Not paired".red())
+        };
+        println!("  {} Device          {}", pair_icon, pair_status);
+
+        // Profile for this directory
+        if let Some(profile) = &config.profile {
+            println!(
+                "  {} Profile         {} {}",
+                "✓".green(),
+                profile.name.green(),
+                format!("(from {})", profile.source).dimmed()
+            );
+        }
+
+        // Notifications status
+        let until = afk_until
+            .and_then(|secs| Timestamp::from_second(secs as i64).ok())
+            .map(|at| config.schedule.local_time(at, now));
+        let (notif_icon, notif_status) = match (mode, &presence) {
+            (ActiveMode::On, _) => match &until {
+                Some(until) => (
+                    "✓".green(),
+                    format!(
+                        "Active until {}, then {}",
+                        until,
+                        Self::mode_name(config.active)
+                    )
+                    .green(),
+                ),
+                None => ("✓".green(), "Active".green()),
+            },
+            (ActiveMode::Off, _) => ("○".yellow(), "Inactive".yellow()),
+            (ActiveMode::Auto, Some(presence)) if notifications_active => (
+                "✓".green(),
+                format!(
+                    "Auto · away ({})",
+                    Self::describe_presence(presence, threshold)
+                )
+                .green(),
+            ),
+            (ActiveMode::Auto, Some(presence)) => (
+                "○".dimmed(),
+                format!(
+                    "Auto · at the computer ({})",
+                    Self::describe_presence(presence, threshold)
+                )
+                .normal(),
+            ),
+            (ActiveMode::Auto, None) => ("○".yellow(), "Auto · idle state unknown".yellow()),
+        };
+        println!("  {} Notifications   {}", notif_icon, notif_status);
+
+        // Schedule status
+        if !config.schedule.is_empty() {
+            let (schedule_icon, schedule_status) = match &schedule_check {
+                ScheduleCheck::Quiet(window) => {
+                    ("○".yellow(), format!("Quiet hours ({})", window).yellow())
+                }
+                ScheduleCheck::OutsideHours => ("○".yellow(), "Outside working hours".yellow()),
+                ScheduleCheck::Open if config.schedule.hours.is_empty() => {
+                    ("✓".green(), "Outside quiet hours".green())
+                }
+                ScheduleCheck::Open => ("✓".green(), "Within working hours".green()),
+            };
+            println!("  {} Schedule        {}", schedule_icon, schedule_status);
+        }
+
+        // Hooks status
+        let scope_names = hook_scopes
+            .iter()
+            .map(|scope| scope.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let event_names = hook_events
+            .iter()
+            .map(|event| event.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (hooks_icon, hooks_status) = if hooks_outdated {
+            (
+                "○".yellow(),
+                format!("Outdated ({}) · {}", scope_names, event_names).yellow(),
+            )
+        } else if hooks_installed {
+            (
+                "✓".green(),
+                format!("Installed ({}) · {}", scope_names, event_names).green(),
+            )
+        } else if !hook_events.is_empty() {
+            (
+                "○".yellow(),
+                format!("Partial ({}) · {} only", scope_names, event_names).yellow(),
+            )
+        } else {
+            ("○".yellow(), "Not installed".yellow())
+        };
+        println!("  {} Hooks           {}", hooks_icon, hooks_status);
+
+        if let Some(health) = &health {
+            Self::print_backend_health(health);
+        }
+
+        let subscription_lost = health
+            .as_ref()
+            .and_then(|h| h.subscription.as_deref())
+            .is_some_and(|s| s != "active");
+
+        // Helpful hints
+        if !device_paired || token_expired || token_rejected || subscription_lost {
+            println!();
+            println!(
+                "  {} Run {} to set up notifications",
+                "Tip:".dimmed(),
+                "claude-afk pair".cyan()
+            );
+        } else if !hooks_installed {
+            println!();
+            println!(
+                "  {} Run {} to install Claude Code hooks",
+                "Tip:".dimmed(),
+                "claude-afk install-hook".cyan()
+            );
+        } else if hooks_outdated {
+            println!();
+            println!(
+                "  {} Run {} to update hooks from an older install or a moved binary",
+                "Tip:".dimmed(),
+                "claude-afk install-hooks".cyan()
+            );
+        } else if mode == ActiveMode::Auto && presence.is_none() {
+            println!();
+            println!(
+                "  {} Auto mode can't read the idle or lock state here, so nothing is sent.",
+                "Tip:".dimmed()
+            );
+            println!(
+                "       Install {} (X11) or run {} when you leave",
+                "xprintidle".cyan(),
+                "claude-afk afk".cyan()
+            );
+        } else if mode == ActiveMode::Off {
+            println!();
+            println!(
+                "  {} Run {} to enable notifications",
+                "Tip:".dimmed(),
+                "claude-afk activate".cyan()
+            );
+        }
+        println!();
+
+        if check_failed {
+            return Err("Backend health check failed".into());
+        }
+        Ok(())
+    }
+
+    fn print_backend_health(health: &BackendHealth) {
+        let (backend_icon, backend_status) = match (health.reachable, health.latency_ms) {
+            (true, Some(ms)) => ("✓".green(), format!("Reachable ({} ms)", ms).green()),
+            (true, None) => ("✓".green(), "Reachable".green()),
+            (false, _) => (
+                "✗".red(),
+                health
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| "Unreachable".to_string())
+                    .red(),
+            ),
+        };
+        println!("  {} Backend         {}", backend_icon, backend_status);
+
+        if !health.reachable {
+            return;
+        }
+
+        let (token_icon, token_status) = match health.token_valid {
+            Some(true) => ("✓".green(), "Valid".green()),
+            Some(false) => ("✗".red(), "Rejected (revoked or expired)".red()),
+            None => (
+                "✗".red(),
+                health
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| "Unknown".to_string())
+                    .red(),
+            ),
+        };
+        println!("  {} Token           {}", token_icon, token_status);
+
+        if let Some(subscription) = &health.subscription {
+            let (push_icon, push_status) = match subscription.as_str() {
+                "active" => ("✓".green(), "Subscribed".green()),
+                "expired" => ("✗".red(), "Subscription expired on the phone".red()),
+                "missing" => ("✗".red(), "No push subscription".red()),
+                other => ("✗".red(), other.to_string().red()),
+            };
+            println!("  {} Push            {}", push_icon, push_status);
+        }
+    }
+
+    /// Ask the backend whether it still accepts our token and can reach the phone
+    fn check_backend(backend_url: &str, device_token: Option<&str>) -> BackendHealth {
+        let Some(device_token) = device_token else {
+            // Nothing to authenticate with, but reachability is still worth reporting
+            let start = Instant::now();
+            let result = ureq::get(&format!("{}/api/vapid-public-key", backend_url))
+                .config()
+                .timeout_global(Some(HEALTH_CHECK_TIMEOUT))
+                .build()
+                .call();
+            return match result {
+                Ok(_) | Err(ureq::Error::StatusCode(_)) => BackendHealth {
+                    reachable: true,
+                    latency_ms: Some(start.elapsed().as_millis() as u64),
+                    error: Some("No device paired".to_string()),
+                    ..Default::default()
+                },
+                Err(e) => BackendHealth {
+                    error: Some(format!("Unreachable: {}", e)),
+                    ..Default::default()
+                },
+            };
+        };
+
+        let start = Instant::now();
+        let result = ureq::get(&format!("{}/api/device/status", backend_url))
+            .header("Authorization", &format!("Bearer {}", device_token))
+            .config()
+            .timeout_global(Some(HEALTH_CHECK_TIMEOUT))
+            .build()
+            .call();
+        let latency_ms = Some(start.elapsed().as_millis() as u64);
+
+        match result {
+            Ok(resp) => match resp.into_body().read_json::<DeviceStatusResponse>() {
+                Ok(status) => BackendHealth {
+                    reachable: true,
+                    token_valid: Some(status.valid),
+                    subscription: Some(status.subscription),
+                    latency_ms,
+                    error: status
+                        .rotated
+                        .then(|| "Token was replaced by a newer one and stops working soon; run `claude-afk pair`".to_string()),
+                },
+                Err(e) => BackendHealth {
+                    reachable: true,
+                    latency_ms,
+                    error: Some(format!("Invalid status response: {}", e)),
+                    ..Default::default()
+                },
+            },
+            Err(ureq::Error::StatusCode(401)) => BackendHealth {
+                reachable: true,
+                token_valid: Some(false),
+                latency_ms,
+                error: Some(TOKEN_REJECTED_MESSAGE.to_string()),
+                ..Default::default()
+            },
+            Err(ureq::Error::StatusCode(code)) => BackendHealth {
+                reachable: true,
+                latency_ms,
+                error: Some(format!("Backend returned HTTP {}", code)),
+                ..Default::default()
+            },
+            Err(e) => BackendHealth {
+                error: Some(format!("Unreachable: {}", e)),
+                ..Default::default()
+            },
+        }
+    }
 
-    pub fn activate() -> Result<(), Box<dyn std::error::Error>> {
+    pub fn activate(
+        duration: Option<Duration>,
+        until: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let mut config = Config::load()?;
 
         if config.device_token.is_none() {
@@ -391,7 +1363,19 @@ impl Cmd {
             return Err("No device paired".into());
         }
 
-        if config.active {
+        let now = Timestamp::now();
+        let afk_until = match (duration, until) {
+            (Some(duration), _) => Some(
+                jiff::SignedDuration::try_from(duration)
+                    .ok()
+                    .and_then(|duration| now.checked_add(duration).ok())
+                    .ok_or("--for is too long")?,
+            ),
+            (None, Some(until)) => Some(config.schedule.parse_until(until, now)?),
+            (None, None) => None,
+        };
+
+        if afk_until.is_none() && config.active == ActiveMode::On && config.afk_until.is_none() {
             println!();
             println!(
                 "  {} Notifications are already {}",
@@ -402,19 +1386,56 @@ impl Cmd {
             return Ok(());
         }
 
-        config.active = true;
+        match afk_until {
+            Some(afk_until) => {
+                // A timed afk returns to auto, or otherwise to off
+                if config.active == ActiveMode::On {
+                    config.active = ActiveMode::Off;
+                }
+                config.afk_until = Some(afk_until.as_second().max(0) as u64);
+            }
+            None => {
+                config.active = ActiveMode::On;
+                config.afk_until = None;
+            }
+        }
         Config::save(&config)?;
 
         println!();
-        println!(
-            "  {} Notifications {}",
-            "✓".green().bold(),
-            "activated".green().bold()
-        );
-        println!(
-            "    {} You'll receive push notifications when Claude needs input",
-            "→".dimmed()
-        );
+        match afk_until {
+            Some(afk_until) => {
+                println!(
+                    "  {} Notifications {} until {}",
+                    "✓".green().bold(),
+                    "activated".green().bold(),
+                    config.schedule.local_time(afk_until, now).bold()
+                );
+                println!(
+                    "    {} Back to {} afterwards, or run {} to stop now",
+                    "→".dimmed(),
+                    Self::mode_name(config.active),
+                    "claude-afk back".cyan()
+                );
+            }
+            None => {
+                println!(
+                    "  {} Notifications {}",
+                    "✓".green().bold(),
+                    "activated".green().bold()
+                );
+                println!(
+                    "    {} You'll receive push notifications when Claude needs input",
+                    "→".dimmed()
+                );
+            }
+        }
+        if let Some(hold) = Self::describe_schedule(&config.schedule.check(now)) {
+            println!(
+                "    {} Held back for now by the schedule: {}",
+                "○".yellow(),
+                hold
+            );
+        }
         println!();
         Ok(())
     }
@@ -422,7 +1443,7 @@ impl Cmd {
     pub fn deactivate() -> Result<(), Box<dyn std::error::Error>> {
         let mut config = Config::load()?;
 
-        if !config.active {
+        if config.mode_at(Self::unix_now()) == ActiveMode::Off {
             println!();
             println!(
                 "  {} Notifications are already {}",
@@ -433,7 +1454,8 @@ impl Cmd {
             return Ok(());
         }
 
-        config.active = false;
+        config.active = ActiveMode::Off;
+        config.afk_until = None;
         Config::save(&config)?;
 
         println!();
@@ -451,6 +1473,70 @@ impl Cmd {
         Ok(())
     }
 
+    pub fn auto(idle_after: Option<Duration>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = Config::load()?;
+
+        if config.device_token.is_none() {
+            println!();
+            println!("  {} {}", "✗".red(), "No device paired".red());
+            println!(
+                "    {} Run {} first",
+                "→".dimmed(),
+                "claude-afk pair".cyan()
+            );
+            println!();
+            return Err("No device paired".into());
+        }
+
+        config.active = ActiveMode::Auto;
+        config.afk_until = None;
+        if let Some(idle_after) = idle_after {
+            config.idle_threshold = Some(idle_after.as_secs());
+        }
+        Config::save(&config)?;
+
+        let threshold = config.idle_threshold();
+        println!();
+        println!(
+            "  {} Notifications {}",
+            "✓".green().bold(),
+            "on while you're away".green().bold()
+        );
+        println!(
+            "    {} Sent while the screen is locked or after {} without input",
+            "→".dimmed(),
+            Self::format_age(threshold.as_secs())
+        );
+        match Presence::detect() {
+            Some(presence) => println!(
+                "    {} Right now: {} ({})",
+                "→".dimmed(),
+                if presence.is_away(threshold) {
+                    "away"
+                } else {
+                    "at the computer"
+                },
+                Self::describe_presence(&presence, threshold)
+            ),
+            None => {
+                println!();
+                println!(
+                    "  {} The idle and lock state can't be read here, so nothing will be sent.",
+                    "○".yellow()
+                );
+                println!(
+                    "    {} Install {} (X11) or use {} and {} instead",
+                    "→".dimmed(),
+                    "xprintidle".cyan(),
+                    "claude-afk afk".cyan(),
+                    "back".cyan()
+                );
+            }
+        }
+        println!();
+        Ok(())
+    }
+
     pub fn clear() -> Result<(), Box<dyn std::error::Error>> {
         let mut config = Config::load()?;
 
@@ -466,7 +1552,10 @@ impl Cmd {
         }
 
         config.device_token = None;
-        config.active = false;
+        config.token_expires_at = None;
+        config.token_rotate_after = None;
+        config.active = ActiveMode::Off;
+        config.afk_until = None;
         Config::save(&config)?;
 
         println!();
@@ -480,119 +1569,186 @@ impl Cmd {
         Ok(())
     }
 
-    pub fn install_hooks() -> Result<(), Box<dyn std::error::Error>> {
-        println!();
-        println!("  {} {}", "◆".cyan(), "Installing Claude Code Hooks".bold());
-        println!();
-
-        // Get the path to the current executable
-        let exe_path = std::env::current_exe()?;
-        let exe_path_str = exe_path.to_string_lossy().to_string();
-
-        // Find the Claude Code settings file
-        let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))?;
-        let settings_path = PathBuf::from(&home).join(".claude").join("settings.json");
+    pub fn rotate_token() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = Self::load_config_here()?;
 
-        // Ensure .claude directory exists
-        if let Some(parent) = settings_path.parent() {
-            fs::create_dir_all(parent)?;
+        if config.device_token.is_none() {
+            println!();
+            println!("  {} {}", "✗".red(), "No device paired".red());
+            println!(
+                "    {} Run {} first",
+                "→".dimmed(),
+                "claude-afk pair".cyan()
+            );
+            println!();
+            return Err("No device paired".into());
         }
 
-        // Read existing settings or create empty object
-        let mut settings: serde_json::Value = if settings_path.exists() {
-            let content = fs::read_to_string(&settings_path)?;
-            serde_json::from_str(&content).unwrap_or_else(|_| serde_json::json!({}))
-        } else {
-            serde_json::json!({})
-        };
+        let backend_url = Self::get_backend_url(&config);
 
-        // Create the PermissionRequest hook structure (wildcard matcher)
-        let permission_hook_entry = serde_json::json!({
-            "matcher": "*",
-            "hooks": [
-                {
-                    "type": "command",
-                    "command": exe_path_str
-                }
-            ]
-        });
-
-        // Create the Notification hook structure (idle_prompt only)
-        let notification_hook_entry = serde_json::json!({
-            "matcher": "idle_prompt",
-            "hooks": [
-                {
-                    "type": "command",
-                    "command": exe_path_str
-                }
-            ]
-        });
-
-        // Get or create the hooks object
-        let hooks = settings
-            .as_object_mut()
-            .ok_or("Settings is not an object")?
-            .entry("hooks")
-            .or_insert_with(|| serde_json::json!({}));
-
-        let hooks_obj = hooks.as_object_mut().ok_or("Hooks is not an object")?;
-
-        // Helper closure to check if a hook entry contains claude-afk
-        let contains_claude_afk = |hook: &serde_json::Value| -> bool {
-            if let Some(inner_hooks) = hook.get("hooks").and_then(|h| h.as_array()) {
-                inner_hooks.iter().any(|h| {
-                    h.get("command")
-                        .and_then(|c| c.as_str())
-                        .map(|c| c.contains("claude-afk"))
-                        .unwrap_or(false)
-                })
+        if let Err(e) = Self::rotate_device_token(&mut config, &backend_url) {
+            let unauthorized = matches!(
+                e.downcast_ref::<ureq::Error>(),
+                Some(ureq::Error::StatusCode(401))
+            );
+            println!();
+            if unauthorized {
+                println!(
+                    "  {} {}",
+                    "✗".red(),
+                    "Device pairing revoked or expired".red()
+                );
+                println!(
+                    "    {} Run {} to pair again",
+                    "→".dimmed(),
+                    "claude-afk pair".cyan()
+                );
             } else {
-                false
+                println!("  {} {}", "✗".red(), "Token rotation failed".red());
             }
+            println!();
+            return Err(e);
+        }
+
+        println!();
+        println!(
+            "  {} Device token {}",
+            "✓".green().bold(),
+            "rotated".green().bold()
+        );
+        if let Some(expires_at) = config.token_expires_at {
+            let days = expires_at.saturating_sub(Self::unix_now()) / 86400;
+            println!("    {} New token expires in {} days", "→".dimmed(), days);
+        }
+        println!();
+        Ok(())
+    }
+
+    pub fn install_hooks(
+        scope: HookScope,
+        events: Vec<HookEvent>,
+        tools: String,
+        dry_run: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let events = if events.is_empty() {
+            HookEvent::ALL.to_vec()
+        } else {
+            events
         };
 
-        // Install PermissionRequest hook
-        let permission_request = hooks_obj
-            .entry("PermissionRequest")
-            .or_insert_with(|| serde_json::json!([]));
+        println!();
+        if dry_run {
+            println!(
+                "  {} {} {}",
+                "◆".cyan(),
+                "Installing Claude Code Hooks".bold(),
+                "(dry run)".dimmed()
+            );
+        } else {
+            println!("  {} {}", "◆".cyan(), "Installing Claude Code Hooks".bold());
+        }
+        println!();
 
-        let permission_array = permission_request
-            .as_array_mut()
-            .ok_or("PermissionRequest is not an array")?;
+        // Get the path to the current executable
+        let exe_path = std::env::current_exe()?;
+        let exe_path_str = exe_path.to_string_lossy().to_string();
+        let command = ClaudeSettings::hook_command(&exe_path);
 
-        // Remove any existing claude-afk hooks and add the new one
-        permission_array.retain(|hook| !contains_claude_afk(hook));
-        permission_array.push(permission_hook_entry);
+        // Find the Claude Code settings file
+        let settings_path = scope.path()?;
 
-        // Install Notification hook
-        let notification = hooks_obj
-            .entry("Notification")
-            .or_insert_with(|| serde_json::json!([]));
+        // Read existing settings or create empty object
+        let original = ClaudeSettings::read_source(&settings_path)?.unwrap_or_default();
+        let mut settings = ClaudeSettings::parse(&settings_path, &original)?;
+
+        for &event in &events {
+            let matcher = match event {
+                HookEvent::PermissionRequest => tools.as_str(),
+                HookEvent::Notification => "idle_prompt",
+                // Only tools that can prompt have anything to retract
+                HookEvent::PostToolUse | HookEvent::PostToolUseFailure => tools.as_str(),
+                // Claude Code ignores matchers for prompt submissions
+                HookEvent::UserPromptSubmit => "",
+            };
+            ClaudeSettings::install_claude_afk_hook(&mut settings, event, matcher, &command)?;
+        }
 
-        let notification_array = notification
-            .as_array_mut()
-            .ok_or("Notification is not an array")?;
+        // Bring entries for the other events up to date too
+        let migrated = ClaudeSettings::migrate_hooks(&mut settings, &command);
 
-        // Remove any existing claude-afk hooks and add the new one
-        notification_array.retain(|hook| !contains_claude_afk(hook));
-        notification_array.push(notification_hook_entry);
+        if dry_run {
+            let updated = ClaudeSettings::render(&settings)?;
+            let diff = ClaudeSettings::diff(scope.display_path(), &original, &updated);
+            if diff.is_empty() {
+                println!(
+                    "  {} No changes to {}",
+                    "○".dimmed(),
+                    scope.display_path().cyan()
+                );
+            } else {
+                for line in diff.lines() {
+                    if line.starts_with("+++") || line.starts_with("---") {
+                        println!("  {}", line.bold());
+                    } else if line.starts_with('+') {
+                        println!("  {}", line.green());
+                    } else if line.starts_with('-') {
+                        println!("  {}", line.red());
+                    } else if line.starts_with("@@") {
+                        println!("  {}", line.cyan());
+                    } else {
+                        println!("  {}", line);
+                    }
+                }
+            }
+            println!();
+            println!(
+                "  {} Run without {} to write these changes",
+                "Tip:".dimmed(),
+                "--dry-run".cyan()
+            );
+            println!();
+            return Ok(());
+        }
 
         // Write the settings back
-        let formatted = serde_json::to_string_pretty(&settings)?;
-        fs::write(&settings_path, formatted)?;
+        let backup_path = ClaudeSettings::save(&settings_path, &settings)?;
 
         println!(
             "  {} Hooks installed to {}",
             "✓".green().bold(),
-            "~/.claude/settings.json".cyan()
+            scope.display_path().cyan()
         );
+        Self::print_settings_backup(backup_path.as_deref(), &original);
         println!();
         println!("  {} Binary path:", "→".dimmed());
         println!("    {}", exe_path_str.dimmed());
         println!();
         println!("  {} Installed hooks:", "→".dimmed());
-        println!("    • PermissionRequest (all tools)");
-        println!("    • Notification (idle_prompt)");
+        for event in &events {
+            match event {
+                HookEvent::PermissionRequest if tools == "*" => {
+                    println!("    • PermissionRequest (all tools)")
+                }
+                HookEvent::PermissionRequest => println!("    • PermissionRequest ({})", tools),
+                HookEvent::Notification => println!("    • Notification (idle_prompt)"),
+                HookEvent::PostToolUse => {
+                    println!("    • PostToolUse (retracts prompts answered at the terminal)")
+                }
+                HookEvent::PostToolUseFailure => {
+                    println!("    • PostToolUseFailure (retracts prompts for failed tool calls)")
+                }
+                HookEvent::UserPromptSubmit => {
+                    println!("    • UserPromptSubmit (retracts prompts denied at the terminal)")
+                }
+            }
+        }
+        if !migrated.is_empty() {
+            println!();
+            println!("  {} Updated existing hooks:", "→".dimmed());
+            for change in &migrated {
+                println!("    • {}", change);
+            }
+        }
         println!();
         println!(
             "  {} Claude Code will now send push notifications",
@@ -601,103 +1757,949 @@ impl Cmd {
         println!("    when it needs your input or is waiting.");
         println!();
 
-        println!(
-            "  {} Run {} to enable notifications",
-            "Tip:".dimmed(),
-            "claude-afk activate".cyan()
+        println!(
+            "  {} Run {} to enable notifications",
+            "Tip:".dimmed(),
+            "claude-afk activate".cyan()
+        );
+        println!();
+
+        Ok(())
+    }
+
+    pub fn uninstall_hooks(scope: HookScope) -> Result<(), Box<dyn std::error::Error>> {
+        println!();
+        println!("  {} {}", "◆".cyan(), "Removing Claude Code Hooks".bold());
+        println!();
+
+        let settings_path = scope.path()?;
+
+        let Some(original) = ClaudeSettings::read_source(&settings_path)? else {
+            println!(
+                "  {} No settings file at {}",
+                "○".dimmed(),
+                scope.display_path().cyan()
+            );
+            println!();
+            return Ok(());
+        };
+        let mut settings = ClaudeSettings::parse(&settings_path, &original)?;
+
+        let changes = ClaudeSettings::remove_claude_afk_hooks(&mut settings);
+        if changes.is_empty() {
+            println!(
+                "  {} No claude-afk hooks found in {}",
+                "○".dimmed(),
+                scope.display_path().cyan()
+            );
+            println!();
+            return Ok(());
+        }
+
+        let backup_path = ClaudeSettings::save(&settings_path, &settings)?;
+
+        println!(
+            "  {} Hooks removed from {}",
+            "✓".green().bold(),
+            scope.display_path().cyan()
+        );
+        Self::print_settings_backup(backup_path.as_deref(), &original);
+        println!();
+        println!("  {} Changes:", "→".dimmed());
+        for change in &changes {
+            println!("    • {}", change);
+        }
+        println!();
+
+        Ok(())
+    }
+
+    fn print_settings_backup(backup_path: Option<&std::path::Path>, original: &str) {
+        let Some(backup_path) = backup_path else {
+            return;
+        };
+        println!(
+            "  {} Previous version saved to {}",
+            "→".dimmed(),
+            backup_path.display().to_string().dimmed()
+        );
+        if ClaudeSettings::has_jsonc_syntax(original) {
+            println!(
+                "  {} Comments and trailing commas were not kept; they remain in the backup",
+                "!".yellow()
+            );
+        }
+    }
+
+    pub fn record(dir: Option<PathBuf>, off: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = Config::load()?;
+
+        println!();
+        if off {
+            config.record_dir = None;
+            Config::save(&config)?;
+            println!("  {} Hook recording {}", "○".yellow(), "stopped".yellow());
+            println!();
+            return Ok(());
+        }
+
+        let dir = match dir {
+            Some(dir) => std::path::absolute(dir)?,
+            None => Fixtures::default_dir()?,
+        };
+        config.record_dir = Some(dir.clone());
+        Config::save(&config)?;
+
+        println!("  {} Hook recording {}", "✓".green(), "started".green());
+        println!();
+        println!("  {} Fixtures are saved (redacted) to:", "→".dimmed());
+        println!("    {}", dir.display().to_string().dimmed());
+        println!();
+        println!(
+            "  {} Run {} to inspect one, and {} when you're done",
+            "Tip:".dimmed(),
+            "claude-afk replay <file>".cyan(),
+            "claude-afk record --off".cyan()
+        );
+        println!();
+        Ok(())
+    }
+
+    pub fn sessions(all: bool, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let registry = SessionRegistry::load(&SessionRegistry::path()?);
+        let now = SessionRegistry::now();
+        let sessions: Vec<(&String, &SessionRecord)> = if all {
+            let mut sessions: Vec<_> = registry.sessions.iter().collect();
+            sessions.sort_by_key(|(_, record)| std::cmp::Reverse(record.last_seen));
+            sessions
+        } else {
+            registry.active(now)
+        };
+
+        if json {
+            let entries = sessions
+                .iter()
+                .map(|(id, record)| {
+                    let mut entry = serde_json::Map::new();
+                    entry.insert("id".to_string(), serde_json::json!(id));
+                    entry.insert(
+                        "blocked".to_string(),
+                        serde_json::json!(record.is_blocked()),
+                    );
+                    if let serde_json::Value::Object(fields) = serde_json::to_value(record)? {
+                        entry.extend(fields);
+                    }
+                    Ok(serde_json::Value::Object(entry))
+                })
+                .collect::<Result<Vec<_>, serde_json::Error>>()?;
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+            return Ok(());
+        }
+
+        println!();
+        println!("  {} {}", "◆".cyan(), "Claude Sessions".bold());
+        println!();
+
+        if sessions.is_empty() {
+            println!("  {} No active sessions", "○".yellow());
+            println!();
+            println!(
+                "  {} Sessions appear once Claude Code runs a claude-afk hook. Use {} to include older ones",
+                "Tip:".dimmed(),
+                "--all".cyan()
+            );
+            println!();
+            return Ok(());
+        }
+
+        for (_, record) in &sessions {
+            if record.is_blocked() {
+                println!(
+                    "  {} {}  {}",
+                    "◌".yellow(),
+                    record.label.bold(),
+                    "waiting on you".yellow()
+                );
+            } else {
+                println!("  {} {}", "✓".green(), record.label.bold());
+            }
+
+            let mut counts = vec![format!("{} allowed", record.allowed)];
+            for (count, name) in [
+                (record.denied, "denied"),
+                (record.dismissed, "dismissed"),
+                (record.unanswered, "unanswered"),
+                (record.terminal, "at the terminal"),
+            ] {
+                if count > 0 {
+                    counts.push(format!("{} {}", count, name));
+                }
+            }
+            println!(
+                "    {}",
+                format!(
+                    "{} · active {} ago · {}",
+                    record.cwd,
+                    Self::format_age(now.saturating_sub(record.last_seen)),
+                    counts.join(", ")
+                )
+                .dimmed()
+            );
+
+            for prompt in &record.pending {
+                let summary = prompt.message.lines().next().unwrap_or_default();
+                println!(
+                    "    {} {} {} {}",
+                    "→".dimmed(),
+                    prompt.title,
+                    summary,
+                    format!(
+                        "(waiting {})",
+                        Self::format_age(now.saturating_sub(prompt.since))
+                    )
+                    .dimmed()
+                );
+            }
+            println!();
+        }
+        Ok(())
+    }
+
+    pub fn history(
+        since: Option<String>,
+        tool: Option<String>,
+        decision: Option<AuditDecision>,
+        session: Option<String>,
+        json: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let since = since
+            .map(|since| AuditLog::parse_since(&since, jiff::Timestamp::now()))
+            .transpose()?;
+        let filter = AuditFilter {
+            since,
+            tool,
+            decision,
+            session,
+        };
+        let entries = AuditLog::read(&AuditLog::path()?, &filter)?;
+
+        if json {
+            for entry in &entries {
+                println!("{}", serde_json::to_string(entry)?);
+            }
+            return Ok(());
+        }
+
+        println!();
+        println!("  {} {}", "◆".cyan(), "Decision History".bold());
+        println!();
+
+        if entries.is_empty() {
+            println!("  {} No matching decisions", "○".yellow());
+            println!();
+            return Ok(());
+        }
+
+        let tz = jiff::tz::TimeZone::system();
+        for entry in &entries {
+            let icon = match entry.decision {
+                AuditDecision::Allow => "✓".green(),
+                AuditDecision::Deny | AuditDecision::Error => "✗".red(),
+                AuditDecision::Dismiss | AuditDecision::Timeout => "○".yellow(),
+                AuditDecision::Terminal => "○".dimmed(),
+            };
+            let when = entry.time.to_zoned(tz.clone()).strftime("%Y-%m-%d %H:%M");
+            println!(
+                "  {}  {} {:<8} {:<6} {}",
+                when.to_string().dimmed(),
+                icon,
+                entry.decision.as_str(),
+                entry.tool,
+                entry.summary()
+            );
+
+            let mut details: Vec<String> = entry.session.iter().cloned().collect();
+            details.extend(entry.device.iter().cloned());
+            details.push(format!("{:.1}s", entry.latency_ms as f64 / 1000.0));
+            details.extend(entry.error.iter().cloned());
+            println!("  {:<16}  {}", "", details.join(" · ").dimmed());
+        }
+        println!();
+        Ok(())
+    }
+
+    pub fn replay(file: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Could not read {}: {}", file.display(), e))?;
+
+        println!();
+        println!(
+            "  {} {} {}",
+            "◆".cyan(),
+            "Replaying".bold(),
+            file.display().to_string().dimmed()
+        );
+        println!();
+        for line in Fixtures::replay(&input).lines() {
+            println!("  {}", line);
+        }
+        println!();
+        Ok(())
+    }
+
+    pub fn doctor(skip_push: bool) -> Result<(), Box<dyn std::error::Error>> {
+        println!();
+        println!("  {} {}", "◆".cyan(), "Claude AFK Doctor".bold());
+        println!();
+
+        let mut failures = 0;
+        let mut report = |outcome: DoctorOutcome| {
+            if matches!(outcome, DoctorOutcome::Fail { .. }) {
+                failures += 1;
+            }
+            outcome.print();
+        };
+
+        // 1. Config file parses, and the profile for this directory exists
+        let config = match Config::load() {
+            Ok(mut config) => {
+                report(DoctorOutcome::pass("Config", "Parsed"));
+                let applied = std::env::current_dir()
+                    .map_err(|e| e.to_string())
+                    .and_then(|cwd| config.apply_project(&cwd));
+                match (applied, &config.profile) {
+                    (Err(e), _) => {
+                        report(DoctorOutcome::fail(
+                            "Profile",
+                            e,
+                            "Define the profile under [profiles] or fix .claude-afk.toml",
+                        ));
+                        None
+                    }
+                    (Ok(()), Some(profile)) => {
+                        report(DoctorOutcome::pass(
+                            "Profile",
+                            format!("{} (from {})", profile.name, profile.source),
+                        ));
+                        Some(config)
+                    }
+                    (Ok(()), None) => Some(config),
+                }
+            }
+            Err(e) => {
+                let path = confy::get_configuration_file_path(APP_NAME, None)
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|_| "the config file".to_string());
+                report(DoctorOutcome::fail(
+                    "Config",
+                    format!("Could not be parsed: {}", e),
+                    format!("Fix or delete {} and run claude-afk pair", path),
+                ));
+                None
+            }
+        };
+
+        // 2. Hook entries point to an existing executable of the same version
+        for (scope, path) in ClaudeSettings::scope_paths() {
+            if let Err(e) = ClaudeSettings::read(&path) {
+                report(DoctorOutcome::fail(
+                    "Settings",
+                    e.to_string(),
+                    format!("Fix {} so Claude Code can load it", scope.display_path()),
+                ));
+            }
+        }
+
+        let hook_commands: Vec<(String, String)> = ClaudeSettings::read_all()
+            .iter()
+            .flat_map(|(_, settings)| ClaudeSettings::claude_afk_hook_commands(settings))
+            .collect();
+
+        if hook_commands.is_empty() {
+            report(DoctorOutcome::fail(
+                "Hooks",
+                "No claude-afk hooks in any Claude Code settings file",
+                "Run claude-afk install-hooks",
+            ));
+        } else {
+            for (event, command) in &hook_commands {
+                report(Self::diagnose_hook_command(event, command));
+            }
+        }
+
+        // 3. Notifications are switched on
+        let Some(config) = config else {
+            return Err("Doctor found problems".into());
+        };
+        match config.mode_at(Self::unix_now()) {
+            ActiveMode::On => report(DoctorOutcome::pass("Notifications", "Active")),
+            ActiveMode::Off => report(DoctorOutcome::warn(
+                "Notifications",
+                "Inactive",
+                "Run claude-afk afk to enable notifications",
+            )),
+            ActiveMode::Auto => match Presence::detect() {
+                Some(presence) => report(DoctorOutcome::pass(
+                    "Notifications",
+                    format!(
+                        "Auto, idle state from {} ({})",
+                        presence.source,
+                        Self::describe_presence(&presence, config.idle_threshold())
+                    ),
+                )),
+                None => report(DoctorOutcome::warn(
+                    "Notifications",
+                    "Auto, but the idle and lock state can't be read",
+                    "Install xprintidle (X11) or use claude-afk afk and back",
+                )),
+            },
+        }
+        if !config.schedule.is_empty() {
+            match Self::describe_schedule(&config.schedule.check(Timestamp::now())) {
+                Some(hold) => report(DoctorOutcome::warn(
+                    "Schedule",
+                    format!("Holding notifications back: {}", hold),
+                    "They resume when the window ends; see [schedule] in the config file",
+                )),
+                None => report(DoctorOutcome::pass("Schedule", "Notifications allowed now")),
+            }
+        }
+
+        // 4. Backend reachable and token accepted
+        let backend_url = Self::get_backend_url(&config);
+        let health = Self::check_backend(&backend_url, config.device_token.as_deref());
+
+        if health.reachable {
+            report(DoctorOutcome::pass(
+                "Backend",
+                format!(
+                    "{} ({} ms)",
+                    backend_url,
+                    health.latency_ms.unwrap_or_default()
+                ),
+            ));
+        } else {
+            report(DoctorOutcome::fail(
+                "Backend",
+                health.error.clone().unwrap_or_default(),
+                "Check your network connection and CLAUDE_AFK_API_URL",
+            ));
+        }
+
+        match (config.device_token.as_deref(), health.token_valid) {
+            (None, _) => report(DoctorOutcome::fail(
+                "Token",
+                "No device paired",
+                "Run claude-afk pair",
+            )),
+            (Some(_), Some(true)) => report(DoctorOutcome::pass("Token", "Accepted")),
+            (Some(_), Some(false)) => report(DoctorOutcome::fail(
+                "Token",
+                "Rejected (revoked or expired)",
+                "Run claude-afk pair to pair again",
+            )),
+            (Some(_), None) if health.reachable => report(DoctorOutcome::fail(
+                "Token",
+                health.error.clone().unwrap_or_default(),
+                "Check that CLAUDE_AFK_API_URL points at a claude-afk backend",
+            )),
+            (Some(_), None) => {}
+        }
+
+        match health.subscription.as_deref() {
+            Some("active") => report(DoctorOutcome::pass("Push", "Subscribed")),
+            Some(other) => report(DoctorOutcome::fail(
+                "Push",
+                format!("Subscription {}", other),
+                "Re-enable notifications for the site on your phone and run claude-afk pair",
+            )),
+            None => {}
+        }
+
+        // 5. Test push end-to-end
+        if health.is_healthy() && !skip_push {
+            let device_token = config.device_token.as_deref().unwrap_or_default();
+            let payload = SimpleNotifyPayload {
+                title: "Claude AFK test".to_string(),
+                message: "If you can read this, notifications work 🎉".to_string(),
+                session: None,
+            };
+            match ureq::post(&format!("{}/api/notify/simple", backend_url))
+                .header("Authorization", &format!("Bearer {}", device_token))
+                .send_json(&payload)
+            {
+                Ok(_) => report(DoctorOutcome::pass("Test push", "Sent - check your phone")),
+                Err(e) => report(DoctorOutcome::fail(
+                    "Test push",
+                    format!("Failed: {}", e),
+                    "Run claude-afk pair to refresh the push subscription",
+                )),
+            }
+        }
+
+        println!();
+        if failures > 0 {
+            return Err(format!("Doctor found {} problem(s)", failures).into());
+        }
+        println!(
+            "  {} {}",
+            "✓".green().bold(),
+            "Everything looks good".green()
+        );
+        println!();
+        Ok(())
+    }
+
+    /// Check that a hook command runs an existing claude-afk of this version
+    fn diagnose_hook_command(event: &str, command: &str) -> DoctorOutcome {
+        let name = format!("Hook {}", event);
+        let exe = ClaudeSettings::hook_executable(command);
+
+        if !exe.exists() {
+            return DoctorOutcome::fail(
+                name,
+                format!("{} does not exist", exe.display()),
+                "Run claude-afk install-hooks to point the hook at this binary",
+            );
+        }
+
+        if ClaudeSettings::hook_kind(command) == HookKind::Legacy {
+            return DoctorOutcome::warn(
+                name,
+                format!("{} was installed by an older version", exe.display()),
+                "Run claude-afk install-hooks to migrate it",
+            );
+        }
+
+        let expected = format!("{} {}", APP_NAME, env!("CARGO_PKG_VERSION"));
+        match std::process::Command::new(&exe).arg("--version").output() {
+            Ok(output) if String::from_utf8_lossy(&output.stdout).trim() == expected => {
+                DoctorOutcome::pass(name, exe.display().to_string())
+            }
+            Ok(output) => DoctorOutcome::warn(
+                name,
+                format!(
+                    "{} reports {:?}, expected {:?}",
+                    exe.display(),
+                    String::from_utf8_lossy(&output.stdout).trim(),
+                    expected
+                ),
+                "Run claude-afk install-hooks from the binary you want Claude Code to use",
+            ),
+            Err(e) => DoctorOutcome::fail(
+                name,
+                format!("{} could not be run: {}", exe.display(), e),
+                "Check the file permissions or run claude-afk install-hooks",
+            ),
+        }
+    }
+
+    pub fn logs(
+        lines: usize,
+        follow: bool,
+        clear: bool,
+        path: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let log_path = Logger::log_file_path().ok_or("Could not determine the log file path")?;
+
+        if path {
+            println!("{}", log_path.display());
+            return Ok(());
+        }
+
+        if clear {
+            let removed = Logger::clear_logs(&log_path)?;
+            println!();
+            if removed == 0 {
+                println!("  {} No logs to clear", "○".dimmed());
+            } else {
+                println!("  {} Logs {}", "✓".green(), "cleared".white());
+            }
+            println!();
+            return Ok(());
+        }
+
+        let entries = Logger::tail(&log_path, lines)?;
+        if entries.is_empty() && !follow {
+            let settings = Logger::settings();
+            let level = Logger::level(std::env::var(LOG_ENV).ok().as_deref(), settings.level);
+            println!();
+            println!(
+                "  {} No log entries yet in {}",
+                "○".dimmed(),
+                log_path.display().to_string().dimmed()
+            );
+            if level == LogLevel::Off {
+                println!();
+                println!(
+                    "  {} Logging is off. Set {} or add {} under {} in the config file",
+                    "Tip:".dimmed(),
+                    format!("{}=info", LOG_ENV).cyan(),
+                    "level = \"info\"".cyan(),
+                    "[log]".cyan()
+                );
+            }
+            println!();
+            return Ok(());
+        }
+        for entry in &entries {
+            println!("{}", entry);
+        }
+
+        if follow {
+            Self::follow_log(&log_path)?;
+        }
+        Ok(())
+    }
+
+    /// Print lines appended to the log until interrupted, starting over when
+    /// the file is rotated or cleared
+    fn follow_log(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::{Seek, SeekFrom};
+
+        let mut position = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        loop {
+            thread::sleep(LOG_FOLLOW_INTERVAL);
+            let Ok(len) = std::fs::metadata(path).map(|m| m.len()) else {
+                position = 0;
+                continue;
+            };
+            if len < position {
+                position = 0;
+            }
+            if len == position {
+                continue;
+            }
+
+            let mut file = std::fs::File::open(path)?;
+            file.seek(SeekFrom::Start(position))?;
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            // Hold back a partially written last line until it's complete
+            let complete = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+            print!("{}", String::from_utf8_lossy(&bytes[..complete]));
+            std::io::Write::flush(&mut std::io::stdout())?;
+            position += complete as u64;
+        }
+    }
+
+    fn get_backend_url(config: &Config) -> String {
+        // Priority: env var (for local development) > profile > default production URL
+        std::env::var("CLAUDE_AFK_API_URL").unwrap_or_else(|_| {
+            config
+                .profile_backend_url()
+                .unwrap_or(DEFAULT_API_URL)
+                .to_string()
+        })
+    }
+
+    /// The config with the profile for the current directory applied, as
+    /// hooks for a session running here would see it
+    fn load_config_here() -> Result<Config, Box<dyn std::error::Error>> {
+        let mut config = Config::load()?;
+        config.apply_project(&std::env::current_dir()?)?;
+        Ok(config)
+    }
+
+    /// A timeout or poll interval, overridable in milliseconds through the
+    /// environment so tests against a mock backend don't wait minutes. Debug
+    /// builds only: a release build always uses the validated value.
+    fn timing(env_var: &str, default: Duration) -> Duration {
+        if !cfg!(debug_assertions) {
+            return default;
+        }
+        std::env::var(env_var)
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(default)
+    }
+
+    /// Append the outcome of a permission request to the audit log. Like the
+    /// registry, a failure here never affects the decision itself.
+    fn audit(
+        request: &PermissionRequestInput,
+        session: &SessionInfo,
+        outcome: &Result<DecisionOutcome, DecisionError>,
+        sent_at: Instant,
+        backend_url: &str,
+    ) {
+        let (decision, source, device, error) = match outcome {
+            Ok(DecisionOutcome::Decided(remote)) => (
+                remote.decision.into(),
+                remote.source.clone(),
+                remote.device.clone(),
+                None,
+            ),
+            Ok(DecisionOutcome::TimedOut) => (AuditDecision::Timeout, None, None, None),
+            Ok(DecisionOutcome::Cancelled) => (AuditDecision::Terminal, None, None, None),
+            Err(e) => (AuditDecision::Error, None, None, Some(e.to_string())),
+        };
+
+        let home = Redact::home();
+        let mut input = request.tool_input.clone();
+        Redact::value(&mut input, home.as_deref());
+        let backend = backend_url
+            .split("://")
+            .last()
+            .unwrap_or(backend_url)
+            .trim_end_matches('/')
+            .to_string();
+
+        let entry = AuditEntry {
+            time: jiff::Timestamp::from_second(Self::unix_now() as i64).unwrap_or_default(),
+            session_id: request.session_id.clone(),
+            session: Some(session.label.clone()),
+            cwd: Redact::text(&request.cwd, home.as_deref()),
+            tool: request.tool_name.clone(),
+            input,
+            decision,
+            source,
+            device,
+            transport: "web-push".to_string(),
+            backend,
+            latency_ms: sent_at.elapsed().as_millis() as u64,
+            tool_use_id: request.tool_use_id.clone(),
+            error,
+        };
+        let result = AuditLog::path().and_then(|path| AuditLog::append(&path, &entry));
+        if let Err(e) = result {
+            Logger::warn(&format!("Failed to write audit log: {}", e));
+        }
+    }
+
+    /// Whether hooks should forward prompts right now: the mode (after any
+    /// timed `afk` runs out), the schedule, and in auto mode the desktop's
+    /// idle and lock state
+    fn notifications_enabled(config: &Config) -> bool {
+        let mode = config.mode_at(Self::unix_now());
+        if mode == ActiveMode::Off {
+            return false;
+        }
+        if let Some(hold) = Self::describe_schedule(&config.schedule.check(Timestamp::now())) {
+            Logger::debug(&format!("Schedule: {}, not notifying", hold));
+            return false;
+        }
+        if mode != ActiveMode::Auto {
+            return true;
+        }
+        let presence = Presence::detect();
+        let away = Self::mode_allows(mode, presence.as_ref(), config.idle_threshold());
+        match &presence {
+            Some(presence) => Logger::debug(&format!(
+                "Auto mode: {} ({:?})",
+                if away { "away" } else { "present" },
+                presence
+            )),
+            None => Logger::debug("Auto mode: idle state unknown, not notifying"),
+        }
+        away
+    }
+
+    /// Title and message for a permission request: the tool's template where
+    /// the config has one, the built-in text otherwise
+    pub fn permission_text(
+        request: &PermissionRequestInput,
+        session: &SessionInfo,
+        config: &Config,
+    ) -> (String, String) {
+        let builtin = ToolInfo::from_pre_tool_use(request).format_for_notification();
+        match config.templates.for_tool(&request.tool_name) {
+            Some((_, template)) => {
+                template.render(&Templates::permission_fields(request, session), builtin)
+            }
+            None => builtin,
+        }
+    }
+
+    /// Title and message for the idle notification
+    pub fn idle_text(
+        notification: &NotificationInput,
+        session: &SessionInfo,
+        config: &Config,
+    ) -> (String, String) {
+        let builtin = (
+            "Claude is waiting".to_string(),
+            notification.message.clone(),
         );
-        println!();
+        match &config.templates.idle {
+            Some(template) => {
+                template.render(&Templates::idle_fields(notification, session), builtin)
+            }
+            None => builtin,
+        }
+    }
 
-        Ok(())
+    /// Why the schedule holds notifications back, or None when it doesn't
+    fn describe_schedule(check: &ScheduleCheck) -> Option<String> {
+        match check {
+            ScheduleCheck::Open => None,
+            ScheduleCheck::Quiet(window) => Some(format!("quiet hours ({})", window)),
+            ScheduleCheck::OutsideHours => Some("outside working hours".to_string()),
+        }
     }
 
-    #[cfg(debug_assertions)]
-    pub fn clear_logs() -> Result<(), Box<dyn std::error::Error>> {
-        Logger::clear_logs()?;
-        println!();
-        println!("  {} Debug logs {}", "✓".green(), "cleared".white());
-        println!();
-        Ok(())
+    /// "off" or "auto", for the mode a timed `afk` returns to
+    fn mode_name(mode: ActiveMode) -> &'static str {
+        match mode {
+            ActiveMode::On => "on",
+            ActiveMode::Off => "off",
+            ActiveMode::Auto => "auto",
+        }
+    }
+
+    /// An unknown idle state counts as present: a needless phone prompt would
+    /// hold up the terminal prompt for someone sitting right there
+    fn mode_allows(mode: ActiveMode, presence: Option<&Presence>, threshold: Duration) -> bool {
+        match mode {
+            ActiveMode::On => true,
+            ActiveMode::Off => false,
+            ActiveMode::Auto => presence.is_some_and(|p| p.is_away(threshold)),
+        }
     }
 
-    fn get_backend_url() -> String {
-        // Priority: env var (for local development) > default production URL
-        std::env::var("CLAUDE_AFK_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string())
+    /// e.g. "screen locked", "idle 12m, away after 5m"
+    fn describe_presence(presence: &Presence, threshold: Duration) -> String {
+        if presence.locked {
+            return "screen locked".to_string();
+        }
+        match presence.idle_secs {
+            Some(idle) => format!(
+                "idle {}, away after {}",
+                Self::format_age(idle),
+                Self::format_age(threshold.as_secs())
+            ),
+            None => "screen unlocked".to_string(),
+        }
     }
 
-    fn hooks_installed() -> bool {
-        let home = match std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
-            Ok(h) => h,
-            Err(_) => return false,
+    /// Register the session a hook payload belongs to
+    fn track_session(input: &str, config: &Config) {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(input) else {
+            return;
         };
+        let field = |key: &str| value.get(key).and_then(|v| v.as_str());
+        let (Some(session_id), Some(cwd)) = (field("session_id"), field("cwd")) else {
+            return;
+        };
+        let session = SessionInfo::detect(session_id, cwd, &config.labels);
+        Self::update_registry(|registry| registry.touch(&session, cwd, SessionRegistry::now()));
+    }
 
-        let settings_path = PathBuf::from(&home).join(".claude").join("settings.json");
+    /// Apply a change to the session registry; failures are only logged since
+    /// the registry is informational
+    fn update_registry(change: impl FnOnce(&mut SessionRegistry)) {
+        let result =
+            SessionRegistry::path().and_then(|path| SessionRegistry::update(&path, change));
+        if let Err(e) = result {
+            Logger::warn(&format!("Failed to update session registry: {}", e));
+        }
+    }
 
-        if !settings_path.exists() {
-            return false;
+    /// Compact age such as "45s", "12m", "3h" or "2d"
+    fn format_age(secs: u64) -> String {
+        match secs {
+            0..60 => format!("{}s", secs),
+            60..3600 => format!("{}m", secs / 60),
+            3600..86400 => format!("{}h", secs / 3600),
+            _ => format!("{}d", secs / 86400),
         }
+    }
 
-        let content = match fs::read_to_string(&settings_path) {
-            Ok(c) => c,
-            Err(_) => return false,
-        };
+    /// Split a pairing code in two halves for readability, e.g. "ABCD-EFGH"
+    fn format_pairing_code(code: &str) -> String {
+        // Split on chars: the code comes from the backend and may not be ASCII
+        let chars: Vec<char> = code.chars().collect();
+        let (first, second) = chars.split_at(chars.len().div_ceil(2));
+        format!(
+            "{}-{}",
+            first.iter().collect::<String>(),
+            second.iter().collect::<String>()
+        )
+    }
 
-        let settings: serde_json::Value = match serde_json::from_str(&content) {
-            Ok(s) => s,
-            Err(_) => return false,
-        };
+    fn unix_now() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
 
-        // Check if hooks object exists with claude-afk entries
-        let hooks = match settings.get("hooks") {
-            Some(h) => h,
-            None => return false,
-        };
+    /// Whether the backend asked for the device token to be replaced by now
+    fn token_rotation_due(config: &Config) -> bool {
+        config
+            .token_rotate_after
+            .is_some_and(|rotate_after| rotate_after <= Self::unix_now())
+    }
 
-        // Helper to check if a hook array contains claude-afk
-        let has_claude_afk = |hook_array: &serde_json::Value| -> bool {
-            hook_array
-                .as_array()
-                .map(|arr| {
-                    arr.iter().any(|hook| {
-                        hook.get("hooks")
-                            .and_then(|h| h.as_array())
-                            .map(|inner| {
-                                inner.iter().any(|h| {
-                                    h.get("command")
-                                        .and_then(|c| c.as_str())
-                                        .map(|c| c.contains("claude-afk"))
-                                        .unwrap_or(false)
-                                })
-                            })
-                            .unwrap_or(false)
-                    })
-                })
-                .unwrap_or(false)
-        };
+    /// Exchange the current device token for a new one and persist it
+    fn rotate_device_token(
+        config: &mut Config,
+        backend_url: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let device_token = config.device_token.as_deref().ok_or("No device paired")?;
 
-        // Check both PermissionRequest and Notification hooks
-        let has_permission = hooks
-            .get("PermissionRequest")
-            .map(has_claude_afk)
-            .unwrap_or(false);
+        let response: TokenRotateResponse =
+            ureq::post(&format!("{}/api/device/rotate", backend_url))
+                .header("Authorization", &format!("Bearer {}", device_token))
+                .send_empty()?
+                .into_body()
+                .read_json()?;
 
-        let has_notification = hooks
-            .get("Notification")
-            .map(has_claude_afk)
-            .unwrap_or(false);
+        // Save into a fresh copy: `config` may have a profile applied on top.
+        // A profile sharing the default device rotated the default token.
+        let profile = config.device_profile().map(String::from);
+        let mut stored = Config::load()?;
+        stored.set_device_token(
+            profile.as_deref(),
+            Some(response.device_token.clone()),
+            Some(response.expires_at),
+            Some(response.rotate_after),
+        )?;
+        Config::save(&stored)?;
+
+        config.device_token = Some(response.device_token);
+        config.token_expires_at = Some(response.expires_at);
+        config.token_rotate_after = Some(response.rotate_after);
+
+        Logger::info("Device token rotated");
+        Ok(())
+    }
 
-        has_permission && has_notification
+    /// Events with a claude-afk hook in at least one scope
+    fn hook_events() -> Vec<HookEvent> {
+        let all_settings = ClaudeSettings::read_all();
+        HookEvent::ALL
+            .into_iter()
+            .filter(|event| {
+                all_settings.iter().any(|(_, settings)| {
+                    ClaudeSettings::has_claude_afk_hook(settings, event.as_str())
+                })
+            })
+            .collect()
     }
 
-    fn render_qr(url: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let code = QrCode::new(url)?;
-        let string = code
-            .render::<Dense1x2>()
-            .dark_color(Dense1x2::Light)
-            .light_color(Dense1x2::Dark)
-            .build();
-        println!("{}", string);
-        Ok(())
+    /// Scopes with at least one claude-afk hook
+    fn hook_scopes() -> Vec<HookScope> {
+        ClaudeSettings::read_all()
+            .into_iter()
+            .filter(|(_, settings)| {
+                HookEvent::ALL
+                    .iter()
+                    .any(|event| ClaudeSettings::has_claude_afk_hook(settings, event.as_str()))
+            })
+            .map(|(scope, _)| scope)
+            .collect()
+    }
+
+    /// Infer the QR format from the output file extension, e.g. "qr.png"
+    fn qr_format_from_extension(path: &std::path::Path) -> Option<QrFormat> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" => Some(QrFormat::Png),
+            "svg" => Some(QrFormat::Svg),
+            "txt" => Some(QrFormat::Ascii),
+            _ => None,
+        }
     }
 }
 
@@ -706,11 +2708,12 @@ mod tests {
     use crate::cmd::Cmd;
     use crate::config::Config;
     use crate::models::{
-        GenericHookInput, HookOutput, NotificationInput, NotifyPayload, PermissionRequestInput,
-        SimpleNotifyPayload, ToolInfo,
+        GenericHookInput, HookOutput, NotificationInput, NotifyContext, NotifyPayload,
+        PermissionRequestInput, SimpleNotifyPayload, ToolInfo,
     };
 
     use super::*;
+    use crate::constants::DEFAULT_IDLE_THRESHOLD;
     use std::sync::Mutex;
 
     // Mutex to ensure env var tests don't run in parallel
@@ -723,7 +2726,7 @@ mod tests {
         let config = Config::default();
         assert!(config.device_token.is_none());
         assert!(config.backend_url.is_empty());
-        assert!(!config.active);
+        assert_eq!(config.active, ActiveMode::Off);
     }
 
     #[test]
@@ -731,7 +2734,9 @@ mod tests {
         let config = Config {
             device_token: Some("test-token-12345".to_string()),
             backend_url: "https://example.com".to_string(),
-            active: true,
+            active: ActiveMode::Auto,
+            idle_threshold: Some(600),
+            ..Default::default()
         };
 
         let serialized = toml::to_string(&config).unwrap();
@@ -740,6 +2745,8 @@ mod tests {
         assert_eq!(deserialized.device_token, config.device_token);
         assert_eq!(deserialized.backend_url, config.backend_url);
         assert_eq!(deserialized.active, config.active);
+        assert_eq!(deserialized.idle_threshold(), Duration::from_secs(600));
+        assert!(serialized.contains("active = \"auto\""));
     }
 
     #[test]
@@ -753,7 +2760,141 @@ mod tests {
         let config: Config = toml::from_str(toml_str).unwrap();
         assert_eq!(config.device_token, Some("my-device-token".to_string()));
         assert_eq!(config.backend_url, "http://localhost:5173");
-        assert!(config.active);
+        // Older versions wrote a boolean
+        assert_eq!(config.active, ActiveMode::On);
+    }
+
+    #[test]
+    fn test_config_active_modes() {
+        for (value, mode) in [
+            ("\"on\"", ActiveMode::On),
+            ("\"off\"", ActiveMode::Off),
+            ("\"auto\"", ActiveMode::Auto),
+            ("false", ActiveMode::Off),
+        ] {
+            let config: Config =
+                toml::from_str(&format!("backend_url = \"\"\nactive = {}", value)).unwrap();
+            assert_eq!(config.active, mode);
+            assert_eq!(config.idle_threshold(), DEFAULT_IDLE_THRESHOLD);
+        }
+
+        let err = toml::from_str::<Config>("backend_url = \"\"\nactive = \"sometimes\"")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected \"on\", \"off\", \"auto\" or a boolean"));
+    }
+
+    #[test]
+    fn test_config_timed_afk_reverts() {
+        let config = Config {
+            active: ActiveMode::Auto,
+            afk_until: Some(1_700_000_000),
+            ..Default::default()
+        };
+
+        assert_eq!(config.mode_at(1_699_999_999), ActiveMode::On);
+        assert_eq!(config.mode_at(1_700_000_000), ActiveMode::Auto);
+        assert_eq!(Config::default().mode_at(0), ActiveMode::Off);
+    }
+
+    #[test]
+    fn test_config_schedule_is_validated_on_load() {
+        let config: Config = toml::from_str(
+            "backend_url = \"\"\nactive = \"on\"\n[schedule]\ntimezone = \"Europe/Berlin\"\nhours = [\"mon-fri 09:00-19:00\"]\nquiet = [\"22:00-07:00\"]",
+        )
+        .unwrap();
+        assert_eq!(config.schedule.hours.len(), 1);
+        assert_eq!(config.schedule.quiet[0].to_string(), "22:00-07:00");
+
+        let err = toml::from_str::<Config>(
+            "backend_url = \"\"\nactive = \"on\"\n[schedule]\ntimezone = \"Europe/Nowhere\"",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("Unknown time zone 'Europe/Nowhere'"));
+    }
+
+    #[test]
+    fn test_config_decision_timeout_is_limited() {
+        let config: Config =
+            toml::from_str("backend_url = \"\"\nactive = \"on\"\ndecision_timeout = 600").unwrap();
+        assert_eq!(config.decision_timeout(), Duration::from_secs(600));
+
+        for toml in [
+            "backend_url = \"\"\nactive = \"on\"\ndecision_timeout = 601",
+            "backend_url = \"\"\nactive = \"on\"\ndecision_timeout = 0",
+            "backend_url = \"\"\nactive = \"on\"\n[profiles.work]\ndecision_timeout = 3600",
+        ] {
+            let err = toml::from_str::<Config>(toml).unwrap_err().to_string();
+            assert!(err.contains("decision_timeout must be between 1 and 600 seconds"));
+        }
+    }
+
+    #[test]
+    fn test_config_apply_profile() {
+        let mut config: Config = toml::from_str(
+            r#"
+            device_token = "personal"
+            backend_url = ""
+            active = "auto"
+            afk_until = 1700000000
+
+            [profiles.work]
+            backend_url = "https://afk.example.com"
+            decision_timeout = 600
+            active = "on"
+
+            [profiles.slow]
+            decision_timeout = 300
+            "#,
+        )
+        .unwrap();
+        let mut shared = Config {
+            device_token: config.device_token.clone(),
+            profiles: config.profiles.clone(),
+            ..Default::default()
+        };
+
+        config.apply_profile("work", "~/work/*").unwrap();
+        // Its own backend means its own (here missing) pairing
+        assert_eq!(config.device_token, None);
+        assert_eq!(config.active, ActiveMode::On);
+        assert_eq!(config.afk_until, None);
+        assert_eq!(config.decision_timeout(), Duration::from_secs(600));
+        assert_eq!(
+            config.profile_backend_url(),
+            Some("https://afk.example.com")
+        );
+
+        shared.apply_profile("slow", "--profile").unwrap();
+        assert_eq!(shared.device_token.as_deref(), Some("personal"));
+        assert_eq!(shared.decision_timeout(), Duration::from_secs(300));
+        assert_eq!(shared.profile_backend_url(), None);
+
+        let err = shared
+            .apply_profile("play", "/repo/.claude-afk.toml")
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "Profile 'play' (from /repo/.claude-afk.toml) is not defined in the config file"
+        );
+    }
+
+    #[test]
+    fn test_describe_schedule() {
+        let schedule: crate::schedule::Schedule =
+            toml::from_str("quiet = [\"22:00-07:00\"]").unwrap();
+        let window = schedule.quiet[0].clone();
+
+        assert_eq!(Cmd::describe_schedule(&ScheduleCheck::Open), None);
+        assert_eq!(
+            Cmd::describe_schedule(&ScheduleCheck::Quiet(window)).as_deref(),
+            Some("quiet hours (22:00-07:00)")
+        );
+        assert_eq!(
+            Cmd::describe_schedule(&ScheduleCheck::OutsideHours).as_deref(),
+            Some("outside working hours")
+        );
     }
 
     #[test]
@@ -767,7 +2908,54 @@ mod tests {
         let config: Config = toml::from_str(toml_str).unwrap();
         assert!(config.device_token.is_none());
         assert!(config.backend_url.is_empty());
-        assert!(!config.active);
+        assert_eq!(config.active, ActiveMode::Off);
+    }
+
+    #[test]
+    fn test_config_deserialize_token_expiry() {
+        let toml_str = r#"
+            device_token = "my-device-token"
+            backend_url = "http://localhost:5173"
+            active = true
+            token_expires_at = 1700000000
+            token_rotate_after = 1690000000
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.token_expires_at, Some(1700000000));
+        assert_eq!(config.token_rotate_after, Some(1690000000));
+    }
+
+    // ==================== Token Rotation Tests ====================
+
+    #[test]
+    fn test_token_rotation_due_when_past_rotate_after() {
+        let config = Config {
+            device_token: Some("token".to_string()),
+            token_rotate_after: Some(Cmd::unix_now() - 60),
+            ..Default::default()
+        };
+        assert!(Cmd::token_rotation_due(&config));
+    }
+
+    #[test]
+    fn test_token_rotation_not_due_before_rotate_after() {
+        let config = Config {
+            device_token: Some("token".to_string()),
+            token_rotate_after: Some(Cmd::unix_now() + 3600),
+            ..Default::default()
+        };
+        assert!(!Cmd::token_rotation_due(&config));
+    }
+
+    #[test]
+    fn test_token_rotation_not_due_without_expiry_info() {
+        // Pairings made against older backends carry no rotation schedule
+        let config = Config {
+            device_token: Some("token".to_string()),
+            ..Default::default()
+        };
+        assert!(!Cmd::token_rotation_due(&config));
     }
 
     // ==================== Backend URL Tests ====================
@@ -777,16 +2965,24 @@ mod tests {
         let _guard = ENV_MUTEX.lock().unwrap();
         std::env::remove_var("CLAUDE_AFK_API_URL");
 
-        let result = Cmd::get_backend_url();
+        let result = Cmd::get_backend_url(&Config::default());
         assert_eq!(result, DEFAULT_API_URL);
     }
 
+    #[test]
+    fn test_timing_defaults_without_env_var() {
+        assert_eq!(
+            Cmd::timing("CLAUDE_AFK_TEST_UNSET_TIMING_MS", Duration::from_secs(2)),
+            Duration::from_secs(2)
+        );
+    }
+
     #[test]
     fn test_get_backend_url_env_var_overrides_default() {
         let _guard = ENV_MUTEX.lock().unwrap();
         std::env::set_var("CLAUDE_AFK_API_URL", "http://localhost:5173");
 
-        let result = Cmd::get_backend_url();
+        let result = Cmd::get_backend_url(&Config::default());
         assert_eq!(result, "http://localhost:5173");
 
         std::env::remove_var("CLAUDE_AFK_API_URL");
@@ -1021,6 +3217,15 @@ mod tests {
         assert!(message.contains("Hello world"));
     }
 
+    #[test]
+    fn test_format_age() {
+        assert_eq!(Cmd::format_age(0), "0s");
+        assert_eq!(Cmd::format_age(59), "59s");
+        assert_eq!(Cmd::format_age(125), "2m");
+        assert_eq!(Cmd::format_age(7200), "2h");
+        assert_eq!(Cmd::format_age(3 * 86400), "3d");
+    }
+
     // ==================== NotifyPayload Tests ====================
 
     #[test]
@@ -1030,6 +3235,15 @@ mod tests {
             message: "Test Message".to_string(),
             tool_use_id: "tool-123".to_string(),
             session_id: "sess-456".to_string(),
+            session: SessionInfo {
+                id: "sess-456".to_string(),
+                label: "app (main)".to_string(),
+                project: "app".to_string(),
+                branch: Some("main".to_string()),
+                host: None,
+            },
+            context: None,
+            timeout: 120,
         };
 
         let json = serde_json::to_string(&payload).unwrap();
@@ -1037,6 +3251,39 @@ mod tests {
         assert!(json.contains("\"message\":\"Test Message\""));
         assert!(json.contains("\"tool_use_id\":\"tool-123\""));
         assert!(json.contains("\"session_id\":\"sess-456\""));
+        assert!(json.contains("\"label\":\"app (main)\""));
+        assert!(json.contains("\"branch\":\"main\""));
+        assert!(!json.contains("\"host\""));
+        assert!(!json.contains("context"));
+        assert!(json.contains("\"timeout\":120"));
+    }
+
+    #[test]
+    fn test_notify_payload_serializes_context() {
+        let payload = NotifyPayload {
+            title: "Run command".to_string(),
+            message: "npm test".to_string(),
+            tool_use_id: "tool-123".to_string(),
+            session_id: "sess-456".to_string(),
+            session: SessionInfo {
+                id: "sess-456".to_string(),
+                label: "app (main)".to_string(),
+                project: "app".to_string(),
+                branch: Some("main".to_string()),
+                host: None,
+            },
+            context: Some(NotifyContext {
+                summary: "Running the tests".to_string(),
+                prompt: Some("Fix the tests".to_string()),
+                reasoning: None,
+            }),
+            timeout: 120,
+        };
+
+        let json: serde_json::Value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["context"]["summary"], "Running the tests");
+        assert_eq!(json["context"]["prompt"], "Fix the tests");
+        assert!(json["context"].get("reasoning").is_none());
     }
 
     // ==================== HookOutput Tests ====================
@@ -1075,6 +3322,22 @@ mod tests {
         assert_eq!(response.pairing_token, "xyz789");
     }
 
+    #[test]
+    fn test_pairing_init_response_parse_with_code() {
+        let json =
+            r#"{"pairingId": "abc123", "pairingToken": "xyz789", "pairingCode": "7K3QM2XR"}"#;
+        let response: PairingInitResponse = serde_json::from_str(json).unwrap();
+
+        assert_eq!(response.pairing_code, Some("7K3QM2XR".to_string()));
+    }
+
+    #[test]
+    fn test_format_pairing_code() {
+        assert_eq!(Cmd::format_pairing_code("7K3QM2XR"), "7K3Q-M2XR");
+        assert_eq!(Cmd::format_pairing_code("ABCDEFG"), "ABCD-EFG");
+        assert_eq!(Cmd::format_pairing_code("ÄBÇD"), "ÄB-ÇD");
+    }
+
     #[test]
     fn test_pairing_status_response_incomplete() {
         let json = r#"{"complete": false, "deviceToken": null}"#;
@@ -1096,6 +3359,114 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_pairing_status_response_complete_with_expiry() {
+        let json = r#"{"complete": true, "deviceToken": "device-token-12345", "expiresAt": 1700000000, "rotateAfter": 1690000000}"#;
+        let response: PairingStatusResponse = serde_json::from_str(json).unwrap();
+
+        assert_eq!(response.expires_at, Some(1700000000));
+        assert_eq!(response.rotate_after, Some(1690000000));
+    }
+
+    #[test]
+    fn test_token_rotate_response_parse() {
+        let json = r#"{"success": true, "deviceToken": "new-token", "expiresAt": 1700000000, "rotateAfter": 1690000000}"#;
+        let response: TokenRotateResponse = serde_json::from_str(json).unwrap();
+
+        assert_eq!(response.device_token, "new-token");
+        assert_eq!(response.expires_at, 1700000000);
+        assert_eq!(response.rotate_after, 1690000000);
+    }
+
+    #[test]
+    fn test_device_status_response_parse() {
+        let json = r#"{"valid": true, "subscription": "expired", "expiresAt": 1700000000, "rotateAfter": 1690000000}"#;
+        let response: DeviceStatusResponse = serde_json::from_str(json).unwrap();
+
+        assert!(response.valid);
+        assert_eq!(response.subscription, "expired");
+        assert!(!response.rotated);
+    }
+
+    // ==================== Status Report Tests ====================
+
+    #[test]
+    fn test_backend_health_is_healthy() {
+        let health = BackendHealth {
+            reachable: true,
+            token_valid: Some(true),
+            subscription: Some("active".to_string()),
+            latency_ms: Some(42),
+            error: None,
+        };
+        assert!(health.is_healthy());
+    }
+
+    #[test]
+    fn test_backend_health_reports_each_failure() {
+        let unreachable = BackendHealth {
+            error: Some("Unreachable: connection failed".to_string()),
+            ..Default::default()
+        };
+        assert!(!unreachable.is_healthy());
+
+        let rejected = BackendHealth {
+            reachable: true,
+            token_valid: Some(false),
+            ..Default::default()
+        };
+        assert!(!rejected.is_healthy());
+
+        let expired_subscription = BackendHealth {
+            reachable: true,
+            token_valid: Some(true),
+            subscription: Some("expired".to_string()),
+            ..Default::default()
+        };
+        assert!(!expired_subscription.is_healthy());
+    }
+
+    #[test]
+    fn test_status_report_serialization() {
+        let report = StatusReport {
+            paired: true,
+            token_expired: false,
+            token_expires_at: Some(1700000000),
+            active: true,
+            mode: ActiveMode::On,
+            afk_until: None,
+            profile: None,
+            presence: None,
+            schedule_hold: None,
+            hooks_installed: false,
+            hook_scopes: vec![HookScope::Project],
+            hook_events: vec![HookEvent::PostToolUse],
+            hooks_outdated: false,
+            backend_url: "https://example.com".to_string(),
+            check: None,
+        };
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        assert_eq!(parsed["paired"], true);
+        assert_eq!(parsed["hooks_installed"], false);
+        assert_eq!(parsed["hook_scopes"], serde_json::json!(["project"]));
+        assert_eq!(parsed["hook_events"], serde_json::json!(["PostToolUse"]));
+        assert_eq!(parsed["token_expires_at"], 1700000000);
+        // The check section only appears with --check
+        assert!(parsed.get("check").is_none());
+    }
+
+    #[test]
+    fn test_check_backend_unreachable() {
+        // Port 9 (discard) on localhost is not expected to run an HTTP server
+        let health = Cmd::check_backend("http://127.0.0.1:9", Some("token"));
+
+        assert!(!health.reachable);
+        assert!(health.token_valid.is_none());
+        assert!(health.error.is_some());
+    }
+
     #[test]
     fn test_notify_response_parse() {
         let json = r#"{"success": true, "decisionId": "decision-abc123"}"#;
@@ -1146,12 +3517,7 @@ mod tests {
     #[test]
     fn test_qr_code_generation() {
         let url = "https://example.com/pair/test-token";
-        let code = QrCode::new(url).unwrap();
-        let rendered = code
-            .render::<Dense1x2>()
-            .dark_color(Dense1x2::Light)
-            .light_color(Dense1x2::Dark)
-            .build();
+        let rendered = Qr::render_text(url, QrFormat::Unicode).unwrap();
 
         // QR code should be non-empty and contain multiple lines
         assert!(!rendered.is_empty());
@@ -1161,10 +3527,29 @@ mod tests {
     #[test]
     fn test_qr_code_with_long_url() {
         let url = "https://example.com/pair/very-long-token-that-is-32-chars";
-        let result = QrCode::new(url);
+        let result = Qr::render_text(url, QrFormat::Ascii);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_qr_format_from_extension() {
+        use std::path::Path;
+
+        assert_eq!(
+            Cmd::qr_format_from_extension(Path::new("qr.png")),
+            Some(QrFormat::Png)
+        );
+        assert_eq!(
+            Cmd::qr_format_from_extension(Path::new("docs/QR.SVG")),
+            Some(QrFormat::Svg)
+        );
+        assert_eq!(
+            Cmd::qr_format_from_extension(Path::new("qr.txt")),
+            Some(QrFormat::Ascii)
+        );
+        assert_eq!(Cmd::qr_format_from_extension(Path::new("qr")), None);
+    }
+
     // ==================== URL Construction Tests ====================
 
     #[test]
@@ -1197,39 +3582,60 @@ mod tests {
     // ==================== Config State Logic Tests ====================
 
     #[test]
-    fn test_should_notify_when_configured_and_active() {
-        let config = Config {
-            device_token: Some("token".to_string()),
-            backend_url: "http://example.com".to_string(),
-            active: true,
-        };
-
-        let should_notify = config.device_token.is_some() && config.active;
-        assert!(should_notify);
-    }
-
-    #[test]
-    fn test_should_not_notify_when_no_token() {
-        let config = Config {
-            device_token: None,
-            backend_url: "http://example.com".to_string(),
-            active: true,
+    fn test_mode_allows() {
+        let threshold = Duration::from_secs(300);
+        let presence = |locked, idle_secs| Presence {
+            locked,
+            idle_secs,
+            source: "test".to_string(),
         };
 
-        let should_notify = config.device_token.is_some() && config.active;
-        assert!(!should_notify);
+        assert!(Cmd::mode_allows(ActiveMode::On, None, threshold));
+        assert!(!Cmd::mode_allows(
+            ActiveMode::Off,
+            Some(&presence(true, None)),
+            threshold
+        ));
+        assert!(Cmd::mode_allows(
+            ActiveMode::Auto,
+            Some(&presence(true, Some(0))),
+            threshold
+        ));
+        assert!(Cmd::mode_allows(
+            ActiveMode::Auto,
+            Some(&presence(false, Some(600))),
+            threshold
+        ));
+        assert!(!Cmd::mode_allows(
+            ActiveMode::Auto,
+            Some(&presence(false, Some(10))),
+            threshold
+        ));
+        // Unknown idle state counts as present
+        assert!(!Cmd::mode_allows(ActiveMode::Auto, None, threshold));
     }
 
     #[test]
-    fn test_should_not_notify_when_inactive() {
-        let config = Config {
-            device_token: Some("token".to_string()),
-            backend_url: "http://example.com".to_string(),
-            active: false,
+    fn test_describe_presence() {
+        let threshold = Duration::from_secs(300);
+        let presence = |locked, idle_secs| Presence {
+            locked,
+            idle_secs,
+            source: "test".to_string(),
         };
 
-        let should_notify = config.device_token.is_some() && config.active;
-        assert!(!should_notify);
+        assert_eq!(
+            Cmd::describe_presence(&presence(true, Some(5)), threshold),
+            "screen locked"
+        );
+        assert_eq!(
+            Cmd::describe_presence(&presence(false, Some(125)), threshold),
+            "idle 2m, away after 5m"
+        );
+        assert_eq!(
+            Cmd::describe_presence(&presence(false, None), threshold),
+            "screen unlocked"
+        );
     }
 
     // ==================== Authorization Header Tests ====================
@@ -1318,6 +3724,7 @@ mod tests {
         let payload = SimpleNotifyPayload {
             title: "Claude is waiting".to_string(),
             message: "Claude needs your input".to_string(),
+            session: None,
         };
 
         let json = serde_json::to_string(&payload).unwrap();
@@ -1333,6 +3740,7 @@ mod tests {
         let payload = SimpleNotifyPayload {
             title: "Test".to_string(),
             message: "Test message".to_string(),
+            session: None,
         };
 
         let json = serde_json::to_string(&payload).unwrap();
@@ -1341,6 +3749,136 @@ mod tests {
         assert_eq!(parsed.as_object().unwrap().len(), 2);
     }
 
+    // ==================== Test Command Tests ====================
+
+    #[test]
+    fn test_sample_permission_request_matches_tool_info() {
+        let cases = [
+            (TestTool::Bash, "Run bash command? 🐚"),
+            (TestTool::Write, "Write file? 📝"),
+            (TestTool::Edit, "Edit file? 📝"),
+            (TestTool::Read, "Read file? 📝"),
+            (TestTool::Unknown, "Tool: mcp__example__lookup"),
+        ];
+
+        for (tool, expected_title) in cases {
+            let input = Cmd::sample_permission_request(tool);
+            assert_eq!(input.hook_event_name, "PermissionRequest");
+            assert!(input.tool_use_id.is_some());
+
+            let (title, _) = ToolInfo::from_pre_tool_use(&input).format_for_notification();
+            assert_eq!(title, expected_title);
+        }
+    }
+
+    #[test]
+    fn test_decision_hook_output() {
+        let allow = serde_json::to_string(&Decision::Allow.hook_output().unwrap()).unwrap();
+        assert!(allow.contains("\"behavior\":\"allow\""));
+
+        let deny = serde_json::to_string(&Decision::Deny.hook_output().unwrap()).unwrap();
+        assert!(deny.contains("\"behavior\":\"deny\""));
+
+        // Dismissing falls back to the regular terminal prompt
+        assert!(Decision::Dismiss.hook_output().is_none());
+    }
+
+    // ==================== Hook Error Tests ====================
+
+    #[test]
+    fn test_unknown_hook_event_fails() {
+        let input = r#"{"session_id": "s", "hook_event_name": "SessionStart"}"#;
+        let result =
+            Cmd::handle_hook_input(input, &Config::default(), "token", "http://127.0.0.1:9");
+        assert!(
+            matches!(result, Err(HookError::Failed(m)) if m == "Unknown hook event: SessionStart")
+        );
+    }
+
+    #[test]
+    fn test_invalid_hook_json_fails() {
+        let result = Cmd::handle_hook_input(
+            "not json",
+            &Config::default(),
+            "token",
+            "http://127.0.0.1:9",
+        );
+        assert!(matches!(result, Err(HookError::Failed(_))));
+    }
+
+    #[test]
+    fn test_ignored_notification_falls_back_silently() {
+        let input = r#"{
+            "session_id": "s",
+            "transcript_path": "/tmp/t.jsonl",
+            "cwd": "/tmp",
+            "permission_mode": "default",
+            "hook_event_name": "Notification",
+            "message": "Claude needs your permission",
+            "notification_type": "permission_prompt"
+        }"#;
+        let result =
+            Cmd::handle_hook_input(input, &Config::default(), "token", "http://127.0.0.1:9");
+        assert!(matches!(result, Err(HookError::Fallback(None))));
+    }
+
+    #[test]
+    fn test_malformed_permission_request_fails() {
+        let input = r#"{"session_id": "s", "hook_event_name": "PermissionRequest"}"#;
+        let result =
+            Cmd::handle_hook_input(input, &Config::default(), "token", "http://127.0.0.1:9");
+        assert!(
+            matches!(result, Err(HookError::Failed(m)) if m.starts_with("Failed to parse PermissionRequest input"))
+        );
+    }
+
+    // ==================== Doctor Tests ====================
+
+    #[test]
+    fn test_claude_afk_hook_commands() {
+        let settings = serde_json::json!({
+            "hooks": {
+                "PermissionRequest": [
+                    {"matcher": "*", "hooks": [{"type": "command", "command": "/usr/local/bin/claude-afk"}]},
+                    {"matcher": "Bash", "hooks": [{"type": "command", "command": "/usr/bin/other-tool"}]}
+                ],
+                "Notification": [
+                    {"matcher": "idle_prompt", "hooks": [{"type": "command", "command": "/usr/local/bin/claude-afk"}]}
+                ]
+            }
+        });
+
+        let commands = ClaudeSettings::claude_afk_hook_commands(&settings);
+        assert_eq!(commands.len(), 2);
+        assert!(commands.contains(&(
+            "PermissionRequest".to_string(),
+            "/usr/local/bin/claude-afk".to_string()
+        )));
+        assert!(commands.contains(&(
+            "Notification".to_string(),
+            "/usr/local/bin/claude-afk".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_claude_afk_hook_commands_without_hooks() {
+        assert!(ClaudeSettings::claude_afk_hook_commands(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_hook_executable_strips_arguments() {
+        assert_eq!(
+            ClaudeSettings::hook_executable("/nonexistent/claude-afk notify --hook-id claude-afk"),
+            PathBuf::from("/nonexistent/claude-afk")
+        );
+    }
+
+    #[test]
+    fn test_diagnose_missing_hook_executable() {
+        let outcome = Cmd::diagnose_hook_command("PermissionRequest", "/nonexistent/claude-afk");
+        assert!(matches!(outcome, DoctorOutcome::Fail { .. }));
+    }
+
     // ==================== Hook Type Detection Tests ====================
 
     #[test]
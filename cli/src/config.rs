@@ -1,20 +1,293 @@
-use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use crate::constants::APP_NAME;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    constants::{APP_NAME, DECISION_TIMEOUT, DEFAULT_IDLE_THRESHOLD, MAX_DECISION_TIMEOUT},
+    logger::LogSettings,
+    profile::{Profile, ProjectFile},
+    schedule::Schedule,
+    templates::Templates,
+};
+
+/// Whether hooks forward prompts to the phone
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ActiveMode {
+    On,
+    #[default]
+    Off,
+    /// Only while the screen is locked or the desktop has been idle for a while
+    Auto,
+}
+
+/// Accepts "on", "off" and "auto", and the `true`/`false` older versions wrote
+impl<'de> Deserialize<'de> for ActiveMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ActiveModeVisitor;
+
+        impl de::Visitor<'_> for ActiveModeVisitor {
+            type Value = ActiveMode;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "\"on\", \"off\", \"auto\" or a boolean")
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<ActiveMode, E> {
+                Ok(if value {
+                    ActiveMode::On
+                } else {
+                    ActiveMode::Off
+                })
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<ActiveMode, E> {
+                match value {
+                    "on" => Ok(ActiveMode::On),
+                    "off" => Ok(ActiveMode::Off),
+                    "auto" => Ok(ActiveMode::Auto),
+                    _ => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(ActiveModeVisitor)
+    }
+}
+
+/// Which profile applies and why, e.g. "work" from "~/work/*"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectedProfile {
+    pub name: String,
+    pub source: String,
+}
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Config {
     pub device_token: Option<String>,
     pub backend_url: String,
-    pub active: bool,
+    pub active: ActiveMode,
+    /// Unix timestamp (seconds) until which `afk --for`/`--until` keeps
+    /// notifications on; `active` applies again afterwards
+    #[serde(default)]
+    pub afk_until: Option<u64>,
+    /// Unix timestamp (seconds) after which the backend rejects the device token
+    #[serde(default)]
+    pub token_expires_at: Option<u64>,
+    /// Unix timestamp (seconds) after which the token should be rotated
+    #[serde(default)]
+    pub token_rotate_after: Option<u64>,
+    /// When set, every raw hook payload is saved here (redacted) for replay
+    #[serde(default)]
+    pub record_dir: Option<PathBuf>,
+    /// Session label overrides, keyed by project root path or repository name
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Seconds without keyboard or mouse input after which `auto` mode
+    /// counts you as away
+    #[serde(default)]
+    pub idle_threshold: Option<u64>,
+    /// Seconds to wait for an answer on the phone before asking at the terminal
+    #[serde(default, deserialize_with = "decision_timeout_secs")]
+    pub decision_timeout: Option<u64>,
+    /// Working hours and quiet hours
+    #[serde(default)]
+    pub schedule: Schedule,
+    /// Notification titles and messages per tool and hook event
+    #[serde(default)]
+    pub templates: Templates,
+    /// Named profiles, picked per project by `paths` or `.claude-afk.toml`
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// The profile applied by `apply_project`; never saved
+    #[serde(skip)]
+    pub profile: Option<SelectedProfile>,
+    /// Log level, format and rotation for debug.log
+    #[serde(default)]
+    pub log: LogSettings,
+}
+
+/// `decision_timeout` in seconds, rejecting values the backend won't wait for
+pub fn decision_timeout_secs<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let secs = Option::<u64>::deserialize(deserializer)?;
+    match secs {
+        Some(secs) if secs == 0 || secs > MAX_DECISION_TIMEOUT.as_secs() => {
+            Err(de::Error::custom(format!(
+                "decision_timeout must be between 1 and {} seconds, got {}",
+                MAX_DECISION_TIMEOUT.as_secs(),
+                secs
+            )))
+        }
+        _ => Ok(secs),
+    }
 }
 
 impl Config {
     pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
-        Ok(confy::load(APP_NAME, None)?)
+        match confy::load(APP_NAME, None) {
+            Ok(config) => Ok(config),
+            // confy's own message doesn't say what is wrong, e.g. a bad template
+            Err(confy::ConfyError::BadTomlData(e)) => {
+                let path = confy::get_configuration_file_path(APP_NAME, None)?;
+                Err(format!("Invalid config file {}: {}", path.display(), e.message()).into())
+            }
+            Err(e) => Err(e.into()),
+        }
     }
 
     pub fn save(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
         Ok(confy::store(APP_NAME, None, config)?)
     }
+
+    pub fn idle_threshold(&self) -> Duration {
+        self.idle_threshold
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_IDLE_THRESHOLD)
+    }
+
+    pub fn decision_timeout(&self) -> Duration {
+        self.decision_timeout
+            .map(Duration::from_secs)
+            .unwrap_or(DECISION_TIMEOUT)
+    }
+
+    /// Apply the profile and `.claude-afk.toml` for a session working in
+    /// `cwd`. A project file naming a profile wins over `paths` patterns.
+    pub fn apply_project(&mut self, cwd: &Path) -> Result<(), String> {
+        let project_file = ProjectFile::find(cwd)?;
+        let home = crate::redact::Redact::home();
+        let selected = match &project_file {
+            Some((
+                path,
+                ProjectFile {
+                    profile: Some(name),
+                    ..
+                },
+            )) => {
+                let source = match &home {
+                    Some(home) => path.to_string_lossy().replacen(home.as_str(), "~", 1),
+                    None => path.to_string_lossy().to_string(),
+                };
+                Some((name.clone(), source))
+            }
+            _ => Profile::matching(&self.profiles, cwd)
+                .map(|(name, pattern)| (name.to_string(), pattern.to_string())),
+        };
+        if let Some((name, source)) = selected {
+            self.apply_profile(&name, &source)?;
+        }
+        if let Some(active) = project_file.and_then(|(_, file)| file.active) {
+            self.active = active;
+            self.afk_until = None;
+        }
+        Ok(())
+    }
+
+    /// Replace the top-level settings with those the profile sets
+    pub fn apply_profile(&mut self, name: &str, source: &str) -> Result<(), String> {
+        let profile = self.profiles.get(name).cloned().ok_or_else(|| {
+            format!(
+                "Profile '{}' (from {}) is not defined in the config file",
+                name, source
+            )
+        })?;
+
+        if profile.has_own_device() {
+            self.device_token = profile.device_token;
+            self.token_expires_at = profile.token_expires_at;
+            self.token_rotate_after = profile.token_rotate_after;
+        }
+        if let Some(active) = profile.active {
+            self.active = active;
+            self.afk_until = None;
+        }
+        if profile.idle_threshold.is_some() {
+            self.idle_threshold = profile.idle_threshold;
+        }
+        if profile.decision_timeout.is_some() {
+            self.decision_timeout = profile.decision_timeout;
+        }
+        if let Some(schedule) = profile.schedule {
+            self.schedule = schedule;
+        }
+        if let Some(templates) = profile.templates {
+            self.templates.permission.extend(templates.permission);
+            if templates.idle.is_some() {
+                self.templates.idle = templates.idle;
+            }
+        }
+        self.profile = Some(SelectedProfile {
+            name: name.to_string(),
+            source: source.to_string(),
+        });
+        Ok(())
+    }
+
+    /// The backend the selected profile sets, if any
+    pub fn profile_backend_url(&self) -> Option<&str> {
+        let name = &self.profile.as_ref()?.name;
+        self.profiles.get(name)?.backend_url.as_deref()
+    }
+
+    /// The selected profile if it has its own device token; other profiles
+    /// share the default device and its token
+    pub fn device_profile(&self) -> Option<&str> {
+        let name = &self.profile.as_ref()?.name;
+        self.profiles
+            .get(name)
+            .filter(|profile| profile.has_own_device())
+            .map(|_| name.as_str())
+    }
+
+    /// Store a device token for `profile`, or the default device
+    pub fn set_device_token(
+        &mut self,
+        profile: Option<&str>,
+        device_token: Option<String>,
+        expires_at: Option<u64>,
+        rotate_after: Option<u64>,
+    ) -> Result<(), String> {
+        match profile {
+            Some(name) => {
+                let profile = self
+                    .profiles
+                    .get_mut(name)
+                    .ok_or_else(|| format!("No profile named '{}' in the config file", name))?;
+                profile.device_token = device_token;
+                profile.token_expires_at = expires_at;
+                profile.token_rotate_after = rotate_after;
+            }
+            None => {
+                self.device_token = device_token;
+                self.token_expires_at = expires_at;
+                self.token_rotate_after = rotate_after;
+            }
+        }
+        Ok(())
+    }
+
+    /// The mode in effect at `now` (unix seconds), after a timed `afk` runs out
+    pub fn mode_at(&self, now: u64) -> ActiveMode {
+        match self.afk_until {
+            Some(until) if now < until => ActiveMode::On,
+            _ => self.active,
+        }
+    }
+
+    /// Directory holding the config file and claude-afk's other local state
+    pub fn dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let config_path = confy::get_configuration_file_path(APP_NAME, None)?;
+        let config_dir = config_path
+            .parent()
+            .ok_or("Config path has no parent directory")?;
+        Ok(config_dir.to_path_buf())
+    }
 }
@@ -5,4 +5,39 @@ pub const DEFAULT_API_URL: &str = "https://claude-afk.dev";
 pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
 pub const SETUP_TIMEOUT: Duration = Duration::from_secs(300); // 5 minutes
 pub const DECISION_TIMEOUT: Duration = Duration::from_secs(120); // 2 minutes
+/// The backend keeps a prompt open for at most this long, so a longer
+/// `decision_timeout` could never be waited out
+pub const MAX_DECISION_TIMEOUT: Duration = Duration::from_secs(600); // 10 minutes
 pub const DECISION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Test-only timing overrides in milliseconds, ignored by release builds
+pub const POLL_INTERVAL_ENV: &str = "CLAUDE_AFK_POLL_INTERVAL_MS";
+pub const SETUP_TIMEOUT_ENV: &str = "CLAUDE_AFK_SETUP_TIMEOUT_MS";
+pub const DECISION_TIMEOUT_ENV: &str = "CLAUDE_AFK_DECISION_TIMEOUT_MS";
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+pub const TOKEN_REJECTED_MESSAGE: &str =
+    "Device pairing has been revoked or has expired. Run `claude-afk pair` to pair again.";
+pub const SETTINGS_BACKUPS_KEPT: usize = 5;
+pub const HOOK_ID: &str = "claude-afk";
+/// How much of the end of a session transcript is read for notification context
+pub const TRANSCRIPT_TAIL_BYTES: u64 = 256 * 1024;
+pub const CONTEXT_PROMPT_CHARS: usize = 280;
+pub const CONTEXT_REASONING_CHARS: usize = 480;
+pub const CONTEXT_SUMMARY_CHARS: usize = 120;
+/// Sessions without hook activity for this long drop out of `claude-afk sessions`
+pub const SESSION_ACTIVE_WINDOW: Duration = Duration::from_secs(60 * 60);
+/// Sessions not seen for this long are removed from the registry
+pub const SESSION_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+pub const REGISTRY_LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+pub const REGISTRY_LOCK_STALE: Duration = Duration::from_secs(10);
+/// Log level filter (off, error, warn, info, debug); overrides `[log] level`
+pub const LOG_ENV: &str = "CLAUDE_AFK_LOG";
+/// debug.log is rotated once it grows past this size, unless `[log] max_bytes` says otherwise
+pub const LOG_MAX_BYTES: u64 = 1024 * 1024;
+/// Rotated logs kept next to debug.log (debug.log.1 … debug.log.3)
+pub const LOG_FILES_KEPT: usize = 3;
+pub const LOG_FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+/// How long the desktop must be idle before `auto` mode sends notifications
+pub const DEFAULT_IDLE_THRESHOLD: Duration = Duration::from_secs(5 * 60);
+/// Per-project file naming a profile or switching notifications, looked up
+/// from the session's working directory upwards
+pub const PROJECT_FILE: &str = ".claude-afk.toml";
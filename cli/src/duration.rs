@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+/// Short durations as typed on the command line or in the config file, such
+/// as "90s", "5m", "2h", "1d" or "2w"
+pub struct HumanDuration;
+
+impl HumanDuration {
+    pub fn parse(value: &str) -> Option<Duration> {
+        let value = value.trim();
+        let unit = value.chars().last().filter(char::is_ascii_alphabetic)?;
+        let amount: u64 = value[..value.len() - 1].trim_end().parse().ok()?;
+        let secs = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+        amount.checked_mul(secs).map(Duration::from_secs)
+    }
+
+    /// For clap's `value_parser`
+    pub fn parse_arg(value: &str) -> Result<Duration, String> {
+        Self::parse(value)
+            .ok_or_else(|| format!("'{}' is not a duration like 90s, 5m, 2h or 1d", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(HumanDuration::parse("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(HumanDuration::parse("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(
+            HumanDuration::parse(" 2h "),
+            Some(Duration::from_secs(7200))
+        );
+        assert_eq!(
+            HumanDuration::parse("1w"),
+            Some(Duration::from_secs(7 * 86400))
+        );
+        assert_eq!(HumanDuration::parse("5"), None);
+        assert_eq!(HumanDuration::parse("5y"), None);
+        assert_eq!(HumanDuration::parse("-5m"), None);
+        assert_eq!(HumanDuration::parse("m"), None);
+    }
+
+    #[test]
+    fn test_parse_arg_error() {
+        assert_eq!(
+            HumanDuration::parse_arg("soon").unwrap_err(),
+            "'soon' is not a duration like 90s, 5m, 2h or 1d"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt;
+
+use crate::constants::TOKEN_REJECTED_MESSAGE;
+
+/// Why pairing did not complete. Each case has its own exit code so
+/// provisioning scripts can tell them apart.
+#[derive(Debug)]
+pub enum PairError {
+    /// Nobody completed the pairing within SETUP_TIMEOUT, or before the
+    /// backend expired it
+    Timeout,
+    /// The backend doesn't know the pairing session
+    Rejected(String),
+    /// The backend could not be reached or returned an unexpected response
+    Network(String),
+}
+
+impl PairError {
+    pub const TIMEOUT_EXIT_CODE: i32 = 3;
+    pub const REJECTED_EXIT_CODE: i32 = 4;
+    pub const NETWORK_EXIT_CODE: i32 = 5;
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PairError::Timeout => Self::TIMEOUT_EXIT_CODE,
+            PairError::Rejected(_) => Self::REJECTED_EXIT_CODE,
+            PairError::Network(_) => Self::NETWORK_EXIT_CODE,
+        }
+    }
+
+    /// Short machine-readable name used in `pair --json` output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PairError::Timeout => "timeout",
+            PairError::Rejected(_) => "rejected",
+            PairError::Network(_) => "network",
+        }
+    }
+}
+
+impl fmt::Display for PairError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PairError::Timeout => write!(f, "Pairing timed out"),
+            PairError::Rejected(reason) => write!(f, "Pairing rejected: {}", reason),
+            PairError::Network(reason) => write!(f, "Network error during pairing: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PairError {}
+
+impl From<ureq::Error> for PairError {
+    fn from(e: ureq::Error) -> Self {
+        match e {
+            ureq::Error::StatusCode(404) => PairError::Rejected("pairing session not found".into()),
+            // The pairing ran out of time, however long this process waited
+            ureq::Error::StatusCode(410) => PairError::Timeout,
+            other => PairError::Network(other.to_string()),
+        }
+    }
+}
+
+/// How a hook invocation ends when it doesn't print a decision, following
+/// Claude Code's hook exit-code contract:
+///
+/// - `0`: success. stdout is parsed as hook output; without output Claude
+///   Code carries on as if the hook weren't installed.
+/// - `2`: blocking error. stderr is fed back to Claude and a
+///   PermissionRequest is denied.
+/// - anything else: non-blocking error. stderr is shown to the user and
+///   Claude Code asks in the terminal as usual.
+///
+/// claude-afk never exits with `2`: failing to reach the phone must only
+/// fall back to the regular prompt, never deny a tool call.
+#[derive(Debug)]
+pub enum HookError {
+    /// Nothing for claude-afk to do: not paired, notifications off, an
+    /// ignored event or a dismissed request. The note, if any, goes to stderr.
+    Fallback(Option<String>),
+    /// The hook could not do its job; the message goes to stderr
+    Failed(String),
+}
+
+impl HookError {
+    pub const FALLBACK_EXIT_CODE: i32 = 0;
+    pub const FAILED_EXIT_CODE: i32 = 1;
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            HookError::Fallback(_) => Self::FALLBACK_EXIT_CODE,
+            HookError::Failed(_) => Self::FAILED_EXIT_CODE,
+        }
+    }
+
+    /// What to write to stderr
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            HookError::Fallback(note) => note.as_deref(),
+            HookError::Failed(message) => Some(message),
+        }
+    }
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookError::Fallback(None) => write!(f, "Falling back to the terminal prompt"),
+            HookError::Fallback(Some(note)) => write!(f, "{}", note),
+            HookError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// Why a permission request forwarded to the phone got no decision
+#[derive(Debug)]
+pub enum DecisionError {
+    /// Nobody answered before the decision timeout, or the backend expired
+    /// the request first
+    TimedOut,
+    /// Answered at the terminal first; the phone prompt was retracted
+    Cancelled,
+    /// The backend no longer accepts the device token
+    TokenRejected,
+    /// The backend could not be reached or refused the request
+    Network(String),
+    /// The backend answered with something claude-afk doesn't understand
+    Protocol(String),
+}
+
+impl fmt::Display for DecisionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecisionError::TimedOut => write!(f, "Decision timed out"),
+            DecisionError::Cancelled => write!(f, "Answered at the terminal"),
+            DecisionError::TokenRejected => write!(f, "{}", TOKEN_REJECTED_MESSAGE),
+            DecisionError::Network(reason) => write!(f, "{}", reason),
+            DecisionError::Protocol(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for DecisionError {}
+
+impl From<DecisionError> for HookError {
+    fn from(e: DecisionError) -> Self {
+        match e {
+            // Claude Code has moved on, so there is nothing left to say
+            DecisionError::Cancelled => HookError::Fallback(None),
+            // The user should hear why the phone stayed silent
+            DecisionError::TimedOut
+            | DecisionError::TokenRejected
+            | DecisionError::Network(_)
+            | DecisionError::Protocol(_) => HookError::Failed(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for HookError {
+    fn from(e: std::io::Error) -> Self {
+        HookError::Failed(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pair_error_exit_codes_are_distinct() {
+        let codes = [
+            PairError::Timeout.exit_code(),
+            PairError::Rejected("expired".into()).exit_code(),
+            PairError::Network("connection refused".into()).exit_code(),
+        ];
+        assert_eq!(codes, [3, 4, 5]);
+    }
+
+    #[test]
+    fn test_pair_error_from_status_codes() {
+        assert!(matches!(
+            PairError::from(ureq::Error::StatusCode(404)),
+            PairError::Rejected(_)
+        ));
+        assert!(matches!(
+            PairError::from(ureq::Error::StatusCode(410)),
+            PairError::Timeout
+        ));
+        assert!(matches!(
+            PairError::from(ureq::Error::StatusCode(500)),
+            PairError::Network(_)
+        ));
+        assert!(matches!(
+            PairError::from(ureq::Error::ConnectionFailed),
+            PairError::Network(_)
+        ));
+    }
+
+    #[test]
+    fn test_pair_error_kind() {
+        assert_eq!(PairError::Timeout.kind(), "timeout");
+        assert_eq!(PairError::Rejected(String::new()).kind(), "rejected");
+        assert_eq!(PairError::Network(String::new()).kind(), "network");
+    }
+
+    #[test]
+    fn test_hook_error_exit_codes() {
+        assert_eq!(HookError::Fallback(None).exit_code(), 0);
+        assert_eq!(HookError::Fallback(Some("note".into())).exit_code(), 0);
+        assert_eq!(HookError::Failed("boom".into()).exit_code(), 1);
+    }
+
+    #[test]
+    fn test_hook_error_never_blocks() {
+        // Exit code 2 would deny the tool call in Claude Code
+        for error in [
+            HookError::Fallback(None),
+            HookError::Failed("Decision timed out".into()),
+        ] {
+            assert_ne!(error.exit_code(), 2);
+        }
+    }
+
+    #[test]
+    fn test_decision_error_exit_codes() {
+        let cases = [
+            (DecisionError::Cancelled, 0),
+            (DecisionError::TimedOut, 1),
+            (DecisionError::TokenRejected, 1),
+            (DecisionError::Network("connection refused".into()), 1),
+            (DecisionError::Protocol("Unknown decision".into()), 1),
+        ];
+        for (error, code) in cases {
+            assert_eq!(HookError::from(error).exit_code(), code);
+        }
+        assert_eq!(
+            HookError::from(DecisionError::TokenRejected).message(),
+            Some(TOKEN_REJECTED_MESSAGE)
+        );
+    }
+
+    #[test]
+    fn test_hook_error_message() {
+        assert_eq!(HookError::Fallback(None).message(), None);
+        assert_eq!(
+            HookError::Fallback(Some("token revoked".into())).message(),
+            Some("token revoked")
+        );
+        assert_eq!(
+            HookError::Failed("Decision timed out".into()).message(),
+            Some("Decision timed out")
+        );
+    }
+}
@@ -1,38 +1,123 @@
-#![allow(unused)]
-
-#[cfg(debug_assertions)]
-use std::fs::OpenOptions;
-#[cfg(debug_assertions)]
-use std::io::Write;
-#[cfg(debug_assertions)]
-use std::path::PathBuf;
-#[cfg(debug_assertions)]
-use std::sync::OnceLock;
-#[cfg(debug_assertions)]
-use std::time::SystemTime;
-
-#[cfg(debug_assertions)]
-use crate::constants::APP_NAME;
-
-#[cfg(debug_assertions)]
-static LOG_FILE_PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
-
-/// Get the path to the log file (same directory as config file)
-#[cfg(debug_assertions)]
-fn get_log_file_path() -> Option<PathBuf> {
-    LOG_FILE_PATH
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::SystemTime,
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    constants::{APP_NAME, LOG_ENV, LOG_FILES_KEPT, LOG_MAX_BYTES},
+};
+
+/// How much goes to debug.log. Each level includes the ones above it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        <Self as ValueEnum>::from_str(value.trim(), true).ok()
+    }
+
+    /// Debug builds log everything; release builds only log when asked to
+    fn build_default() -> Self {
+        if cfg!(debug_assertions) {
+            LogLevel::Debug
+        } else {
+            LogLevel::Off
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `[2026-03-01 10:00:00] INFO - message`
+    #[default]
+    Text,
+    /// One JSON object per line, for log shippers
+    Json,
+}
+
+/// The `[log]` section of the config file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LogSettings {
+    /// Defaults to debug in debug builds and off in release builds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<LogLevel>,
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Size at which debug.log is rotated
+    #[serde(default = "LogSettings::default_max_bytes")]
+    pub max_bytes: u64,
+}
+
+impl LogSettings {
+    fn default_max_bytes() -> u64 {
+        LOG_MAX_BYTES
+    }
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings {
+            level: None,
+            format: LogFormat::Text,
+            max_bytes: LOG_MAX_BYTES,
+        }
+    }
+}
+
+/// Where and how this process logs, resolved once on first use
+#[derive(Debug)]
+struct LogSink {
+    path: PathBuf,
+    level: LogLevel,
+    format: LogFormat,
+    max_bytes: u64,
+}
+
+static LOG_SINK: OnceLock<Option<LogSink>> = OnceLock::new();
+
+fn log_sink() -> Option<&'static LogSink> {
+    LOG_SINK
         .get_or_init(|| {
-            confy::get_configuration_file_path(APP_NAME, None)
-                .ok()
-                .and_then(|config_path| config_path.parent().map(|p| p.to_path_buf()))
-                .map(|dir| dir.join("debug.log"))
+            let path = Logger::log_file_path()?;
+            let settings = Logger::settings();
+            let level = Logger::level(std::env::var(LOG_ENV).ok().as_deref(), settings.level);
+            Some(LogSink {
+                path,
+                level,
+                format: settings.format,
+                max_bytes: settings.max_bytes,
+            })
         })
-        .clone()
+        .as_ref()
 }
 
-/// Format a timestamp from SystemTime
-#[cfg(debug_assertions)]
-fn format_timestamp() -> String {
+/// Current UTC time as (year, month, day, hours, minutes, seconds)
+fn utc_now() -> (u64, u64, u64, u64, u64, u64) {
     let now = SystemTime::now();
     let duration = now
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -49,14 +134,10 @@ fn format_timestamp() -> String {
     // Calculate date from days since epoch (1970-01-01)
     let (year, month, day) = days_to_date(days_since_epoch);
 
-    format!(
-        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
-        year, month, day, hours, minutes, seconds
-    )
+    (year, month, day, hours, minutes, seconds)
 }
 
 /// Convert days since Unix epoch to (year, month, day)
-#[cfg(debug_assertions)]
 fn days_to_date(days: u64) -> (u64, u64, u64) {
     let mut days = days as i64;
     let mut year = 1970i64;
@@ -89,83 +170,184 @@ fn days_to_date(days: u64) -> (u64, u64, u64) {
     (year as u64, month as u64, (days + 1) as u64)
 }
 
-#[cfg(debug_assertions)]
 fn is_leap_year(year: i64) -> bool {
     (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0)
 }
 
-/// Write a log entry to the file (debug builds only)
-#[cfg(debug_assertions)]
-fn write_log(level: &str, message: &str) {
-    let Some(path) = get_log_file_path() else {
+/// Append one entry if `level` passes the sink's filter, rotating first when
+/// the file is full. Logging never fails the caller.
+fn write_log(level: LogLevel, message: &str) {
+    if let Some(sink) = log_sink() {
+        write_entry(sink, level, message);
+    }
+}
+
+fn write_entry(sink: &LogSink, level: LogLevel, message: &str) {
+    if level == LogLevel::Off || level > sink.level {
         return;
-    };
+    }
 
-    // Ensure parent directory exists
-    if let Some(parent) = path.parent() {
-        let _ = std::fs::create_dir_all(parent);
+    if let Some(parent) = sink.path.parent() {
+        let _ = fs::create_dir_all(parent);
     }
+    rotate(&sink.path, sink.max_bytes);
 
-    let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) else {
+    let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&sink.path)
+    else {
         return;
     };
+    // One write per line keeps concurrent hook processes from interleaving
+    let _ = file.write_all(format_entry(sink.format, level, message).as_bytes());
+}
 
-    let timestamp = format_timestamp();
-    let _ = writeln!(file, "[{}] {} - {}", timestamp, level, message);
+fn format_entry(format: LogFormat, level: LogLevel, message: &str) -> String {
+    let (year, month, day, hours, minutes, seconds) = utc_now();
+    match format {
+        LogFormat::Text => format!(
+            "[{:04}-{:02}-{:02} {:02}:{:02}:{:02}] {} - {}\n",
+            year,
+            month,
+            day,
+            hours,
+            minutes,
+            seconds,
+            level.as_str().to_uppercase(),
+            message
+        ),
+        LogFormat::Json => {
+            let entry = serde_json::json!({
+                "time": format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                    year, month, day, hours, minutes, seconds
+                ),
+                "level": level.as_str(),
+                "pid": std::process::id(),
+                "message": message,
+            });
+            format!("{}\n", entry)
+        }
+    }
 }
 
-/// No-op in release builds
-#[cfg(not(debug_assertions))]
-fn write_log(_level: &str, _message: &str) {}
+/// Shift debug.log → debug.log.1 → … once debug.log reaches `max_bytes`,
+/// dropping the oldest
+fn rotate(path: &Path, max_bytes: u64) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+    if max_bytes == 0 || metadata.len() < max_bytes {
+        return;
+    }
+
+    let rotated = Logger::rotated_paths(path);
+    let _ = fs::remove_file(&rotated[LOG_FILES_KEPT - 1]);
+    for i in (1..LOG_FILES_KEPT).rev() {
+        let _ = fs::rename(&rotated[i - 1], &rotated[i]);
+    }
+    let _ = fs::rename(path, &rotated[0]);
+}
 
 pub struct Logger;
 
 impl Logger {
-    /// Log a debug message (only in debug builds)
     pub fn debug(message: &str) {
-        write_log("DEBUG", message);
+        write_log(LogLevel::Debug, message);
     }
 
-    /// Log an info message (only in debug builds)
     pub fn info(message: &str) {
-        write_log("INFO", message);
+        write_log(LogLevel::Info, message);
     }
 
-    /// Log a warning message (only in debug builds)
     pub fn warn(message: &str) {
-        write_log("WARN", message);
+        write_log(LogLevel::Warn, message);
     }
 
-    /// Log an error message (only in debug builds)
     pub fn error(message: &str) {
-        write_log("ERROR", message);
+        write_log(LogLevel::Error, message);
     }
 
-    /// Get the path to the log file (debug builds only)
-    #[cfg(debug_assertions)]
-    pub fn log_file_path() -> Option<PathBuf> {
+    /// Level in effect: `CLAUDE_AFK_LOG` when it names a level, then the
+    /// config file, then the build default
+    pub fn level(env: Option<&str>, configured: Option<LogLevel>) -> LogLevel {
+        env.and_then(LogLevel::parse)
+            .or(configured)
+            .unwrap_or_else(LogLevel::build_default)
+    }
+
+    /// The `[log]` settings from the config file. The file is only read, never
+    /// created, so logging has no side effects on a fresh install.
+    pub fn settings() -> LogSettings {
         confy::get_configuration_file_path(APP_NAME, None)
             .ok()
-            .and_then(|config_path| config_path.parent().map(|p| p.to_path_buf()))
-            .map(|dir| dir.join("debug.log"))
+            .filter(|path| path.exists())
+            .and_then(|path| confy::load_path::<Config>(path).ok())
+            .map(|config| config.log)
+            .unwrap_or_default()
+    }
+
+    /// debug.log, next to the config file
+    pub fn log_file_path() -> Option<PathBuf> {
+        Config::dir().ok().map(|dir| dir.join("debug.log"))
     }
 
-    /// Clear all logs from the log file (debug builds only)
-    #[cfg(debug_assertions)]
-    pub fn clear_logs() -> Result<(), std::io::Error> {
-        if let Some(path) = Self::log_file_path() {
-            if path.exists() {
-                std::fs::remove_file(&path)?;
+    /// debug.log.1 … debug.log.N, newest first
+    pub fn rotated_paths(path: &Path) -> Vec<PathBuf> {
+        (1..=LOG_FILES_KEPT)
+            .map(|i| {
+                let mut name = path.as_os_str().to_os_string();
+                name.push(format!(".{}", i));
+                PathBuf::from(name)
+            })
+            .collect()
+    }
+
+    /// The last `lines` lines of the log, oldest first
+    pub fn tail(path: &Path, lines: usize) -> Result<Vec<String>, std::io::Error> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let all: Vec<&str> = content.lines().collect();
+        Ok(all[all.len().saturating_sub(lines)..]
+            .iter()
+            .map(|line| line.to_string())
+            .collect())
+    }
+
+    /// Remove debug.log and its rotated files, returning how many were removed
+    pub fn clear_logs(path: &Path) -> Result<usize, std::io::Error> {
+        let mut removed = 0;
+        for file in std::iter::once(path.to_path_buf()).chain(Self::rotated_paths(path)) {
+            match fs::remove_file(&file) {
+                Ok(()) => removed += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
             }
         }
-        Ok(())
+        Ok(removed)
     }
 }
 
-#[cfg(all(test, debug_assertions))]
+#[cfg(test)]
 mod tests {
     use super::*;
 
+    fn temp_sink(name: &str, level: LogLevel, format: LogFormat, max_bytes: u64) -> LogSink {
+        let dir =
+            std::env::temp_dir().join(format!("claude-afk-log-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        LogSink {
+            path: dir.join("debug.log"),
+            level,
+            format,
+            max_bytes,
+        }
+    }
+
     #[test]
     fn test_days_to_date() {
         // 1970-01-01
@@ -185,4 +367,108 @@ mod tests {
         assert!(!is_leap_year(1900));
         assert!(is_leap_year(2024));
     }
+
+    #[test]
+    fn test_level_precedence() {
+        assert_eq!(
+            Logger::level(Some("warn"), Some(LogLevel::Debug)),
+            LogLevel::Warn
+        );
+        assert_eq!(Logger::level(Some("INFO"), None), LogLevel::Info);
+        // An unknown env value falls through to the config file
+        assert_eq!(
+            Logger::level(Some("loud"), Some(LogLevel::Error)),
+            LogLevel::Error
+        );
+        assert_eq!(Logger::level(None, None), LogLevel::build_default());
+    }
+
+    #[test]
+    fn test_level_filter() {
+        let sink = temp_sink("filter", LogLevel::Warn, LogFormat::Text, LOG_MAX_BYTES);
+
+        write_entry(&sink, LogLevel::Debug, "hidden");
+        write_entry(&sink, LogLevel::Info, "hidden");
+        write_entry(&sink, LogLevel::Warn, "careful");
+        write_entry(&sink, LogLevel::Error, "broken");
+        let lines = Logger::tail(&sink.path, 10).unwrap();
+        fs::remove_dir_all(sink.path.parent().unwrap()).unwrap();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("] WARN - careful"));
+        assert!(lines[1].ends_with("] ERROR - broken"));
+    }
+
+    #[test]
+    fn test_off_writes_nothing() {
+        let sink = temp_sink("off", LogLevel::Off, LogFormat::Text, LOG_MAX_BYTES);
+
+        write_entry(&sink, LogLevel::Error, "nope");
+
+        assert!(!sink.path.exists());
+    }
+
+    #[test]
+    fn test_json_format() {
+        let sink = temp_sink("json", LogLevel::Debug, LogFormat::Json, LOG_MAX_BYTES);
+
+        write_entry(&sink, LogLevel::Info, "Device token rotated");
+        let lines = Logger::tail(&sink.path, 10).unwrap();
+        fs::remove_dir_all(sink.path.parent().unwrap()).unwrap();
+
+        let entry: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(entry["level"], "info");
+        assert_eq!(entry["message"], "Device token rotated");
+        assert_eq!(entry["pid"], std::process::id());
+        assert!(entry["time"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn test_rotation_keeps_a_bounded_number_of_files() {
+        let sink = temp_sink("rotate", LogLevel::Debug, LogFormat::Text, 64);
+
+        for i in 0..40 {
+            write_entry(&sink, LogLevel::Debug, &format!("message number {}", i));
+        }
+        let rotated = Logger::rotated_paths(&sink.path);
+        let current = fs::metadata(&sink.path).unwrap().len();
+        let newest = Logger::tail(&sink.path, 1).unwrap();
+        let all_rotated_exist = rotated.iter().all(|p| p.exists());
+        let overflow = PathBuf::from(format!("{}.{}", sink.path.display(), LOG_FILES_KEPT + 1));
+        let removed = Logger::clear_logs(&sink.path).unwrap();
+        let left = fs::read_dir(sink.path.parent().unwrap()).unwrap().count();
+        fs::remove_dir_all(sink.path.parent().unwrap()).unwrap();
+
+        assert!(current < 64 + 64);
+        assert_eq!(newest.len(), 1);
+        assert!(newest[0].ends_with("message number 39"));
+        assert!(all_rotated_exist);
+        assert!(!overflow.exists());
+        assert_eq!(removed, LOG_FILES_KEPT + 1);
+        assert_eq!(left, 0);
+    }
+
+    #[test]
+    fn test_tail() {
+        let sink = temp_sink("tail", LogLevel::Debug, LogFormat::Text, LOG_MAX_BYTES);
+        fs::create_dir_all(sink.path.parent().unwrap()).unwrap();
+        fs::write(&sink.path, "one\ntwo\nthree\n").unwrap();
+
+        let last_two = Logger::tail(&sink.path, 2).unwrap();
+        let all = Logger::tail(&sink.path, 10).unwrap();
+        fs::remove_dir_all(sink.path.parent().unwrap()).unwrap();
+
+        assert_eq!(last_two, vec!["two", "three"]);
+        assert_eq!(all.len(), 3);
+        assert!(Logger::tail(&sink.path, 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_settings_deserialize_with_defaults() {
+        let settings: LogSettings =
+            serde_json::from_value(serde_json::json!({"format": "json"})).unwrap();
+        assert_eq!(settings.level, None);
+        assert_eq!(settings.format, LogFormat::Json);
+        assert_eq!(settings.max_bytes, LOG_MAX_BYTES);
+    }
 }
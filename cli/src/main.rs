@@ -1,17 +1,47 @@
+mod audit;
 mod cmd;
 mod config;
 mod constants;
+mod duration;
+mod error;
 mod logger;
 mod models;
+mod presence;
+mod profile;
+mod qr;
+mod redact;
+mod registry;
+mod replay;
+mod schedule;
+mod session;
+mod settings;
+mod templates;
+mod timestamp;
+mod transcript;
+
+use std::{path::PathBuf, time::Duration};
 
 use std::io::IsTerminal;
 
 use clap::{Parser, Subcommand};
 
-use crate::cmd::Cmd;
+use crate::{
+    audit::AuditDecision,
+    cmd::Cmd,
+    duration::HumanDuration,
+    error::{HookError, PairError},
+    logger::Logger,
+    models::TestTool,
+    qr::QrFormat,
+    settings::{HookEvent, HookScope},
+};
 
 #[derive(Parser)]
-#[command(name = "claude-afk", about = "Push notifications for Claude Code")]
+#[command(
+    name = "claude-afk",
+    version,
+    about = "Push notifications for Claude Code"
+)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
@@ -19,39 +49,201 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Set up device pairing by scanning a QR code
-    Pair,
+    /// Set up device pairing by scanning a QR code.
+    /// Exit codes: 3 = timed out, 4 = rejected/expired, 5 = network error
+    Pair {
+        /// QR code format (defaults to unicode, or ascii when the terminal can't show it)
+        #[arg(long, value_enum)]
+        qr_format: Option<QrFormat>,
+        /// Write the QR code to a file instead of the terminal
+        #[arg(long, value_name = "PATH")]
+        qr_output: Option<PathBuf>,
+        /// Print the pairing URL and id as a JSON line and exit without waiting
+        #[arg(long)]
+        json: bool,
+        /// Wait for a pairing started earlier with --json to complete
+        #[arg(long, value_name = "PAIRING_ID")]
+        wait: Option<String>,
+        /// Pair a device for this profile from the config file instead of the default one
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+    },
     /// Send a notification (accepts JSON as argument or reads from stdin).
     /// The command used by Claude Code hooks
     Notify {
         /// JSON input (if not provided, reads from stdin)
         json: Option<String>,
+        /// Marker identifying hooks installed by claude-afk (ignored)
+        #[arg(long, value_name = "ID")]
+        hook_id: Option<String>,
     },
     /// Show current configuration status
-    Status,
+    Status {
+        /// Print the status as a JSON object
+        #[arg(long)]
+        json: bool,
+        /// Contact the backend to verify the token and push subscription
+        #[arg(long)]
+        check: bool,
+    },
     /// Enable notifications
-    Activate,
+    Activate {
+        /// Turn notifications off again after this long, e.g. 2h or 45m
+        #[arg(long = "for", value_name = "DURATION", value_parser = HumanDuration::parse_arg)]
+        duration: Option<Duration>,
+        /// Turn notifications off again at this time, e.g. 17:30 or 2026-03-01T08:00
+        #[arg(long, value_name = "TIME", conflicts_with = "duration")]
+        until: Option<String>,
+    },
     /// Alias for Activate
-    Afk,
+    Afk {
+        /// Turn notifications off again after this long, e.g. 2h or 45m
+        #[arg(long = "for", value_name = "DURATION", value_parser = HumanDuration::parse_arg)]
+        duration: Option<Duration>,
+        /// Turn notifications off again at this time, e.g. 17:30 or 2026-03-01T08:00
+        #[arg(long, value_name = "TIME", conflicts_with = "duration")]
+        until: Option<String>,
+    },
     /// Disable notifications
     Deactivate,
     /// Alias for Deactivate
     Back,
+    /// Enable notifications only while the screen is locked or the desktop is idle
+    Auto {
+        /// Idle time after which you count as away, e.g. 5m (default) or 90s
+        #[arg(long, value_name = "DURATION", value_parser = HumanDuration::parse_arg)]
+        idle_after: Option<Duration>,
+    },
     /// Clear device pairing
     Clear,
+    /// Replace the device token with a freshly issued one
+    RotateToken,
     /// Install Claude Code hooks for push notifications
-    InstallHooks,
-    /// Clear all debug logs (debug builds only)
-    #[cfg(debug_assertions)]
-    ClearLogs,
+    InstallHooks {
+        /// Settings file to install into
+        #[arg(long, value_enum, default_value = "user")]
+        scope: HookScope,
+        /// Hook events to install, comma separated (default: all)
+        #[arg(long = "event", value_enum, value_delimiter = ',')]
+        events: Vec<HookEvent>,
+        /// Tools that trigger permission notifications, e.g. "Bash|Write|Edit"
+        #[arg(long, default_value = "*")]
+        tools: String,
+        /// Print a diff of the settings file instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remove claude-afk hooks from Claude Code settings
+    UninstallHooks {
+        /// Settings file to remove hooks from
+        #[arg(long, value_enum, default_value = "user")]
+        scope: HookScope,
+    },
+    /// Send a sample permission request to your phone and show the resulting hook output
+    Test {
+        /// Tool the sample request asks permission for
+        #[arg(long, value_enum, default_value = "bash")]
+        tool: TestTool,
+    },
+    /// Show the notification text your templates produce, without sending anything
+    Preview {
+        /// Tool the sample request asks permission for
+        #[arg(long, value_enum, default_value = "bash")]
+        tool: TestTool,
+        /// Preview the idle "Claude is waiting" notification instead
+        #[arg(long)]
+        idle: bool,
+        /// A recorded hook payload (see `claude-afk record`) to preview instead of a sample
+        #[arg(conflicts_with_all = ["idle", "tool"])]
+        file: Option<PathBuf>,
+    },
+    /// Diagnose why notifications aren't arriving
+    Doctor {
+        /// Don't send a test push notification
+        #[arg(long)]
+        skip_push: bool,
+    },
+    /// Save every hook payload Claude Code sends (redacted) for later replay
+    Record {
+        /// Directory for recorded fixtures (defaults to the config directory)
+        #[arg(long, value_name = "DIR", conflicts_with = "off")]
+        dir: Option<PathBuf>,
+        /// Stop recording
+        #[arg(long)]
+        off: bool,
+    },
+    /// List active Claude Code sessions and the prompts they're waiting on
+    Sessions {
+        /// Include sessions without recent activity
+        #[arg(long)]
+        all: bool,
+        /// Print the sessions as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show permission requests answered remotely, from the local audit log
+    History {
+        /// Only entries newer than an age (30m, 12h, 7d, 2w), a date or an RFC 3339 timestamp
+        #[arg(long, value_name = "WHEN")]
+        since: Option<String>,
+        /// Only requests for this tool, e.g. Bash
+        #[arg(long)]
+        tool: Option<String>,
+        /// Only requests with this outcome
+        #[arg(long, value_enum)]
+        decision: Option<AuditDecision>,
+        /// Only sessions whose id starts with, or label contains, this text
+        #[arg(long)]
+        session: Option<String>,
+        /// Print matching entries as JSON lines
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show how a recorded hook payload is parsed and handled, offline
+    Replay {
+        /// Recorded hook JSON file
+        file: PathBuf,
+    },
+    /// Show, follow or clear the log file (debug.log).
+    /// Logging is off in release builds unless CLAUDE_AFK_LOG or [log] level is set
+    Logs {
+        /// Number of recent lines to show
+        #[arg(short = 'n', long, default_value_t = 50)]
+        lines: usize,
+        /// Keep printing new entries as they are written
+        #[arg(short, long)]
+        follow: bool,
+        /// Delete debug.log and its rotated files
+        #[arg(long, conflicts_with_all = ["follow", "path"])]
+        clear: bool,
+        /// Print the log file path and exit
+        #[arg(long, conflicts_with = "follow")]
+        path: bool,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
 
     if let Err(e) = run(cli) {
+        // Hook invocations follow Claude Code's exit-code contract
+        if let Some(hook_error) = e.downcast_ref::<HookError>() {
+            if let Some(message) = hook_error.message() {
+                match hook_error {
+                    HookError::Failed(_) => Logger::error(message),
+                    HookError::Fallback(_) => Logger::warn(message),
+                }
+                eprintln!("{}", message);
+            }
+            std::process::exit(hook_error.exit_code());
+        }
+
         eprintln!("Error: {}", e);
-        std::process::exit(1);
+        let code = e
+            .downcast_ref::<PairError>()
+            .map(PairError::exit_code)
+            .unwrap_or(1);
+        std::process::exit(code);
     }
 }
 
@@ -60,7 +252,7 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         // No subcommand: default to notify if stdin is piped, otherwise show help
         None => {
             if !std::io::stdin().is_terminal() {
-                Cmd::notify(None)
+                Ok(Cmd::notify(None)?)
             } else {
                 // Re-parse with --help to show usage
                 use clap::CommandFactory;
@@ -69,14 +261,47 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 Ok(())
             }
         }
-        Some(Commands::Pair) => Cmd::pair(),
-        Some(Commands::Notify { json }) => Cmd::notify(json),
-        Some(Commands::Status) => Cmd::status(),
-        Some(Commands::Activate) | Some(Commands::Afk) => Cmd::activate(),
+        Some(Commands::Pair {
+            qr_format,
+            qr_output,
+            json,
+            wait,
+            profile,
+        }) => Cmd::pair(qr_format, qr_output, json, wait, profile),
+        Some(Commands::Notify { json, .. }) => Ok(Cmd::notify(json)?),
+        Some(Commands::Status { json, check }) => Cmd::status(json, check),
+        Some(Commands::Activate { duration, until }) | Some(Commands::Afk { duration, until }) => {
+            Cmd::activate(duration, until.as_deref())
+        }
         Some(Commands::Deactivate) | Some(Commands::Back) => Cmd::deactivate(),
+        Some(Commands::Auto { idle_after }) => Cmd::auto(idle_after),
         Some(Commands::Clear) => Cmd::clear(),
-        Some(Commands::InstallHooks) => Cmd::install_hooks(),
-        #[cfg(debug_assertions)]
-        Some(Commands::ClearLogs) => Cmd::clear_logs(),
+        Some(Commands::RotateToken) => Cmd::rotate_token(),
+        Some(Commands::InstallHooks {
+            scope,
+            events,
+            tools,
+            dry_run,
+        }) => Cmd::install_hooks(scope, events, tools, dry_run),
+        Some(Commands::UninstallHooks { scope }) => Cmd::uninstall_hooks(scope),
+        Some(Commands::Test { tool }) => Cmd::test(tool),
+        Some(Commands::Preview { tool, idle, file }) => Cmd::preview(tool, idle, file),
+        Some(Commands::Doctor { skip_push }) => Cmd::doctor(skip_push),
+        Some(Commands::Record { dir, off }) => Cmd::record(dir, off),
+        Some(Commands::Sessions { all, json }) => Cmd::sessions(all, json),
+        Some(Commands::History {
+            since,
+            tool,
+            decision,
+            session,
+            json,
+        }) => Cmd::history(since, tool, decision, session, json),
+        Some(Commands::Replay { file }) => Cmd::replay(file),
+        Some(Commands::Logs {
+            lines,
+            follow,
+            clear,
+            path,
+        }) => Cmd::logs(lines, follow, clear, path),
     }
 }
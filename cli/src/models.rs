@@ -1,12 +1,27 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::{
+    config::ActiveMode,
+    presence::Presence,
+    session::SessionInfo,
+    settings::{HookEvent, HookScope},
+};
+
 #[derive(Deserialize)]
 pub struct PairingInitResponse {
     #[serde(rename = "pairingId")]
     pub pairing_id: String,
     #[serde(rename = "pairingToken")]
     pub pairing_token: String,
+    /// Short code that can be typed on the pairing page instead of scanning
+    /// the QR code (absent on older backends)
+    #[serde(rename = "pairingCode", default)]
+    pub pairing_code: Option<String>,
+    /// Unix timestamp (seconds) when the backend gives up on the pairing
+    /// (absent on older backends)
+    #[serde(rename = "pairingExpiresAt", default)]
+    pub pairing_expires_at: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -14,6 +29,33 @@ pub struct PairingStatusResponse {
     pub complete: bool,
     #[serde(rename = "deviceToken")]
     pub device_token: Option<String>,
+    #[serde(rename = "expiresAt", default)]
+    pub expires_at: Option<u64>,
+    #[serde(rename = "rotateAfter", default)]
+    pub rotate_after: Option<u64>,
+    /// While pending: when the pairing expires, as in PairingInitResponse
+    #[serde(rename = "pairingExpiresAt", default)]
+    pub pairing_expires_at: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct TokenRotateResponse {
+    #[serde(rename = "deviceToken")]
+    pub device_token: String,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: u64,
+    #[serde(rename = "rotateAfter")]
+    pub rotate_after: u64,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceStatusResponse {
+    pub valid: bool,
+    /// "active", "missing" or "expired"
+    pub subscription: String,
+    /// The token was replaced by a rotation and only works during its grace period
+    #[serde(default)]
+    pub rotated: bool,
 }
 
 #[derive(Deserialize)]
@@ -28,6 +70,67 @@ pub struct NotifyResponse {
 pub struct DecisionStatusResponse {
     pub status: String,
     pub decision: Option<String>,
+    /// How the decision was made ("notification" or "details"), if decided
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Device that made the decision, e.g. "Chrome on Android"
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+// ==================== Status Structures ====================
+
+/// Result of contacting the backend for `status --check`
+#[derive(Debug, Default, Serialize)]
+pub struct BackendHealth {
+    pub reachable: bool,
+    /// None when the backend could not be asked
+    pub token_valid: Option<bool>,
+    pub subscription: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl BackendHealth {
+    pub fn is_healthy(&self) -> bool {
+        self.reachable
+            && self.token_valid == Some(true)
+            && self.subscription.as_deref() == Some("active")
+    }
+}
+
+/// Machine-readable output of `status --json`
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+    pub paired: bool,
+    pub token_expired: bool,
+    pub token_expires_at: Option<u64>,
+    /// Whether hooks would notify right now
+    pub active: bool,
+    pub mode: ActiveMode,
+    /// Unix timestamp (seconds) until which a timed `afk` overrides `mode`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub afk_until: Option<u64>,
+    /// Profile applied for the current directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Detected idle and lock state, in auto mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence: Option<Presence>,
+    /// Why the schedule holds notifications back right now, e.g.
+    /// "quiet hours (22:00-07:00)"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule_hold: Option<String>,
+    /// Every required event has a claude-afk hook in some scope
+    pub hooks_installed: bool,
+    pub hook_scopes: Vec<HookScope>,
+    /// Events with a claude-afk hook, across all scopes
+    pub hook_events: Vec<HookEvent>,
+    /// Hooks are untagged or point at a binary that no longer exists
+    pub hooks_outdated: bool,
+    pub backend_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check: Option<BackendHealth>,
 }
 
 // ==================== Hook Input Structures ====================
@@ -68,6 +171,21 @@ pub struct NotificationInput {
     pub notification_type: String,
 }
 
+/// Input structure for PostToolUse and PostToolUseFailure hooks, used to
+/// retract phone prompts that were answered at the terminal
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct PostToolUseInput {
+    pub session_id: String,
+    pub cwd: String,
+    pub hook_event_name: String,
+    pub tool_name: String,
+    #[serde(default)]
+    pub tool_input: Value,
+    #[serde(default)]
+    pub tool_use_id: Option<String>,
+}
+
 /// Bash tool input
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -324,6 +442,62 @@ impl HookOutput {
     }
 }
 
+/// Decision made on the phone for a permission request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    Deny,
+    Dismiss,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Decision::Allow => "allow",
+            Decision::Deny => "deny",
+            Decision::Dismiss => "dismiss",
+        }
+    }
+
+    /// Output for Claude Code, or None to let it ask at the terminal as usual
+    pub fn hook_output(self) -> Option<HookOutput> {
+        match self {
+            Decision::Allow => Some(HookOutput::allow()),
+            Decision::Deny => Some(HookOutput::deny(None)),
+            Decision::Dismiss => None,
+        }
+    }
+}
+
+/// A decision made on the phone, with where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteDecision {
+    pub decision: Decision,
+    pub source: Option<String>,
+    pub device: Option<String>,
+}
+
+/// How waiting for the phone ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecisionOutcome {
+    Decided(RemoteDecision),
+    /// No answer before the decision timeout
+    TimedOut,
+    /// Answered at the terminal first; the phone prompt was retracted
+    Cancelled,
+}
+
+/// Tool used for the synthetic permission request sent by `claude-afk test`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TestTool {
+    Bash,
+    Write,
+    Edit,
+    Read,
+    /// A tool claude-afk has no dedicated formatting for
+    Unknown,
+}
+
 // ==================== Notify Payload ====================
 
 #[derive(Debug, Serialize)]
@@ -332,6 +506,23 @@ pub struct NotifyPayload {
     pub message: String,
     pub tool_use_id: String,
     pub session_id: String,
+    pub session: SessionInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<NotifyContext>,
+    /// Seconds the hook waits for an answer; the backend expires the prompt then
+    pub timeout: u64,
+}
+
+/// What the conversation was about when the tool call was made, taken from
+/// the session transcript. `summary` is appended to the notification body,
+/// the rest is shown in the details view.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NotifyContext {
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
 }
 
 /// Simple notification payload for informational notifications (e.g., idle_prompt)
@@ -340,4 +531,6 @@ pub struct NotifyPayload {
 pub struct SimpleNotifyPayload {
     pub title: String,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionInfo>,
 }
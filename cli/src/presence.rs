@@ -0,0 +1,226 @@
+use std::{
+    process::Command,
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+
+/// Whether someone seems to be at this computer, for `active = "auto"`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Presence {
+    /// The session's screen is locked, according to logind
+    pub locked: bool,
+    /// Seconds since the last keyboard or mouse input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_secs: Option<u64>,
+    /// Where the state came from, e.g. "logind, xprintidle"
+    pub source: String,
+}
+
+impl Presence {
+    /// Read the lock state from logind and the idle time from the desktop.
+    /// None when neither is available, e.g. over SSH or in a container.
+    pub fn detect() -> Option<Presence> {
+        let mut sources = Vec::new();
+
+        let logind = Self::run("loginctl", &Self::loginctl_args())
+            .map(|output| Self::parse_logind(&output, SystemTime::now()));
+        if logind.is_some() {
+            sources.push("logind");
+        }
+        let (locked, logind_idle) = logind.unwrap_or_default();
+
+        // Desktop idle time is more precise than logind's IdleHint, which only
+        // flips once the desktop's own idle delay has passed
+        let desktop_idle = [
+            ("mutter", Self::mutter_idle as fn() -> Option<Duration>),
+            ("xprintidle", Self::xprintidle),
+            ("ioreg", Self::ioreg_idle),
+        ]
+        .into_iter()
+        .find_map(|(source, read)| read().map(|idle| (source, idle)));
+        if let Some((source, _)) = desktop_idle {
+            sources.push(source);
+        }
+
+        if sources.is_empty() {
+            return None;
+        }
+        Some(Presence {
+            locked,
+            idle_secs: desktop_idle
+                .map(|(_, idle)| idle)
+                .or(logind_idle)
+                .map(|idle| idle.as_secs()),
+            source: sources.join(", "),
+        })
+    }
+
+    /// Away when the screen is locked or input has been idle for `threshold`
+    pub fn is_away(&self, threshold: Duration) -> bool {
+        self.locked
+            || self
+                .idle_secs
+                .is_some_and(|idle| idle >= threshold.as_secs())
+    }
+
+    fn loginctl_args() -> Vec<String> {
+        let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "self".to_string());
+        [
+            "show-session",
+            &session,
+            "-p",
+            "LockedHint",
+            "-p",
+            "IdleHint",
+            "-p",
+            "IdleSinceHint",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect()
+    }
+
+    /// `loginctl show-session` properties as (locked, idle for). IdleSinceHint
+    /// is a wall-clock timestamp in microseconds.
+    fn parse_logind(output: &str, now: SystemTime) -> (bool, Option<Duration>) {
+        let property = |name: &str| {
+            output.lines().find_map(|line| {
+                line.strip_prefix(name)
+                    .and_then(|rest| rest.strip_prefix('='))
+                    .map(str::trim)
+            })
+        };
+        let locked = property("LockedHint") == Some("yes");
+        let idle = (property("IdleHint") == Some("yes"))
+            .then(|| property("IdleSinceHint")?.parse::<u64>().ok())
+            .flatten()
+            .filter(|&since| since > 0)
+            .and_then(|since| {
+                let since = SystemTime::UNIX_EPOCH + Duration::from_micros(since);
+                now.duration_since(since).ok()
+            });
+        (locked, idle)
+    }
+
+    /// GNOME on Wayland or X11
+    fn mutter_idle() -> Option<Duration> {
+        let output = Self::run(
+            "gdbus",
+            &[
+                "call",
+                "--session",
+                "--dest",
+                "org.gnome.Mutter.IdleMonitor",
+                "--object-path",
+                "/org/gnome/Mutter/IdleMonitor/Core",
+                "--method",
+                "org.gnome.Mutter.IdleMonitor.GetIdletime",
+            ]
+            .map(String::from),
+        )?;
+        Self::parse_mutter(&output)
+    }
+
+    /// `(uint64 12345,)`, in milliseconds
+    fn parse_mutter(output: &str) -> Option<Duration> {
+        let millis = output
+            .trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .trim_end_matches(',')
+            .trim_start_matches("uint64")
+            .trim()
+            .parse()
+            .ok()?;
+        Some(Duration::from_millis(millis))
+    }
+
+    /// Other X11 desktops
+    fn xprintidle() -> Option<Duration> {
+        std::env::var_os("DISPLAY")?;
+        let output = Self::run("xprintidle", &[])?;
+        output.trim().parse().ok().map(Duration::from_millis)
+    }
+
+    /// macOS
+    fn ioreg_idle() -> Option<Duration> {
+        if !cfg!(target_os = "macos") {
+            return None;
+        }
+        let output = Self::run("ioreg", &["-c", "IOHIDSystem", "-d", "4"].map(String::from))?;
+        Self::parse_ioreg(&output)
+    }
+
+    /// `"HIDIdleTime" = 1234567890`, in nanoseconds
+    fn parse_ioreg(output: &str) -> Option<Duration> {
+        output.lines().find_map(|line| {
+            let (_, value) = line.split_once("\"HIDIdleTime\" =")?;
+            value.trim().parse().ok().map(Duration::from_nanos)
+        })
+    }
+
+    /// stdout of a successful command, or None if it is missing or failed
+    fn run(program: &str, args: &[String]) -> Option<String> {
+        Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_logind() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_600);
+
+        let locked = "LockedHint=yes\nIdleHint=no\nIdleSinceHint=0\n";
+        assert_eq!(Presence::parse_logind(locked, now), (true, None));
+
+        let idle = "LockedHint=no\nIdleHint=yes\nIdleSinceHint=1700000000000000\n";
+        assert_eq!(
+            Presence::parse_logind(idle, now),
+            (false, Some(Duration::from_secs(600)))
+        );
+
+        // IdleSinceHint is ignored while the session isn't idle
+        let active = "LockedHint=no\nIdleHint=no\nIdleSinceHint=1700000000000000\n";
+        assert_eq!(Presence::parse_logind(active, now), (false, None));
+    }
+
+    #[test]
+    fn test_parse_desktop_idle_times() {
+        assert_eq!(
+            Presence::parse_mutter("(uint64 42000,)\n"),
+            Some(Duration::from_secs(42))
+        );
+        assert_eq!(Presence::parse_mutter("Error: no such name"), None);
+        assert_eq!(
+            Presence::parse_ioreg(
+                "    | |   \"HIDIdleTime\" = 3000000000\n    | |   \"HIDKeyboardModifierMappingPairs\" = ()"
+            ),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(Presence::parse_ioreg(""), None);
+    }
+
+    #[test]
+    fn test_is_away() {
+        let presence = |locked, idle_secs| Presence {
+            locked,
+            idle_secs,
+            source: "test".to_string(),
+        };
+        let threshold = Duration::from_secs(300);
+
+        assert!(presence(true, Some(0)).is_away(threshold));
+        assert!(presence(false, Some(300)).is_away(threshold));
+        assert!(!presence(false, Some(299)).is_away(threshold));
+        assert!(!presence(false, None).is_away(threshold));
+    }
+}
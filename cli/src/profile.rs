@@ -0,0 +1,224 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{decision_timeout_secs, ActiveMode},
+    constants::PROJECT_FILE,
+    redact::Redact,
+    schedule::Schedule,
+    templates::Templates,
+};
+
+/// Settings that replace the top-level ones for some projects, e.g. a
+/// company backend and a work phone for work repositories
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    /// Working directories the profile applies to, as globs like "~/work/*".
+    /// A pattern that matches a directory also covers everything below it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// A profile with its own backend needs its own `pair --profile`; without
+    /// one it shares the default device unless it is paired separately
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_expires_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_rotate_after: Option<u64>,
+    /// Overrides `afk`, `back` and `auto` for the profile's projects
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<ActiveMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_threshold: Option<u64>,
+    /// Seconds to wait for an answer on the phone
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "decision_timeout_secs"
+    )]
+    pub decision_timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+    /// Added to the top-level templates, replacing those for the same tool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates: Option<Templates>,
+}
+
+/// `.claude-afk.toml` in a project directory. It can only name a profile and
+/// switch notifications, never point prompts at another backend, so a cloned
+/// repository can't redirect them.
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectFile {
+    pub profile: Option<String>,
+    pub active: Option<ActiveMode>,
+}
+
+impl ProjectFile {
+    /// The nearest project file in `cwd` or one of its parents
+    pub fn find(cwd: &Path) -> Result<Option<(PathBuf, ProjectFile)>, String> {
+        let Some(path) = cwd
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_FILE))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let file = toml::from_str(&content)
+            .map_err(|e| format!("Invalid {}: {}", path.display(), e.message()))?;
+        Ok(Some((path, file)))
+    }
+}
+
+impl Profile {
+    /// Whether the profile is paired on its own rather than sharing the
+    /// default device
+    pub fn has_own_device(&self) -> bool {
+        self.backend_url.is_some() || self.device_token.is_some()
+    }
+
+    /// The first profile with a `paths` pattern matching `cwd`, and that pattern
+    pub fn matching<'a>(
+        profiles: &'a BTreeMap<String, Profile>,
+        cwd: &Path,
+    ) -> Option<(&'a str, &'a str)> {
+        let home = Redact::home();
+        profiles.iter().find_map(|(name, profile)| {
+            profile
+                .paths
+                .iter()
+                .find(|pattern| Self::path_matches(pattern, cwd, home.as_deref()))
+                .map(|pattern| (name.as_str(), pattern.as_str()))
+        })
+    }
+
+    fn path_matches(pattern: &str, cwd: &Path, home: Option<&str>) -> bool {
+        let pattern = match (pattern.strip_prefix("~/"), home) {
+            (Some(rest), Some(home)) => format!("{}/{}", home.trim_end_matches('/'), rest),
+            _ => pattern.to_string(),
+        };
+        // Descendants are covered anyway, so "dir/**" is the same as "dir"
+        let pattern = pattern.trim_end_matches("/**").trim_end_matches('/');
+        let pattern: Vec<char> = pattern.chars().collect();
+        cwd.ancestors().any(|dir| {
+            let dir: Vec<char> = dir.to_string_lossy().chars().collect();
+            Self::glob(&pattern, &dir)
+        })
+    }
+
+    /// `*` and `?` match within one path component, `**` across components
+    fn glob(pattern: &[char], text: &[char]) -> bool {
+        match pattern {
+            [] => text.is_empty(),
+            ['*', '*', rest @ ..] => {
+                // "a/**/b" also matches "a/b"
+                rest.strip_prefix(&['/'])
+                    .is_some_and(|r| Self::glob(r, text))
+                    || (0..=text.len()).any(|i| Self::glob(rest, &text[i..]))
+            }
+            ['*', rest @ ..] => {
+                for i in 0..=text.len() {
+                    if Self::glob(rest, &text[i..]) {
+                        return true;
+                    }
+                    if text.get(i) == Some(&'/') {
+                        break;
+                    }
+                }
+                false
+            }
+            ['?', rest @ ..] => {
+                text.first().is_some_and(|c| *c != '/') && Self::glob(rest, &text[1..])
+            }
+            [c, rest @ ..] => text.first() == Some(c) && Self::glob(rest, &text[1..]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, cwd: &str) -> bool {
+        Profile::path_matches(pattern, Path::new(cwd), Some("/home/ada"))
+    }
+
+    #[test]
+    fn test_path_patterns() {
+        assert!(matches("~/work", "/home/ada/work/api/src"));
+        assert!(matches("~/work/*", "/home/ada/work/api"));
+        assert!(matches("~/work/**", "/home/ada/work"));
+        assert!(matches("/srv/repos/acme-*", "/srv/repos/acme-billing/lib"));
+        assert!(matches("/srv/**/acme", "/srv/a/b/acme"));
+        assert!(matches("/srv/**/acme", "/srv/acme"));
+        assert!(matches("/srv/repo?", "/srv/repo1"));
+
+        assert!(!matches("~/work", "/home/ada/workshop"));
+        assert!(!matches("/srv/repos/acme-*", "/srv/repos/other"));
+        assert!(!matches("/srv/*/acme", "/srv/a/b/acme"));
+        assert!(!matches("~/work/*", "/home/bob/work/api"));
+    }
+
+    #[test]
+    fn test_matching_returns_first_profile_and_pattern() {
+        let profiles = BTreeMap::from([
+            (
+                "personal".to_string(),
+                Profile {
+                    paths: vec!["/code/oss/*".to_string()],
+                    ..Default::default()
+                },
+            ),
+            (
+                "work".to_string(),
+                Profile {
+                    paths: vec!["/code/acme".to_string(), "/code/acme-*".to_string()],
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+        assert_eq!(
+            Profile::matching(&profiles, Path::new("/code/acme-web/src")),
+            Some(("work", "/code/acme-*"))
+        );
+        assert_eq!(
+            Profile::matching(&profiles, Path::new("/code/oss/ripgrep")),
+            Some(("personal", "/code/oss/*"))
+        );
+        assert_eq!(Profile::matching(&profiles, Path::new("/tmp")), None);
+    }
+
+    #[test]
+    fn test_project_file_found_in_parent() {
+        let root = std::env::temp_dir().join(format!("claude-afk-project-{}", std::process::id()));
+        let nested = root.join("packages").join("api");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join(PROJECT_FILE), "profile = \"work\"\n").unwrap();
+
+        let found = ProjectFile::find(&nested);
+        fs::write(
+            root.join(PROJECT_FILE),
+            "backend_url = \"https://evil.example\"\n",
+        )
+        .unwrap();
+        let rejected = ProjectFile::find(&nested);
+        fs::remove_dir_all(&root).unwrap();
+
+        let (path, file) = found.unwrap().unwrap();
+        assert_eq!(path, root.join(PROJECT_FILE));
+        assert_eq!(file.profile.as_deref(), Some("work"));
+        assert!(rejected
+            .unwrap_err()
+            .contains("unknown field `backend_url`"));
+    }
+}
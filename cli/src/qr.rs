@@ -0,0 +1,181 @@
+use std::{io::IsTerminal, path::Path};
+
+use clap::ValueEnum;
+use qrcode::{
+    render::{svg, unicode::Dense1x2},
+    QrCode,
+};
+
+/// Output format for the pairing QR code
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum QrFormat {
+    /// Compact unicode half-block rendering
+    Unicode,
+    /// Plain ASCII, for terminals and logs without block characters
+    Ascii,
+    /// SVG image (requires --qr-output)
+    Svg,
+    /// PNG image (requires --qr-output)
+    Png,
+}
+
+impl QrFormat {
+    /// Pick the best text format the current terminal can display
+    pub fn detect() -> Self {
+        let lang = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty());
+        let term = std::env::var("TERM").ok();
+        let ci = std::env::var_os("CI").is_some();
+
+        if Self::supports_unicode(lang.as_deref(), term.as_deref(), ci)
+            && std::io::stdout().is_terminal()
+        {
+            QrFormat::Unicode
+        } else {
+            QrFormat::Ascii
+        }
+    }
+
+    fn supports_unicode(lang: Option<&str>, term: Option<&str>, ci: bool) -> bool {
+        if ci || term == Some("dumb") {
+            return false;
+        }
+
+        // Windows terminals don't advertise a UTF-8 locale but render block characters fine
+        if cfg!(windows) {
+            return true;
+        }
+
+        lang.map(|l| {
+            let l = l.to_ascii_lowercase();
+            l.contains("utf-8") || l.contains("utf8")
+        })
+        .unwrap_or(false)
+    }
+
+    /// Whether the format produces an image file rather than text
+    pub fn is_image(self) -> bool {
+        matches!(self, QrFormat::Svg | QrFormat::Png)
+    }
+}
+
+pub struct Qr;
+
+impl Qr {
+    /// Render the QR code as text for printing or saving
+    pub fn render_text(data: &str, format: QrFormat) -> Result<String, Box<dyn std::error::Error>> {
+        let code = QrCode::new(data)?;
+        let rendered = match format {
+            // Colors are inverted so the code reads correctly on dark terminal backgrounds
+            QrFormat::Unicode => code
+                .render::<Dense1x2>()
+                .dark_color(Dense1x2::Light)
+                .light_color(Dense1x2::Dark)
+                .build(),
+            // Two characters per module keeps the code roughly square. Colors
+            // are inverted so the code reads correctly on dark terminal backgrounds
+            QrFormat::Ascii => code
+                .render::<char>()
+                .module_dimensions(2, 1)
+                .dark_color(' ')
+                .light_color('#')
+                .build(),
+            QrFormat::Svg => code.render::<svg::Color>().min_dimensions(256, 256).build(),
+            QrFormat::Png => return Err("PNG output can only be written to a file".into()),
+        };
+        Ok(rendered)
+    }
+
+    /// Write the QR code to a file in the given format
+    pub fn save(
+        data: &str,
+        format: QrFormat,
+        path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match format {
+            QrFormat::Png => {
+                let image = QrCode::new(data)?
+                    .render::<image::Luma<u8>>()
+                    .min_dimensions(256, 256)
+                    .build();
+                image.save_with_format(path, image::ImageFormat::Png)?;
+            }
+            _ => std::fs::write(path, Self::render_text(data, format)?)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supports_unicode_with_utf8_locale() {
+        assert!(QrFormat::supports_unicode(
+            Some("en_US.UTF-8"),
+            Some("xterm-256color"),
+            false
+        ));
+        assert!(QrFormat::supports_unicode(Some("C.utf8"), None, false));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_no_unicode_without_utf8_locale() {
+        assert!(!QrFormat::supports_unicode(Some("C"), Some("xterm"), false));
+        assert!(!QrFormat::supports_unicode(None, Some("xterm"), false));
+    }
+
+    #[test]
+    fn test_no_unicode_on_dumb_terminal_or_ci() {
+        assert!(!QrFormat::supports_unicode(
+            Some("en_US.UTF-8"),
+            Some("dumb"),
+            false
+        ));
+        assert!(!QrFormat::supports_unicode(Some("en_US.UTF-8"), None, true));
+    }
+
+    #[test]
+    fn test_ascii_render_is_plain_ascii() {
+        let rendered = Qr::render_text("https://example.com/pair/abc", QrFormat::Ascii).unwrap();
+        assert!(rendered.is_ascii());
+        assert!(rendered.contains('#'));
+        assert!(rendered.contains('\n'));
+    }
+
+    #[test]
+    fn test_ascii_render_draws_quiet_zone_as_light() {
+        let rendered = Qr::render_text("https://example.com/pair/abc", QrFormat::Ascii).unwrap();
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        // The quiet zone is four modules of two characters each
+        assert!(lines[0].chars().all(|c| c == '#'));
+        assert!(lines.iter().all(|line| line.starts_with("########")));
+    }
+
+    #[test]
+    fn test_svg_render() {
+        let rendered = Qr::render_text("https://example.com/pair/abc", QrFormat::Svg).unwrap();
+        assert!(rendered.starts_with("<?xml"));
+        assert!(rendered.contains("<svg"));
+    }
+
+    #[test]
+    fn test_png_cannot_render_as_text() {
+        assert!(Qr::render_text("https://example.com", QrFormat::Png).is_err());
+    }
+
+    #[test]
+    fn test_save_png() {
+        let path = std::env::temp_dir().join(format!("claude-afk-qr-{}.png", std::process::id()));
+        Qr::save("https://example.com/pair/abc", QrFormat::Png, &path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(bytes.starts_with(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,124 @@
+use serde_json::Value;
+
+/// Key fragments whose string values are never written to disk
+const SECRET_KEYS: [&str; 8] = [
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "api_key",
+    "apikey",
+    "authorization",
+    "credential",
+];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Removal of secrets and the home directory from hook payloads before they
+/// are written to fixtures or the audit log
+pub struct Redact;
+
+impl Redact {
+    /// The home directory, replaced by `~` in redacted text
+    pub fn home() -> Option<String> {
+        std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .ok()
+    }
+
+    /// Strip secrets and the home directory from every string in the payload
+    pub fn value(value: &mut Value, home: Option<&str>) {
+        match value {
+            Value::Object(map) => {
+                for (key, v) in map.iter_mut() {
+                    let key = key.to_ascii_lowercase();
+                    if v.is_string() && SECRET_KEYS.iter().any(|s| key.contains(s)) {
+                        *v = Value::String(REDACTED.to_string());
+                    } else {
+                        Self::value(v, home);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| Self::value(v, home)),
+            Value::String(s) => *s = Self::text(s, home),
+            _ => {}
+        }
+    }
+
+    /// Redact bearer tokens, `SECRET=value` assignments and `--token value`
+    /// flags in free text such as bash commands
+    pub fn text(text: &str, home: Option<&str>) -> String {
+        let text = match home {
+            Some(home) if home.len() > 1 => text.replace(home, "~"),
+            _ => text.to_string(),
+        };
+
+        let mut redacted = String::with_capacity(text.len());
+        let mut redact_next = false;
+        for piece in text.split_inclusive(char::is_whitespace) {
+            let word = piece.trim_end();
+            let trailing = &piece[word.len()..];
+            let lower = word.to_ascii_lowercase();
+            let is_secret_name = |name: &str| SECRET_KEYS.iter().any(|s| name.contains(s));
+
+            if redact_next && !word.is_empty() {
+                // Keep surrounding quotes so the command still reads naturally
+                let quote = word.chars().last().filter(|c| *c == '"' || *c == '\'');
+                redacted.push_str(REDACTED);
+                redacted.extend(quote);
+                redact_next = false;
+            } else if let Some((name, _)) = word.split_once('=').filter(|(name, _)| {
+                is_secret_name(&name.to_ascii_lowercase()) && !name.contains(['"', '\''])
+            }) {
+                redacted.push_str(name);
+                redacted.push('=');
+                redacted.push_str(REDACTED);
+            } else {
+                redacted.push_str(word);
+                redact_next = lower.trim_start_matches(['"', '\'']) == "bearer"
+                    || (lower.starts_with('-') && is_secret_name(&lower));
+            }
+            redacted.push_str(trailing);
+        }
+        redacted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_secret_keys_and_home() {
+        let mut value = json!({
+            "cwd": "/home/me/project",
+            "tool_input": {"api_key": "sk-123", "content": "saved to /home/me/.env"},
+            "max_tokens": 100
+        });
+
+        Redact::value(&mut value, Some("/home/me"));
+
+        assert_eq!(value["cwd"], "~/project");
+        assert_eq!(value["tool_input"]["api_key"], REDACTED);
+        assert_eq!(value["tool_input"]["content"], "saved to ~/.env");
+        assert_eq!(value["max_tokens"], 100);
+    }
+
+    #[test]
+    fn test_redact_str_in_commands() {
+        assert_eq!(
+            Redact::text(r#"curl -H "Authorization: Bearer abc123" https://x"#, None),
+            r#"curl -H "Authorization: Bearer [REDACTED]" https://x"#
+        );
+        assert_eq!(
+            Redact::text("GITHUB_TOKEN=ghp_x npm publish", None),
+            "GITHUB_TOKEN=[REDACTED] npm publish"
+        );
+        assert_eq!(
+            Redact::text("deploy --password hunter2 --verbose", None),
+            "deploy --password [REDACTED] --verbose"
+        );
+        assert_eq!(Redact::text("npm test", None), "npm test");
+    }
+}
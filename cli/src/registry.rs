@@ -0,0 +1,532 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    config::Config,
+    constants::{
+        DECISION_TIMEOUT, REGISTRY_LOCK_STALE, REGISTRY_LOCK_TIMEOUT, SESSION_ACTIVE_WINDOW,
+        SESSION_RETENTION,
+    },
+    models::Decision,
+    session::SessionInfo,
+};
+
+/// Claude Code sessions seen by the hook, kept in `sessions.json` next to the
+/// config file. Every hook invocation is a separate process, so updates go
+/// through a lock file and an atomic rename.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct SessionRegistry {
+    #[serde(default)]
+    pub sessions: BTreeMap<String, SessionRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionRecord {
+    pub label: String,
+    pub cwd: String,
+    /// Unix timestamps (seconds)
+    pub first_seen: u64,
+    pub last_seen: u64,
+    /// Permission requests waiting for an answer on the phone
+    #[serde(default)]
+    pub pending: Vec<PendingPrompt>,
+    #[serde(default)]
+    pub allowed: u32,
+    #[serde(default)]
+    pub denied: u32,
+    #[serde(default)]
+    pub dismissed: u32,
+    /// Requests that timed out or failed and fell back to the terminal
+    #[serde(default)]
+    pub unanswered: u32,
+    /// Requests answered at the terminal before the phone
+    #[serde(default)]
+    pub terminal: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PendingPrompt {
+    pub decision_id: String,
+    /// Claude Code doesn't always send one; the tool call is matched by
+    /// name and input then
+    pub tool_use_id: Option<String>,
+    #[serde(default)]
+    pub tool_name: String,
+    #[serde(default)]
+    pub tool_input: Value,
+    pub title: String,
+    pub message: String,
+    pub since: u64,
+    /// Unix timestamp (seconds) when the hook stops waiting. Entries written
+    /// before the timeout was configurable have none and use DECISION_TIMEOUT.
+    #[serde(default)]
+    pub deadline: Option<u64>,
+}
+
+impl PendingPrompt {
+    fn deadline(&self) -> u64 {
+        self.deadline
+            .unwrap_or(self.since + DECISION_TIMEOUT.as_secs())
+    }
+
+    /// Whether this prompt asked about the given tool call
+    fn is_for(&self, tool_use_id: Option<&str>, tool_name: &str, tool_input: &Value) -> bool {
+        match (self.tool_use_id.as_deref(), tool_use_id) {
+            (Some(ours), Some(theirs)) => ours == theirs,
+            _ => self.tool_name == tool_name && self.tool_input == *tool_input,
+        }
+    }
+}
+
+impl SessionRecord {
+    pub fn is_blocked(&self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
+impl SessionRegistry {
+    pub fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(Config::dir()?.join("sessions.json"))
+    }
+
+    /// Read the registry; a missing or unreadable file is an empty registry
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Apply `change` to the registry on disk while holding the lock
+    pub fn update(
+        path: &Path,
+        change: impl FnOnce(&mut SessionRegistry),
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _lock = RegistryLock::acquire(path)?;
+        let mut registry = Self::load(path);
+        change(&mut registry);
+        registry.prune(Self::now());
+        registry.save(path)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        let mut file = File::create(&tmp)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Record activity for a session, creating it on first sight
+    pub fn touch(&mut self, session: &SessionInfo, cwd: &str, now: u64) {
+        let record = self
+            .sessions
+            .entry(session.id.clone())
+            .or_insert_with(|| SessionRecord {
+                label: session.label.clone(),
+                cwd: cwd.to_string(),
+                first_seen: now,
+                last_seen: now,
+                pending: Vec::new(),
+                allowed: 0,
+                denied: 0,
+                dismissed: 0,
+                unanswered: 0,
+                terminal: 0,
+            });
+        record.label = session.label.clone();
+        record.cwd = cwd.to_string();
+        record.last_seen = now;
+    }
+
+    /// Note a prompt waiting on the phone. Sessions the hook never saw (such
+    /// as `claude-afk test`) are not tracked.
+    pub fn add_pending(&mut self, session_id: &str, prompt: PendingPrompt) {
+        if let Some(record) = self.sessions.get_mut(session_id) {
+            record.last_seen = record.last_seen.max(prompt.since);
+            record.pending.push(prompt);
+        }
+    }
+
+    /// Clear a pending prompt and count how it ended; `None` means it timed
+    /// out or failed
+    pub fn resolve(
+        &mut self,
+        session_id: &str,
+        decision_id: &str,
+        decision: Option<Decision>,
+        now: u64,
+    ) {
+        let Some(record) = self.sessions.get_mut(session_id) else {
+            return;
+        };
+        let before = record.pending.len();
+        record.pending.retain(|p| p.decision_id != decision_id);
+        if record.pending.len() == before {
+            return;
+        }
+        record.last_seen = now;
+        match decision {
+            Some(Decision::Allow) => record.allowed += 1,
+            Some(Decision::Deny) => record.denied += 1,
+            Some(Decision::Dismiss) => record.dismissed += 1,
+            None => record.unanswered += 1,
+        }
+    }
+
+    /// Clear the prompt for a tool call that went ahead without the phone,
+    /// returning the decision ids to cancel on the backend
+    pub fn retract(
+        &mut self,
+        session_id: &str,
+        tool_use_id: Option<&str>,
+        tool_name: &str,
+        tool_input: &Value,
+        now: u64,
+    ) -> Vec<String> {
+        self.retract_where(session_id, now, |p| {
+            p.is_for(tool_use_id, tool_name, tool_input)
+        })
+    }
+
+    /// Clear every prompt of a session that has moved on, e.g. because the
+    /// user typed the next prompt after denying one at the terminal
+    pub fn retract_all(&mut self, session_id: &str, now: u64) -> Vec<String> {
+        self.retract_where(session_id, now, |_| true)
+    }
+
+    fn retract_where(
+        &mut self,
+        session_id: &str,
+        now: u64,
+        matches: impl Fn(&PendingPrompt) -> bool,
+    ) -> Vec<String> {
+        let Some(record) = self.sessions.get_mut(session_id) else {
+            return Vec::new();
+        };
+        let (retracted, pending) = std::mem::take(&mut record.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|p| matches(p));
+        record.pending = pending;
+        if !retracted.is_empty() {
+            record.last_seen = now;
+            record.terminal += retracted.len() as u32;
+        }
+        retracted.into_iter().map(|p| p.decision_id).collect()
+    }
+
+    /// Drop prompts whose hook process must be gone by now, and sessions not
+    /// seen for a long time
+    fn prune(&mut self, now: u64) {
+        // The hook gives up at the prompt's deadline; anything well past it
+        // was killed (e.g. answered at the terminal) before it could clean up
+        for record in self.sessions.values_mut() {
+            record.pending.retain(|p| now <= p.deadline() + 60);
+        }
+        self.sessions.retain(|_, record| {
+            now.saturating_sub(record.last_seen) <= SESSION_RETENTION.as_secs()
+        });
+    }
+
+    /// Sessions that are blocked or were active recently, blocked first and
+    /// most recent next
+    pub fn active(&self, now: u64) -> Vec<(&String, &SessionRecord)> {
+        let mut sessions: Vec<_> = self
+            .sessions
+            .iter()
+            .filter(|(_, record)| {
+                record.is_blocked()
+                    || now.saturating_sub(record.last_seen) <= SESSION_ACTIVE_WINDOW.as_secs()
+            })
+            .collect();
+        sessions.sort_by(|(_, a), (_, b)| {
+            b.is_blocked()
+                .cmp(&a.is_blocked())
+                .then(b.last_seen.cmp(&a.last_seen))
+        });
+        sessions
+    }
+
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+/// Exclusive lock on the registry, held for the duration of one update
+struct RegistryLock {
+    path: PathBuf,
+}
+
+impl RegistryLock {
+    fn acquire(registry_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(dir) = registry_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let path = registry_path.with_extension("json.lock");
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(RegistryLock { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    // A hook killed mid-update leaves its lock behind
+                    let stale = fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > REGISTRY_LOCK_STALE);
+                    if stale {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if start.elapsed() > REGISTRY_LOCK_TIMEOUT {
+                        return Err("Timed out waiting for the session registry lock".into());
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for RegistryLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str) -> SessionInfo {
+        SessionInfo {
+            id: id.to_string(),
+            label: format!("{} (main)", id),
+            project: id.to_string(),
+            branch: Some("main".to_string()),
+            host: None,
+        }
+    }
+
+    fn prompt(decision_id: &str, since: u64) -> PendingPrompt {
+        PendingPrompt {
+            decision_id: decision_id.to_string(),
+            tool_use_id: Some(format!("tool-{}", decision_id)),
+            tool_name: "Bash".to_string(),
+            tool_input: serde_json::json!({ "command": "npm test" }),
+            title: "Run bash command? 🐚".to_string(),
+            message: "npm test".to_string(),
+            since,
+            deadline: None,
+        }
+    }
+
+    #[test]
+    fn test_touch_creates_and_updates_sessions() {
+        let mut registry = SessionRegistry::default();
+        registry.touch(&session("a"), "/work/a", 100);
+        registry.touch(&session("a"), "/work/a/src", 160);
+
+        let record = &registry.sessions["a"];
+        assert_eq!(record.first_seen, 100);
+        assert_eq!(record.last_seen, 160);
+        assert_eq!(record.cwd, "/work/a/src");
+        assert_eq!(record.label, "a (main)");
+    }
+
+    #[test]
+    fn test_pending_prompts_are_resolved_and_counted() {
+        let mut registry = SessionRegistry::default();
+        registry.touch(&session("a"), "/work/a", 100);
+        registry.add_pending("a", prompt("d1", 110));
+        registry.add_pending("a", prompt("d2", 120));
+        assert!(registry.sessions["a"].is_blocked());
+
+        registry.resolve("a", "d1", Some(Decision::Allow), 130);
+        registry.resolve("a", "d2", None, 140);
+        // Resolving twice doesn't count twice
+        registry.resolve("a", "d2", Some(Decision::Deny), 150);
+
+        let record = &registry.sessions["a"];
+        assert!(!record.is_blocked());
+        assert_eq!(record.allowed, 1);
+        assert_eq!(record.unanswered, 1);
+        assert_eq!(record.denied, 0);
+    }
+
+    #[test]
+    fn test_retract_clears_prompt_for_tool_call() {
+        let mut registry = SessionRegistry::default();
+        registry.touch(&session("a"), "/work/a", 100);
+        registry.add_pending("a", prompt("d1", 110));
+        registry.add_pending("a", prompt("d2", 120));
+
+        let input = serde_json::json!({ "command": "npm test" });
+        assert_eq!(
+            registry.retract("a", Some("tool-d1"), "Bash", &input, 130),
+            vec!["d1"]
+        );
+        // Tool calls that never waited on the phone retract nothing
+        assert!(registry
+            .retract("a", Some("tool-other"), "Bash", &input, 140)
+            .is_empty());
+        assert!(registry
+            .retract("b", Some("tool-d2"), "Bash", &input, 140)
+            .is_empty());
+        // The poller resolving afterwards doesn't count it again
+        registry.resolve("a", "d1", None, 150);
+
+        let record = &registry.sessions["a"];
+        assert_eq!(record.pending, vec![prompt("d2", 120)]);
+        assert_eq!(record.terminal, 1);
+        assert_eq!(record.unanswered, 0);
+        assert_eq!(record.last_seen, 130);
+    }
+
+    #[test]
+    fn test_retract_without_tool_use_id_matches_tool_input() {
+        let mut registry = SessionRegistry::default();
+        registry.touch(&session("a"), "/work/a", 100);
+        registry.add_pending(
+            "a",
+            PendingPrompt {
+                tool_use_id: None,
+                ..prompt("d1", 110)
+            },
+        );
+        registry.add_pending(
+            "a",
+            PendingPrompt {
+                tool_use_id: None,
+                tool_input: serde_json::json!({ "command": "npm run build" }),
+                ..prompt("d2", 120)
+            },
+        );
+
+        let input = serde_json::json!({ "command": "npm test" });
+        assert!(registry.retract("a", None, "Read", &input, 130).is_empty());
+        assert_eq!(
+            registry.retract("a", Some("toolu_late"), "Bash", &input, 130),
+            vec!["d1"]
+        );
+        assert_eq!(registry.sessions["a"].pending.len(), 1);
+    }
+
+    #[test]
+    fn test_retract_all_clears_the_session() {
+        let mut registry = SessionRegistry::default();
+        registry.touch(&session("a"), "/work/a", 100);
+        registry.touch(&session("b"), "/work/b", 100);
+        registry.add_pending("a", prompt("d1", 110));
+        registry.add_pending("a", prompt("d2", 120));
+        registry.add_pending("b", prompt("d3", 120));
+
+        assert_eq!(registry.retract_all("a", 130), vec!["d1", "d2"]);
+        assert_eq!(registry.sessions["a"].terminal, 2);
+        assert!(registry.sessions["b"].is_blocked());
+    }
+
+    #[test]
+    fn test_unknown_sessions_are_not_tracked() {
+        let mut registry = SessionRegistry::default();
+        registry.add_pending("claude-afk-test", prompt("d1", 100));
+        registry.resolve("claude-afk-test", "d1", Some(Decision::Allow), 110);
+        assert!(registry.sessions.is_empty());
+    }
+
+    #[test]
+    fn test_prune_drops_stale_prompts_and_old_sessions() {
+        let mut registry = SessionRegistry::default();
+        registry.touch(&session("old"), "/old", 0);
+        registry.touch(&session("a"), "/a", 1000);
+        registry.add_pending("a", prompt("d1", 1000));
+
+        registry.prune(1000 + DECISION_TIMEOUT.as_secs() + 61);
+        assert!(!registry.sessions["a"].is_blocked());
+
+        registry.prune(SESSION_RETENTION.as_secs() + 1);
+        assert!(!registry.sessions.contains_key("old"));
+        assert!(registry.sessions.contains_key("a"));
+    }
+
+    #[test]
+    fn test_prune_keeps_prompts_until_their_own_deadline() {
+        let mut registry = SessionRegistry::default();
+        registry.touch(&session("a"), "/a", 1000);
+        registry.add_pending(
+            "a",
+            PendingPrompt {
+                deadline: Some(1600),
+                ..prompt("slow", 1000)
+            },
+        );
+
+        registry.prune(1000 + DECISION_TIMEOUT.as_secs() + 61);
+        assert!(registry.sessions["a"].is_blocked());
+
+        registry.prune(1661);
+        assert!(!registry.sessions["a"].is_blocked());
+    }
+
+    #[test]
+    fn test_active_lists_blocked_sessions_first() {
+        let now = SESSION_ACTIVE_WINDOW.as_secs() * 10;
+        let mut registry = SessionRegistry::default();
+        registry.touch(&session("idle"), "/idle", now - 10);
+        registry.touch(&session("blocked"), "/blocked", now - 100);
+        registry.add_pending("blocked", prompt("d1", now - 100));
+        registry.touch(&session("gone"), "/gone", 0);
+
+        let ids: Vec<&str> = registry
+            .active(now)
+            .into_iter()
+            .map(|(id, _)| id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["blocked", "idle"]);
+    }
+
+    #[test]
+    fn test_update_round_trips_through_disk() {
+        let dir = std::env::temp_dir().join(format!("claude-afk-registry-{}", std::process::id()));
+        let path = dir.join("sessions.json");
+        let now = SessionRegistry::now();
+
+        SessionRegistry::update(&path, |r| r.touch(&session("a"), "/work/a", now)).unwrap();
+        SessionRegistry::update(&path, |r| r.add_pending("a", prompt("d1", now))).unwrap();
+
+        let registry = SessionRegistry::load(&path);
+        let leftover_lock = path.with_extension("json.lock").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(registry.sessions["a"].pending.len(), 1);
+        assert!(!leftover_lock);
+    }
+
+    #[test]
+    fn test_load_tolerates_missing_and_corrupt_files() {
+        let path = std::env::temp_dir().join(format!(
+            "claude-afk-registry-corrupt-{}.json",
+            std::process::id()
+        ));
+        assert_eq!(SessionRegistry::load(&path), SessionRegistry::default());
+
+        fs::write(&path, "{not json").unwrap();
+        let registry = SessionRegistry::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(registry, SessionRegistry::default());
+    }
+}
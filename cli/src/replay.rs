@@ -0,0 +1,351 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use nanoid::nanoid;
+use serde_json::Value;
+
+use crate::{
+    cmd::Cmd,
+    config::Config,
+    error::{DecisionError, HookError},
+    models::{
+        Decision, GenericHookInput, NotificationInput, PermissionRequestInput, PostToolUseInput,
+        ToolInfo,
+    },
+    redact::Redact,
+    session::SessionInfo,
+    timestamp::file_timestamp,
+    transcript::Transcript,
+};
+
+/// Width of the label column in replay reports
+const LABEL_WIDTH: usize = 13;
+
+/// Recording of raw hook payloads and offline replay through the parser
+pub struct Fixtures;
+
+impl Fixtures {
+    /// Where recordings go unless `record --dir` says otherwise
+    pub fn default_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(Config::dir()?.join("fixtures"))
+    }
+
+    /// Save a redacted copy of a raw hook payload and return its path
+    pub fn record(dir: &Path, input: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let home = Redact::home();
+
+        // Malformed input is kept as text, since that is usually the interesting case
+        let (content, event, detail) = match serde_json::from_str::<Value>(input) {
+            Ok(mut value) => {
+                Redact::value(&mut value, home.as_deref());
+                let field = |key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+                let event = field("hook_event_name").unwrap_or_else(|| "unknown".to_string());
+                let detail = field("tool_name").or_else(|| field("notification_type"));
+                (
+                    format!("{}\n", serde_json::to_string_pretty(&value)?),
+                    event,
+                    detail,
+                )
+            }
+            Err(_) => (
+                Redact::text(input, home.as_deref()),
+                "malformed".to_string(),
+                None,
+            ),
+        };
+
+        let mut name = format!("{}-{}", file_timestamp(), Self::file_safe(&event));
+        if let Some(detail) = detail {
+            name.push('-');
+            name.push_str(&Self::file_safe(&detail));
+        }
+        name.push('-');
+        name.push_str(&nanoid!(6));
+
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.json", name));
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    fn file_safe(s: &str) -> String {
+        s.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+
+    /// Describe how claude-afk handles a hook payload, without touching the
+    /// network or the local config
+    pub fn replay(input: &str) -> String {
+        let mut report = Report::default();
+
+        let generic: GenericHookInput = match serde_json::from_str(input) {
+            Ok(v) => v,
+            Err(e) => {
+                let error = HookError::Failed(format!("Failed to parse hook input: {}", e));
+                report.failed(error, ", Claude Code asks in the terminal");
+                return report.finish();
+            }
+        };
+
+        report.line("Event", &generic.hook_event_name);
+        report.line("Session", &generic.session_id);
+
+        match generic.hook_event_name.as_str() {
+            "PermissionRequest" => Self::replay_permission_request(input, &mut report),
+            "Notification" => Self::replay_notification(input, &mut report),
+            "PostToolUse" | "PostToolUseFailure" => {
+                Self::replay_post_tool_use(input, &mut report)
+            }
+            "UserPromptSubmit" => report.line(
+                "Path",
+                &format!(
+                    "For each prompt the session still waits on: POST /api/decision/<id>/cancel\nno output, {}",
+                    exit(Ok(()))
+                ),
+            ),
+            other => {
+                let error = HookError::Failed(format!("Unknown hook event: {}", other));
+                report.line("Path", &format!("Unknown hook event, {}", exit(Err(error))));
+            }
+        }
+        report.finish()
+    }
+
+    fn replay_permission_request(input: &str, report: &mut Report) {
+        let request: PermissionRequestInput = match serde_json::from_str(input) {
+            Ok(v) => v,
+            Err(e) => {
+                let error =
+                    HookError::Failed(format!("Failed to parse PermissionRequest input: {}", e));
+                report.failed(error, ", Claude Code asks in the terminal");
+                return;
+            }
+        };
+
+        let tool_info = ToolInfo::from_pre_tool_use(&request);
+        let session = Self::offline_session(&request.session_id);
+        let (title, message) = Cmd::permission_text(&request, &session, &Config::default());
+
+        report.line("Tool", &request.tool_name);
+        report.line("Parsed", &format!("{:?}", tool_info));
+        report.line("Title", &title);
+        report.line("Message", &message);
+        // Only present when the transcript still exists on this machine
+        if let Some(context) = Transcript::context(Path::new(&request.transcript_path)) {
+            report.line("Context", &context.summary);
+        }
+        report.line(
+            "Path",
+            "POST /api/notify, then poll /api/decision/<id>/status",
+        );
+        for decision in [Decision::Allow, Decision::Deny, Decision::Dismiss] {
+            let outcome = match decision.hook_output() {
+                Some(output) => serde_json::to_string(&output).unwrap_or_default(),
+                None => format!(
+                    "no output, {}, Claude Code asks in the terminal",
+                    exit(Err(HookError::Fallback(None)))
+                ),
+            };
+            report.line(&format!("  {}", decision.as_str()), &outcome);
+        }
+        report.line(
+            "  timeout",
+            &format!(
+                "{}, Claude Code asks in the terminal",
+                exit(Err(DecisionError::TimedOut.into()))
+            ),
+        );
+        report.line(
+            "  terminal",
+            &format!(
+                "no output, {} (answered at the terminal, phone prompt retracted)",
+                exit(Err(DecisionError::Cancelled.into()))
+            ),
+        );
+    }
+
+    fn replay_post_tool_use(input: &str, report: &mut Report) {
+        let post_tool_use: PostToolUseInput = match serde_json::from_str(input) {
+            Ok(v) => v,
+            Err(e) => {
+                let error = HookError::Failed(format!("Failed to parse PostToolUse input: {}", e));
+                report.failed(error, "");
+                return;
+            }
+        };
+
+        report.line("Tool", &post_tool_use.tool_name);
+        match post_tool_use.tool_use_id {
+            Some(tool_use_id) => report.line("Tool use", &tool_use_id),
+            None => report.line("Tool use", "none, matched by tool name and input"),
+        }
+        report.line(
+            "Path",
+            &format!(
+                "If still waiting on the phone: POST /api/decision/<id>/cancel\nno output, {}",
+                exit(Ok(()))
+            ),
+        );
+    }
+
+    fn replay_notification(input: &str, report: &mut Report) {
+        let notification: NotificationInput = match serde_json::from_str(input) {
+            Ok(v) => v,
+            Err(e) => {
+                let error = HookError::Failed(format!("Failed to parse Notification input: {}", e));
+                report.failed(error, "");
+                return;
+            }
+        };
+
+        report.line("Type", &notification.notification_type);
+        if notification.notification_type != "idle_prompt" {
+            report.line(
+                "Path",
+                &format!(
+                    "Ignored (only idle_prompt is forwarded), {}",
+                    exit(Err(HookError::Fallback(None)))
+                ),
+            );
+            return;
+        }
+
+        let session = Self::offline_session(&notification.session_id);
+        let (title, message) = Cmd::idle_text(&notification, &session, &Config::default());
+        report.line("Title", &title);
+        report.line("Message", &message);
+        report.line(
+            "Path",
+            &format!("POST /api/notify/simple, no output, {}", exit(Ok(()))),
+        );
+    }
+
+    /// Replays don't look at git or the hostname, so reports are the same on
+    /// every machine. The default config has no templates that would show them.
+    fn offline_session(session_id: &str) -> SessionInfo {
+        SessionInfo {
+            id: session_id.to_string(),
+            label: String::new(),
+            project: String::new(),
+            branch: None,
+            host: None,
+        }
+    }
+}
+
+/// "exit N" for how a hook invocation with this result ends
+fn exit(result: Result<(), HookError>) -> String {
+    let code = match result {
+        Ok(()) => 0,
+        Err(e) => e.exit_code(),
+    };
+    format!("exit {}", code)
+}
+
+/// Plain-text report with aligned labels and indented continuation lines
+#[derive(Default)]
+struct Report {
+    text: String,
+}
+
+impl Report {
+    fn line(&mut self, label: &str, value: &str) {
+        let mut lines = value.lines();
+        self.text.push_str(&format!(
+            "{:<width$}{}\n",
+            label,
+            lines.next().unwrap_or(""),
+            width = LABEL_WIDTH
+        ));
+        for line in lines {
+            self.text
+                .push_str(&format!("{:<width$}{}\n", "", line, width = LABEL_WIDTH));
+        }
+    }
+
+    /// The error line and the exit path for a hook that gives up, followed
+    /// by what Claude Code does next, if anything
+    fn failed(&mut self, error: HookError, then: &str) {
+        self.line("Error", &error.to_string());
+        self.line("Path", &format!("{}{}", exit(Err(error)), then));
+    }
+
+    fn finish(self) -> String {
+        self.text
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("hooks")
+    }
+
+    /// Every fixture's replay must match its .golden file.
+    /// Run with UPDATE_GOLDEN=1 to regenerate them after an intended change.
+    #[test]
+    fn test_replay_matches_golden_files() {
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        let mut fixtures: Vec<PathBuf> = fs::read_dir(fixtures_dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        fixtures.sort();
+        assert!(!fixtures.is_empty());
+
+        for fixture in fixtures {
+            let report = Fixtures::replay(&fs::read_to_string(&fixture).unwrap());
+            let golden = fixture.with_extension("golden");
+            if update {
+                fs::write(&golden, &report).unwrap();
+                continue;
+            }
+            let expected = fs::read_to_string(&golden)
+                .unwrap_or_else(|_| panic!("missing {}", golden.display()));
+            assert_eq!(report, expected, "replay of {}", fixture.display());
+        }
+    }
+
+    #[test]
+    fn test_record_writes_redacted_fixture() {
+        let dir = std::env::temp_dir().join(format!("claude-afk-fixtures-{}", std::process::id()));
+        let input = r#"{"session_id":"s","hook_event_name":"PermissionRequest","tool_name":"Bash","tool_input":{"command":"TOKEN=x make"}}"#;
+
+        let path = Fixtures::record(&dir, input).unwrap();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(name.contains("-PermissionRequest-Bash-"));
+        assert!(saved.contains("TOKEN=[REDACTED] make"));
+    }
+
+    #[test]
+    fn test_record_keeps_malformed_input() {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-afk-fixtures-malformed-{}",
+            std::process::id()
+        ));
+
+        let path = Fixtures::record(&dir, "{not json").unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(path.to_string_lossy().contains("-malformed-"));
+        assert_eq!(saved, "{not json");
+    }
+}
@@ -0,0 +1,419 @@
+use std::fmt;
+
+use jiff::{civil::DateTime, tz::TimeZone, Timestamp, Zoned};
+use serde::{Deserialize, Serialize};
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// When notifications may be sent, on top of `afk`, `auto` and timed `afk`.
+/// Outside `hours` (when any are set) and inside `quiet` windows, hooks fall
+/// back to the terminal.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Schedule {
+    /// IANA time zone the windows are in, e.g. "Europe/Berlin"; the system
+    /// time zone when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<ZoneName>,
+    /// Working hours, e.g. "mon-fri 09:00-19:00"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hours: Vec<Window>,
+    /// Quiet hours, e.g. "22:00-07:00"; these win over `hours`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quiet: Vec<Window>,
+}
+
+/// Why the schedule holds notifications back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleCheck {
+    Open,
+    Quiet(Window),
+    OutsideHours,
+}
+
+impl Schedule {
+    pub fn is_empty(&self) -> bool {
+        self.hours.is_empty() && self.quiet.is_empty()
+    }
+
+    pub fn time_zone(&self) -> TimeZone {
+        self.timezone
+            .as_ref()
+            .and_then(|zone| TimeZone::get(&zone.0).ok())
+            .unwrap_or_else(TimeZone::system)
+    }
+
+    pub fn check(&self, now: Timestamp) -> ScheduleCheck {
+        let now = now.to_zoned(self.time_zone());
+        if let Some(window) = self.quiet.iter().find(|window| window.contains(&now)) {
+            return ScheduleCheck::Quiet(window.clone());
+        }
+        if !self.hours.is_empty() && !self.hours.iter().any(|window| window.contains(&now)) {
+            return ScheduleCheck::OutsideHours;
+        }
+        ScheduleCheck::Open
+    }
+
+    /// `at` as shown to the user: "17:30" today, "Tue 08:00" within the next
+    /// week, a full date otherwise
+    pub fn local_time(&self, at: Timestamp, now: Timestamp) -> String {
+        let tz = self.time_zone();
+        let (at, now) = (at.to_zoned(tz.clone()), now.to_zoned(tz));
+        let days = (at.date() - now.date()).get_days();
+        let format = match days {
+            0 => "%H:%M",
+            1..7 => "%a %H:%M",
+            _ => "%Y-%m-%d %H:%M",
+        };
+        at.strftime(format).to_string()
+    }
+
+    /// Parse `afk --until`: a time of day such as "17:30" (the next time the
+    /// clock shows it), or a date and time such as "2026-03-01T08:00", in the
+    /// schedule's time zone
+    pub fn parse_until(&self, value: &str, now: Timestamp) -> Result<Timestamp, String> {
+        let value = value.trim();
+        let invalid = || {
+            format!(
+                "Invalid --until value '{}'. Use a time like 17:30 or a date and time like 2026-03-01T08:00",
+                value
+            )
+        };
+        let tz = self.time_zone();
+
+        if let Some(minutes) = Window::parse_time(value).filter(|m| *m < MINUTES_PER_DAY) {
+            let (hour, minute) = ((minutes / 60) as i8, (minutes % 60) as i8);
+            let today = now.to_zoned(tz.clone()).date();
+            for date in [Ok(today), today.tomorrow()] {
+                let at = date
+                    .and_then(|date| date.at(hour, minute, 0, 0).to_zoned(tz.clone()))
+                    .map_err(|_| invalid())?;
+                if at.timestamp() > now {
+                    return Ok(at.timestamp());
+                }
+            }
+            return Err(invalid());
+        }
+
+        value
+            .parse::<DateTime>()
+            .and_then(|datetime| datetime.to_zoned(tz))
+            .map(|zoned| zoned.timestamp())
+            .map_err(|_| invalid())
+            .and_then(|at| {
+                if at > now {
+                    Ok(at)
+                } else {
+                    Err(format!("--until {} is in the past", value))
+                }
+            })
+    }
+}
+
+/// A validated IANA time zone name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ZoneName(String);
+
+impl TryFrom<String> for ZoneName {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        TimeZone::get(&name)
+            .map(|_| ZoneName(name.clone()))
+            .map_err(|_| {
+                format!(
+                    "Unknown time zone '{}', expected e.g. \"Europe/Berlin\"",
+                    name
+                )
+            })
+    }
+}
+
+impl From<ZoneName> for String {
+    fn from(zone: ZoneName) -> Self {
+        zone.0
+    }
+}
+
+/// A daily time window on some days of the week, written like
+/// "mon-fri 09:00-19:00", "sat,sun 10:00-14:00" or "22:00-07:00". A window
+/// that ends before it starts runs past midnight and belongs to the day it
+/// starts on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Window {
+    spec: String,
+    /// Monday first
+    days: [bool; 7],
+    /// Minutes after midnight
+    start: u16,
+    end: u16,
+}
+
+impl Window {
+    pub fn contains(&self, at: &Zoned) -> bool {
+        let minute = at.hour() as u16 * 60 + at.minute() as u16;
+        let today = at.weekday().to_monday_zero_offset() as usize;
+        let yesterday = (today + 6) % 7;
+        if self.start < self.end {
+            self.days[today] && (self.start..self.end).contains(&minute)
+        } else {
+            (self.days[today] && minute >= self.start)
+                || (self.days[yesterday] && minute < self.end)
+        }
+    }
+
+    fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.split_whitespace().collect::<Vec<_>>().join(" ");
+        let (days, times) = match spec.rsplit_once(' ') {
+            Some((days, times)) => (Self::parse_days(days)?, times),
+            None => ([true; 7], spec.as_str()),
+        };
+        let (start, end) = times.split_once('-')?;
+        let start = Self::parse_time(start).filter(|m| *m < MINUTES_PER_DAY)?;
+        let end = Self::parse_time(end)?;
+        Some(Window {
+            spec: spec.clone(),
+            days,
+            start,
+            end: end % MINUTES_PER_DAY,
+        })
+    }
+
+    /// "daily", "weekdays", "weekends", or a comma list of days and day
+    /// ranges such as "mon,wed-fri"
+    fn parse_days(value: &str) -> Option<[bool; 7]> {
+        match value.to_lowercase().as_str() {
+            "daily" => return Some([true; 7]),
+            "weekdays" => return Some([true, true, true, true, true, false, false]),
+            "weekends" => return Some([false, false, false, false, false, true, true]),
+            _ => {}
+        }
+        let day = |name: &str| DAY_NAMES.iter().position(|d| name.trim().starts_with(d));
+        let mut days = [false; 7];
+        for part in value.to_lowercase().split(',') {
+            let (first, last) = match part.split_once('-') {
+                Some((first, last)) => (day(first)?, day(last)?),
+                None => (day(part)?, day(part)?),
+            };
+            let mut d = first;
+            loop {
+                days[d] = true;
+                if d == last {
+                    break;
+                }
+                d = (d + 1) % 7;
+            }
+        }
+        Some(days)
+    }
+
+    /// "9:00" or "09:00" as minutes after midnight; "24:00" is allowed as an end
+    fn parse_time(value: &str) -> Option<u16> {
+        let (hour, minute) = value.trim().split_once(':')?;
+        if minute.len() != 2 {
+            return None;
+        }
+        let (hour, minute): (u16, u16) = (hour.parse().ok()?, minute.parse().ok()?);
+        (minute < 60 && (hour < 24 || (hour == 24 && minute == 0))).then_some(hour * 60 + minute)
+    }
+}
+
+impl TryFrom<String> for Window {
+    type Error = String;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        Window::parse(&spec).ok_or_else(|| {
+            format!(
+                "Invalid schedule window '{}', expected e.g. \"mon-fri 09:00-19:00\" or \"22:00-07:00\"",
+                spec
+            )
+        })
+    }
+}
+
+impl From<Window> for String {
+    fn from(window: Window) -> Self {
+        window.spec
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(spec: &str) -> Window {
+        Window::try_from(spec.to_string()).unwrap()
+    }
+
+    fn at(datetime: &str) -> Zoned {
+        // 2026-03-02 is a Monday
+        datetime
+            .parse::<DateTime>()
+            .unwrap()
+            .to_zoned(TimeZone::UTC)
+            .unwrap()
+    }
+
+    fn utc_schedule(hours: &[&str], quiet: &[&str]) -> Schedule {
+        Schedule {
+            timezone: Some(ZoneName::try_from("UTC".to_string()).unwrap()),
+            hours: hours.iter().map(|s| window(s)).collect(),
+            quiet: quiet.iter().map(|s| window(s)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_window_on_weekdays() {
+        let w = window("mon-fri 09:00-19:00");
+
+        assert!(w.contains(&at("2026-03-02T09:00")));
+        assert!(w.contains(&at("2026-03-06T18:59")));
+        assert!(!w.contains(&at("2026-03-02T19:00")));
+        assert!(!w.contains(&at("2026-03-02T08:59")));
+        // Saturday
+        assert!(!w.contains(&at("2026-03-07T12:00")));
+    }
+
+    #[test]
+    fn test_window_past_midnight_belongs_to_its_start_day() {
+        let w = window("fri 22:00-07:00");
+
+        assert!(w.contains(&at("2026-03-06T23:30")));
+        assert!(w.contains(&at("2026-03-07T06:59")));
+        assert!(!w.contains(&at("2026-03-07T07:00")));
+        // Friday morning is Thursday night's window, which isn't included
+        assert!(!w.contains(&at("2026-03-06T03:00")));
+    }
+
+    #[test]
+    fn test_window_day_lists() {
+        assert_eq!(
+            window("mon,wed-fri 10:00-11:00").days,
+            [true, false, true, true, true, false, false]
+        );
+        assert_eq!(
+            window("sat-mon 10:00-11:00").days,
+            [true, false, false, false, false, true, true]
+        );
+        assert_eq!(
+            window("weekends 10:00-11:00").days,
+            [false, false, false, false, false, true, true]
+        );
+        assert_eq!(window("10:00-11:00").days, [true; 7]);
+        assert!(window("daily 00:00-24:00").contains(&at("2026-03-04T23:59")));
+    }
+
+    #[test]
+    fn test_invalid_windows() {
+        for spec in [
+            "",
+            "9-17",
+            "mon-fri",
+            "mon-fri 09:00",
+            "25:00-26:00",
+            "xyz 09:00-10:00",
+            "09:0-10:00",
+        ] {
+            assert!(Window::try_from(spec.to_string()).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn test_check_quiet_wins_over_hours() {
+        let schedule = utc_schedule(&["daily 08:00-23:00"], &["22:00-07:00"]);
+        let check = |t: &str| schedule.check(at(t).timestamp());
+
+        assert_eq!(check("2026-03-02T12:00"), ScheduleCheck::Open);
+        assert_eq!(
+            check("2026-03-02T22:30"),
+            ScheduleCheck::Quiet(window("22:00-07:00"))
+        );
+        assert_eq!(check("2026-03-02T07:30"), ScheduleCheck::OutsideHours);
+        assert_eq!(
+            Schedule::default().check(Timestamp::now()),
+            ScheduleCheck::Open
+        );
+    }
+
+    #[test]
+    fn test_check_uses_the_configured_time_zone() {
+        let mut schedule = utc_schedule(&["09:00-17:00"], &[]);
+        schedule.timezone = Some(ZoneName::try_from("America/New_York".to_string()).unwrap());
+
+        // 15:00 UTC is 10:00 in New York (EST)
+        assert_eq!(
+            schedule.check("2026-03-02T15:00:00Z".parse().unwrap()),
+            ScheduleCheck::Open
+        );
+        assert_eq!(
+            schedule.check("2026-03-02T10:00:00Z".parse().unwrap()),
+            ScheduleCheck::OutsideHours
+        );
+        assert!(ZoneName::try_from("Mars/Olympus".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_parse_until() {
+        let schedule = utc_schedule(&[], &[]);
+        let now: Timestamp = "2026-03-02T16:00:00Z".parse().unwrap();
+
+        assert_eq!(
+            schedule.parse_until("17:30", now).unwrap(),
+            "2026-03-02T17:30:00Z".parse::<Timestamp>().unwrap()
+        );
+        // A time that has already passed today means tomorrow
+        assert_eq!(
+            schedule.parse_until("8:00", now).unwrap(),
+            "2026-03-03T08:00:00Z".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            schedule.parse_until("2026-03-05T09:15", now).unwrap(),
+            "2026-03-05T09:15:00Z".parse::<Timestamp>().unwrap()
+        );
+        assert!(schedule.parse_until("2026-03-01T09:15", now).is_err());
+        assert!(schedule.parse_until("24:00", now).is_err());
+        assert!(schedule.parse_until("later", now).is_err());
+    }
+
+    #[test]
+    fn test_local_time() {
+        let schedule = utc_schedule(&[], &[]);
+        let now = at("2026-03-02T16:00").timestamp();
+
+        assert_eq!(
+            schedule.local_time(at("2026-03-02T17:30").timestamp(), now),
+            "17:30"
+        );
+        assert_eq!(
+            schedule.local_time(at("2026-03-03T08:00").timestamp(), now),
+            "Tue 08:00"
+        );
+        assert_eq!(
+            schedule.local_time(at("2026-03-20T08:00").timestamp(), now),
+            "2026-03-20 08:00"
+        );
+    }
+
+    #[test]
+    fn test_schedule_round_trips_through_toml() {
+        let schedule = utc_schedule(&["mon-fri 09:00-19:00"], &["22:00-07:00"]);
+
+        let toml = toml::to_string(&schedule).unwrap();
+        assert_eq!(
+            toml,
+            "timezone = \"UTC\"\nhours = [\"mon-fri 09:00-19:00\"]\nquiet = [\"22:00-07:00\"]\n"
+        );
+        assert_eq!(toml::from_str::<Schedule>(&toml).unwrap(), schedule);
+
+        let err = toml::from_str::<Schedule>("hours = [\"9 to 5\"]").unwrap_err();
+        assert!(err.to_string().contains("Invalid schedule window '9 to 5'"));
+    }
+}
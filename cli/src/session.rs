@@ -0,0 +1,242 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+/// Which Claude Code session a notification comes from, so notifications
+/// from several repos and machines can be told apart and grouped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    /// Short human-readable label, e.g. "claude-afk (main) · laptop"
+    pub label: String,
+    /// Git repository name, or the working directory name outside a repository
+    pub project: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+}
+
+impl SessionInfo {
+    /// Describe the session running in `cwd`. `labels` maps a project root path
+    /// or project name to a label that replaces the generated one.
+    pub fn detect(session_id: &str, cwd: &str, labels: &BTreeMap<String, String>) -> Self {
+        let cwd = Path::new(cwd);
+        let root = Self::repo_root(cwd);
+        let project_dir = root.unwrap_or(cwd);
+        let project = project_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| project_dir.display().to_string());
+        let branch = root.and_then(Self::git_branch);
+        let host = Self::hostname();
+
+        let label = Self::label_override(labels, project_dir, &project)
+            .unwrap_or_else(|| Self::label(&project, branch.as_deref(), host.as_deref()));
+
+        SessionInfo {
+            id: session_id.to_string(),
+            label,
+            project,
+            branch,
+            host,
+        }
+    }
+
+    fn label(project: &str, branch: Option<&str>, host: Option<&str>) -> String {
+        let mut label = project.to_string();
+        if let Some(branch) = branch {
+            label.push_str(&format!(" ({})", branch));
+        }
+        if let Some(host) = host {
+            label.push_str(&format!(" · {}", host));
+        }
+        label
+    }
+
+    fn label_override(
+        labels: &BTreeMap<String, String>,
+        project_dir: &Path,
+        project: &str,
+    ) -> Option<String> {
+        let home = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .ok();
+        labels
+            .iter()
+            .find(|(key, _)| {
+                let path = match (key.strip_prefix("~/"), &home) {
+                    (Some(rest), Some(home)) => Path::new(home).join(rest),
+                    _ => PathBuf::from(key),
+                };
+                path == project_dir || key.as_str() == project
+            })
+            .map(|(_, label)| label.clone())
+    }
+
+    fn repo_root(dir: &Path) -> Option<&Path> {
+        dir.ancestors()
+            .find(|ancestor| ancestor.join(".git").exists())
+    }
+
+    /// Current branch read straight from `.git/HEAD` (no git process per hook).
+    /// Detached heads show the short commit hash.
+    fn git_branch(root: &Path) -> Option<String> {
+        let git = root.join(".git");
+        // Worktrees and submodules have a `.git` file pointing at the real git dir
+        let git_dir = if git.is_file() {
+            let content = fs::read_to_string(&git).ok()?;
+            let target = content.trim().strip_prefix("gitdir:")?.trim();
+            root.join(target)
+        } else {
+            git
+        };
+
+        let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+        let head = head.trim();
+        match head.strip_prefix("ref:") {
+            Some(reference) => {
+                let reference = reference.trim();
+                Some(
+                    reference
+                        .strip_prefix("refs/heads/")
+                        .unwrap_or(reference)
+                        .to_string(),
+                )
+            }
+            None if head.len() >= 7 => Some(head[..7].to_string()),
+            None => None,
+        }
+    }
+
+    /// Short hostname, without the domain part
+    fn hostname() -> Option<String> {
+        let name = std::env::var("HOSTNAME")
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .ok()
+            .or_else(|| fs::read_to_string("/etc/hostname").ok())
+            .or_else(|| {
+                std::process::Command::new("hostname")
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+            })?;
+        let name = name.trim().split('.').next().unwrap_or_default();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake repository named `name` (field 0) inside its own temp dir (field 1)
+    struct TempRepo(PathBuf, PathBuf);
+
+    impl TempRepo {
+        fn new(name: &str, head: &str) -> Self {
+            let base = std::env::temp_dir().join(format!(
+                "claude-afk-session-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let dir = base.join(name);
+            fs::create_dir_all(dir.join(".git")).unwrap();
+            fs::create_dir_all(dir.join("src")).unwrap();
+            fs::write(dir.join(".git").join("HEAD"), head).unwrap();
+            TempRepo(dir, base)
+        }
+    }
+
+    impl Drop for TempRepo {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.1);
+        }
+    }
+
+    #[test]
+    fn test_detect_repo_name_and_branch_from_subdirectory() {
+        let repo = TempRepo::new("webapp", "ref: refs/heads/feature/login\n");
+        let cwd = repo.0.join("src");
+
+        let session = SessionInfo::detect("sess-1", &cwd.to_string_lossy(), &BTreeMap::new());
+
+        assert_eq!(session.id, "sess-1");
+        assert_eq!(session.project, "webapp");
+        assert_eq!(session.branch.as_deref(), Some("feature/login"));
+        assert!(session.label.starts_with("webapp (feature/login)"));
+    }
+
+    #[test]
+    fn test_detached_head_shows_short_hash() {
+        let repo = TempRepo::new("detached", "4f2c9e1a7b3d5f60718293a4b5c6d7e8f9012345\n");
+
+        assert_eq!(SessionInfo::git_branch(&repo.0).as_deref(), Some("4f2c9e1"));
+    }
+
+    #[test]
+    fn test_worktree_git_file() {
+        let repo = TempRepo::new("main-checkout", "ref: refs/heads/main\n");
+        let worktree_git = repo.0.join(".git").join("worktrees").join("wt");
+        fs::create_dir_all(&worktree_git).unwrap();
+        fs::write(worktree_git.join("HEAD"), "ref: refs/heads/hotfix\n").unwrap();
+        let worktree = repo.0.join("wt");
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}\n", worktree_git.display()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            SessionInfo::git_branch(&worktree).as_deref(),
+            Some("hotfix")
+        );
+    }
+
+    #[test]
+    fn test_outside_repo_uses_directory_name() {
+        let dir = std::env::temp_dir().join(format!("claude-afk-nogit-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let session = SessionInfo::detect("s", &dir.to_string_lossy(), &BTreeMap::new());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            session.project,
+            format!("claude-afk-nogit-{}", std::process::id())
+        );
+        assert_eq!(session.branch, None);
+    }
+
+    #[test]
+    fn test_label_override_by_path_or_name() {
+        let repo = TempRepo::new("api", "ref: refs/heads/main\n");
+        let cwd = repo.0.to_string_lossy().to_string();
+
+        let by_path = BTreeMap::from([(cwd.clone(), "API (prod)".to_string())]);
+        let by_name = BTreeMap::from([("api".to_string(), "Billing API".to_string())]);
+
+        assert_eq!(SessionInfo::detect("s", &cwd, &by_path).label, "API (prod)");
+        assert_eq!(
+            SessionInfo::detect("s", &cwd, &by_name).label,
+            "Billing API"
+        );
+        // Structured fields are still sent with an override
+        assert_eq!(SessionInfo::detect("s", &cwd, &by_name).project, "api");
+    }
+
+    #[test]
+    fn test_label_format() {
+        assert_eq!(
+            SessionInfo::label("app", Some("main"), Some("laptop")),
+            "app (main) · laptop"
+        );
+        assert_eq!(SessionInfo::label("app", None, None), "app");
+    }
+}
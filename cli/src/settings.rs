@@ -0,0 +1,1163 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    constants::{HOOK_ID, SETTINGS_BACKUPS_KEPT},
+    timestamp::file_timestamp,
+};
+
+/// Hook events claude-afk installs entries for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+pub enum HookEvent {
+    /// Permission prompts, answered from your phone
+    PermissionRequest,
+    /// Idle notifications when Claude is waiting for input
+    Notification,
+    /// Retracts phone prompts that were answered at the terminal
+    PostToolUse,
+    /// Retracts phone prompts whose tool call failed after the terminal allowed it
+    PostToolUseFailure,
+    /// Retracts phone prompts that were denied at the terminal
+    UserPromptSubmit,
+}
+
+impl HookEvent {
+    pub const ALL: [HookEvent; 5] = [
+        HookEvent::PermissionRequest,
+        HookEvent::Notification,
+        HookEvent::PostToolUse,
+        HookEvent::PostToolUseFailure,
+        HookEvent::UserPromptSubmit,
+    ];
+
+    /// Events without which prompts or idle notifications never reach the
+    /// phone; the others only tidy up after them
+    pub const REQUIRED: [HookEvent; 2] = [HookEvent::PermissionRequest, HookEvent::Notification];
+
+    /// Key under "hooks" in settings.json
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::PermissionRequest => "PermissionRequest",
+            HookEvent::Notification => "Notification",
+            HookEvent::PostToolUse => "PostToolUse",
+            HookEvent::PostToolUseFailure => "PostToolUseFailure",
+            HookEvent::UserPromptSubmit => "UserPromptSubmit",
+        }
+    }
+}
+
+/// How a hook command relates to claude-afk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookKind {
+    /// Carries the --hook-id marker
+    Tagged,
+    /// Runs a claude-afk binary directly, as installs before the marker did
+    Legacy,
+    /// Someone else's hook
+    Foreign,
+}
+
+/// Which Claude Code settings file hooks are installed into
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookScope {
+    /// ~/.claude/settings.json, applies to every project
+    User,
+    /// .claude/settings.json in the current repo, usually committed
+    Project,
+    /// .claude/settings.local.json in the current repo, not committed
+    Local,
+}
+
+impl HookScope {
+    pub const ALL: [HookScope; 3] = [HookScope::User, HookScope::Project, HookScope::Local];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookScope::User => "user",
+            HookScope::Project => "project",
+            HookScope::Local => "local",
+        }
+    }
+
+    /// Short path shown in output
+    pub fn display_path(self) -> &'static str {
+        match self {
+            HookScope::User => "~/.claude/settings.json",
+            HookScope::Project => ".claude/settings.json",
+            HookScope::Local => ".claude/settings.local.json",
+        }
+    }
+
+    /// The settings file for this scope
+    pub fn path(self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = match self {
+            HookScope::User => {
+                let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))?;
+                PathBuf::from(home).join(".claude").join("settings.json")
+            }
+            HookScope::Project => Self::project_root()?.join(".claude").join("settings.json"),
+            HookScope::Local => Self::project_root()?
+                .join(".claude")
+                .join("settings.local.json"),
+        };
+        Ok(path)
+    }
+
+    fn project_root() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let cwd = std::env::current_dir()?;
+        Ok(Self::project_root_from(&cwd))
+    }
+
+    /// The enclosing git repository, or the directory itself outside of one
+    fn project_root_from(dir: &Path) -> PathBuf {
+        dir.ancestors()
+            .find(|ancestor| ancestor.join(".git").exists())
+            .unwrap_or(dir)
+            .to_path_buf()
+    }
+}
+
+/// Helpers for reading and editing Claude Code's settings.json
+pub struct ClaudeSettings;
+
+impl ClaudeSettings {
+    /// Settings files for every scope, skipping scopes that resolve to the
+    /// same file (e.g. running from the home directory)
+    pub fn scope_paths() -> Vec<(HookScope, PathBuf)> {
+        let mut paths: Vec<(HookScope, PathBuf)> = Vec::new();
+        for scope in HookScope::ALL {
+            if let Ok(path) = scope.path() {
+                if !paths.iter().any(|(_, p)| *p == path) {
+                    paths.push((scope, path));
+                }
+            }
+        }
+        paths
+    }
+
+    /// Parsed settings for every scope that has a readable file
+    pub fn read_all() -> Vec<(HookScope, Value)> {
+        Self::scope_paths()
+            .into_iter()
+            .filter_map(|(scope, path)| Some((scope, Self::read(&path).ok()??)))
+            .collect()
+    }
+
+    /// Read and parse a settings file. Missing files read as None.
+    pub fn read(path: &Path) -> Result<Option<Value>, Box<dyn std::error::Error>> {
+        match Self::read_source(path)? {
+            Some(content) => Ok(Some(Self::parse(path, &content)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Raw contents of a settings file. Missing files read as None.
+    pub fn read_source(path: &Path) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Could not read {}: {}", path.display(), e).into()),
+        }
+    }
+
+    /// Parse settings, tolerating the comments and trailing commas Claude
+    /// Code itself accepts. Anything else is a hard error, so a file we
+    /// can't understand is never replaced.
+    pub fn parse(path: &Path, content: &str) -> Result<Value, Box<dyn std::error::Error>> {
+        if content.trim().is_empty() {
+            return Ok(Value::Object(Default::default()));
+        }
+        serde_json::from_str(&Self::strip_jsonc(content)).map_err(|e| {
+            format!(
+                "Could not parse {} at line {}, column {}: {}. The file was left unchanged.",
+                path.display(),
+                e.line(),
+                e.column(),
+                Self::error_message(&e)
+            )
+            .into()
+        })
+    }
+
+    /// serde_json's message without the trailing " at line X column Y"
+    fn error_message(e: &serde_json::Error) -> String {
+        let message = e.to_string();
+        match message.rfind(" at line ") {
+            Some(idx) => message[..idx].to_string(),
+            None => message,
+        }
+    }
+
+    /// Whether the content uses JSONC syntax that is lost when rewritten
+    pub fn has_jsonc_syntax(content: &str) -> bool {
+        Self::strip_jsonc(content) != content
+    }
+
+    /// Blank out comments and trailing commas so JSONC parses as JSON. Byte
+    /// offsets are kept so parse errors still point at the original location.
+    fn strip_jsonc(content: &str) -> String {
+        let chars: Vec<char> = content.chars().collect();
+        let blank = |c: char| {
+            if c == '\n' {
+                "\n".to_string()
+            } else {
+                " ".repeat(c.len_utf8())
+            }
+        };
+
+        // First pass: comments
+        let mut without_comments = String::with_capacity(content.len());
+        let mut in_string = false;
+        let mut escaped = false;
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    in_string = false;
+                }
+                without_comments.push(c);
+                i += 1;
+            } else if c == '/' && next == Some('/') {
+                while i < chars.len() && chars[i] != '\n' {
+                    without_comments.push_str(&blank(chars[i]));
+                    i += 1;
+                }
+            } else if c == '/' && next == Some('*') {
+                without_comments.push_str("  ");
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    without_comments.push_str(&blank(chars[i]));
+                    i += 1;
+                }
+                if i < chars.len() {
+                    without_comments.push_str("  ");
+                    i += 2;
+                }
+            } else {
+                in_string = c == '"';
+                without_comments.push(c);
+                i += 1;
+            }
+        }
+
+        // Second pass: commas followed only by whitespace and a closing bracket
+        let chars: Vec<char> = without_comments.chars().collect();
+        let mut stripped = String::with_capacity(without_comments.len());
+        let mut in_string = false;
+        let mut escaped = false;
+        for (i, &c) in chars.iter().enumerate() {
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    in_string = false;
+                }
+            } else if c == '"' {
+                in_string = true;
+            } else if c == ',' {
+                let trailing = chars[i + 1..]
+                    .iter()
+                    .find(|c| !c.is_whitespace())
+                    .is_some_and(|c| *c == '}' || *c == ']');
+                if trailing {
+                    stripped.push(' ');
+                    continue;
+                }
+            }
+            stripped.push(c);
+        }
+        stripped
+    }
+
+    /// Command line Claude Code runs for our hooks. The --hook-id marker
+    /// identifies the entry even if the binary is renamed or moved.
+    pub fn hook_command(exe: &Path) -> String {
+        format!(
+            "{} notify --hook-id {}",
+            Self::shell_quote(&exe.to_string_lossy()),
+            HOOK_ID
+        )
+    }
+
+    fn shell_quote(arg: &str) -> String {
+        let needs_quotes = arg.is_empty()
+            || arg
+                .chars()
+                .any(|c| c.is_whitespace() || "'\"\\$`&|;<>()*?!#~".contains(c));
+        if needs_quotes {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        } else {
+            arg.to_string()
+        }
+    }
+
+    /// Split a hook command into words, honouring single and double quotes
+    fn split_command(command: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut in_word = false;
+        let mut quote: Option<char> = None;
+        let mut chars = command.chars();
+
+        while let Some(c) = chars.next() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some('"'), '\\') => word.extend(chars.next()),
+                (Some(_), c) => word.push(c),
+                (None, '\'' | '"') => {
+                    quote = Some(c);
+                    in_word = true;
+                }
+                (None, '\\') => {
+                    word.extend(chars.next());
+                    in_word = true;
+                }
+                (None, c) if c.is_whitespace() => {
+                    if in_word {
+                        words.push(std::mem::take(&mut word));
+                        in_word = false;
+                    }
+                }
+                (None, c) => {
+                    word.push(c);
+                    in_word = true;
+                }
+            }
+        }
+        if in_word {
+            words.push(word);
+        }
+        words
+    }
+
+    /// The executable a hook command runs. Older installs wrote the bare
+    /// binary path unquoted, which may itself contain spaces.
+    pub fn hook_executable(command: &str) -> PathBuf {
+        let whole = PathBuf::from(command.trim());
+        if whole.exists() {
+            return whole;
+        }
+        Self::split_command(command)
+            .into_iter()
+            .next()
+            .map(PathBuf::from)
+            .unwrap_or(whole)
+    }
+
+    /// Whether a hook command was installed by claude-afk, and how
+    pub fn hook_kind(command: &str) -> HookKind {
+        let words = Self::split_command(command);
+        let tagged = words
+            .windows(2)
+            .any(|w| w[0] == "--hook-id" && w[1] == HOOK_ID)
+            || words
+                .iter()
+                .any(|w| w.strip_prefix("--hook-id=") == Some(HOOK_ID));
+        if tagged {
+            return HookKind::Tagged;
+        }
+
+        // Entries written before the marker existed run the binary directly
+        let exe = Self::hook_executable(command);
+        let exe_name = exe.file_name().map(|n| n.to_string_lossy());
+        if matches!(
+            exe_name.as_deref(),
+            Some("claude-afk") | Some("claude-afk.exe")
+        ) {
+            HookKind::Legacy
+        } else {
+            HookKind::Foreign
+        }
+    }
+
+    /// Whether a hook points at a binary that no longer exists
+    pub fn is_stale(command: &str) -> bool {
+        !Self::hook_executable(command).exists()
+    }
+
+    fn is_claude_afk_command(command: &str) -> bool {
+        Self::hook_kind(command) != HookKind::Foreign
+    }
+
+    /// Whether a hook entry ({"matcher": ..., "hooks": [...]}) runs claude-afk
+    pub fn is_claude_afk_entry(entry: &Value) -> bool {
+        entry
+            .get("hooks")
+            .and_then(|h| h.as_array())
+            .map(|inner| {
+                inner.iter().any(|h| {
+                    h.get("command")
+                        .and_then(|c| c.as_str())
+                        .map(Self::is_claude_afk_command)
+                        .unwrap_or(false)
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    /// claude-afk hooks that are untagged or point at a missing binary
+    pub fn outdated_hook_commands(settings: &Value) -> Vec<(String, String)> {
+        Self::claude_afk_hook_commands(settings)
+            .into_iter()
+            .filter(|(_, command)| {
+                Self::hook_kind(command) == HookKind::Legacy || Self::is_stale(command)
+            })
+            .collect()
+    }
+
+    /// Rewrite untagged and stale claude-afk hooks to run `command`, keeping
+    /// their matchers. Returns a description of each change.
+    pub fn migrate_hooks(settings: &mut Value, command: &str) -> Vec<String> {
+        let mut changes = Vec::new();
+        let Some(hooks) = settings.get_mut("hooks").and_then(|h| h.as_object_mut()) else {
+            return changes;
+        };
+
+        for (event, entries) in hooks.iter_mut() {
+            let inner_hooks = entries
+                .as_array_mut()
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.get_mut("hooks").and_then(|h| h.as_array_mut()))
+                .flatten();
+
+            for hook in inner_hooks {
+                let Some(old) = hook.get("command").and_then(|c| c.as_str()) else {
+                    continue;
+                };
+                let change = match Self::hook_kind(old) {
+                    HookKind::Foreign => continue,
+                    _ if Self::is_stale(old) => format!(
+                        "Repointed {} hook from missing {}",
+                        event,
+                        Self::hook_executable(old).display()
+                    ),
+                    HookKind::Legacy => format!("Tagged {} hook with --hook-id {}", event, HOOK_ID),
+                    HookKind::Tagged => continue,
+                };
+                hook["command"] = Value::String(command.to_string());
+                changes.push(change);
+            }
+        }
+
+        changes
+    }
+
+    /// Whether the settings have a claude-afk entry for the given hook event
+    pub fn has_claude_afk_hook(settings: &Value, event: &str) -> bool {
+        settings
+            .get("hooks")
+            .and_then(|h| h.get(event))
+            .and_then(|entries| entries.as_array())
+            .map(|entries| entries.iter().any(Self::is_claude_afk_entry))
+            .unwrap_or(false)
+    }
+
+    /// (event, command) pairs for every claude-afk hook in the settings
+    pub fn claude_afk_hook_commands(settings: &Value) -> Vec<(String, String)> {
+        let Some(hooks) = settings.get("hooks").and_then(|h| h.as_object()) else {
+            return Vec::new();
+        };
+
+        hooks
+            .iter()
+            .flat_map(|(event, entries)| {
+                entries
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|entry| entry.get("hooks").and_then(|h| h.as_array()))
+                    .flatten()
+                    .filter_map(|hook| hook.get("command").and_then(|c| c.as_str()))
+                    .filter(|command| Self::is_claude_afk_command(command))
+                    .map(move |command| (event.clone(), command.to_string()))
+            })
+            .collect()
+    }
+
+    /// Replace any claude-afk entry for the event with one running `command`
+    pub fn install_claude_afk_hook(
+        settings: &mut Value,
+        event: HookEvent,
+        matcher: &str,
+        command: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let entry = serde_json::json!({
+            "matcher": matcher,
+            "hooks": [
+                {
+                    "type": "command",
+                    "command": command
+                }
+            ]
+        });
+
+        // Get or create the hooks object
+        let hooks = settings
+            .as_object_mut()
+            .ok_or("Settings is not an object")?
+            .entry("hooks")
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()
+            .ok_or("Hooks is not an object")?;
+
+        let entries = hooks
+            .entry(event.as_str())
+            .or_insert_with(|| serde_json::json!([]))
+            .as_array_mut()
+            .ok_or_else(|| format!("{} is not an array", event.as_str()))?;
+
+        // Remove any existing claude-afk hooks and add the new one
+        Self::strip_claude_afk_commands(entries);
+        entries.push(entry);
+        Ok(())
+    }
+
+    /// Remove claude-afk commands from each entry's hooks array, keeping any
+    /// other commands that share the entry. An entry is dropped only when
+    /// nothing is left in it. Returns each changed entry's matcher and
+    /// whether the entry was dropped.
+    fn strip_claude_afk_commands(entries: &mut Vec<Value>) -> Vec<(String, bool)> {
+        let mut stripped = Vec::new();
+        entries.retain_mut(|entry| {
+            let matcher = entry
+                .get("matcher")
+                .and_then(|m| m.as_str())
+                .unwrap_or("")
+                .to_string();
+            let Some(inner) = entry.get_mut("hooks").and_then(|h| h.as_array_mut()) else {
+                return true;
+            };
+            let before = inner.len();
+            inner.retain(|h| {
+                !h.get("command")
+                    .and_then(|c| c.as_str())
+                    .is_some_and(Self::is_claude_afk_command)
+            });
+            if inner.len() == before {
+                return true;
+            }
+            let dropped = inner.is_empty();
+            stripped.push((matcher, dropped));
+            !dropped
+        });
+        stripped
+    }
+
+    /// Unified diff between two versions of a settings file
+    pub fn diff(label: &str, before: &str, after: &str) -> String {
+        similar::TextDiff::from_lines(before, after)
+            .unified_diff()
+            .context_radius(3)
+            .header(label, label)
+            .to_string()
+    }
+
+    /// Remove every claude-afk entry, dropping event arrays and the hooks
+    /// object when nothing else is left in them. Returns a description of
+    /// each change.
+    pub fn remove_claude_afk_hooks(settings: &mut Value) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        let Some(hooks) = settings.get_mut("hooks").and_then(|h| h.as_object_mut()) else {
+            return changes;
+        };
+
+        let mut emptied = Vec::new();
+        for (event, entries) in hooks.iter_mut() {
+            let Some(entries) = entries.as_array_mut() else {
+                continue;
+            };
+
+            let before = entries.len();
+            for (matcher, dropped) in Self::strip_claude_afk_commands(entries) {
+                changes.push(if dropped {
+                    format!("Removed {} hook (matcher \"{}\")", event, matcher)
+                } else {
+                    format!(
+                        "Removed claude-afk from {} hook (matcher \"{}\"), keeping its other commands",
+                        event, matcher
+                    )
+                });
+            }
+
+            // Only drop arrays that held nothing but our entries
+            if entries.is_empty() && before > 0 {
+                emptied.push(event.clone());
+            }
+        }
+
+        for event in emptied {
+            hooks.shift_remove(&event);
+            changes.push(format!("Removed empty {} list", event));
+        }
+
+        if hooks.is_empty() && !changes.is_empty() {
+            if let Some(obj) = settings.as_object_mut() {
+                obj.shift_remove("hooks");
+                changes.push("Removed empty hooks object".to_string());
+            }
+        }
+
+        changes
+    }
+
+    /// Copy the settings file next to itself with a timestamp suffix,
+    /// keeping only the most recent few. Writes within the same second get
+    /// a counter ("settings.json.20240101-093000.1.bak") instead of
+    /// replacing the earlier backup.
+    pub fn backup(path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let file_name = Self::file_name(path);
+        let timestamp = file_timestamp();
+        let mut counter = 0;
+        let backup_path = loop {
+            let name = match counter {
+                0 => format!("{}.{}.bak", file_name, timestamp),
+                n => format!("{}.{}.{}.bak", file_name, timestamp, n),
+            };
+            let candidate = path.with_file_name(name);
+            // Claim the name first so a concurrent write can't take it too
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&candidate)
+            {
+                Ok(_) => break candidate,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => counter += 1,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        if let Err(e) = fs::copy(path, &backup_path) {
+            let _ = fs::remove_file(&backup_path);
+            return Err(e.into());
+        }
+        Self::prune_backups(path);
+        Ok(backup_path)
+    }
+
+    /// Sort key for a backup name: its timestamp, then its counter
+    fn backup_order(name: &str, prefix: &str) -> (String, u32) {
+        let stamp = name
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(".bak"))
+            .unwrap_or(name);
+        match stamp.split_once('.') {
+            Some((time, counter)) => (time.to_string(), counter.parse().unwrap_or(0)),
+            None => (stamp.to_string(), 0),
+        }
+    }
+
+    fn file_name(path: &Path) -> String {
+        path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "settings.json".to_string())
+    }
+
+    /// Best-effort cleanup of all but the newest backups
+    fn prune_backups(path: &Path) {
+        let (Some(dir), prefix) = (path.parent(), format!("{}.", Self::file_name(path))) else {
+            return;
+        };
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        // Timestamps sort lexicographically, so the oldest come first
+        let mut backups: Vec<(String, u32, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter_map(|p| {
+                let name = p.file_name()?.to_string_lossy().to_string();
+                (name.starts_with(&prefix) && name.ends_with(".bak")).then(|| {
+                    let (time, counter) = Self::backup_order(&name, &prefix);
+                    (time, counter, p)
+                })
+            })
+            .collect();
+        backups.sort();
+
+        let excess = backups.len().saturating_sub(SETTINGS_BACKUPS_KEPT);
+        for (_, _, old) in &backups[..excess] {
+            let _ = fs::remove_file(old);
+        }
+    }
+
+    /// Settings as they are written to disk
+    pub fn render(settings: &Value) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(format!("{}\n", serde_json::to_string_pretty(settings)?))
+    }
+
+    /// Back up the existing file, then replace it atomically so Claude Code
+    /// never sees a half-written settings file. Returns the backup path.
+    pub fn save(
+        path: &Path,
+        settings: &Value,
+    ) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+        let parent = path
+            .parent()
+            .ok_or("Settings path has no parent directory")?;
+        fs::create_dir_all(parent)?;
+
+        let backup_path = if path.exists() {
+            Some(Self::backup(path)?)
+        } else {
+            None
+        };
+
+        let tmp_path = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            Self::file_name(path),
+            std::process::id()
+        ));
+        let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(Self::render(settings)?.as_bytes())?;
+            file.sync_all()?;
+            if let Ok(metadata) = fs::metadata(path) {
+                fs::set_permissions(&tmp_path, metadata.permissions())?;
+            }
+            fs::rename(&tmp_path, path)?;
+            Ok(())
+        })();
+
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result.map(|_| backup_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claude_afk_entry(matcher: &str) -> Value {
+        json!({
+            "matcher": matcher,
+            "hooks": [{"type": "command", "command": "/usr/local/bin/claude-afk"}]
+        })
+    }
+
+    #[test]
+    fn test_remove_only_claude_afk_entries() {
+        let other = json!({
+            "matcher": "Bash",
+            "hooks": [{"type": "command", "command": "/usr/bin/audit-log"}]
+        });
+        let mut settings = json!({
+            "permissions": {"allow": ["Bash(npm test)"]},
+            "hooks": {
+                "PermissionRequest": [claude_afk_entry("*"), other.clone()],
+                "Notification": [claude_afk_entry("idle_prompt")]
+            }
+        });
+
+        let changes = ClaudeSettings::remove_claude_afk_hooks(&mut settings);
+
+        assert_eq!(settings["hooks"]["PermissionRequest"], json!([other]));
+        assert!(settings["hooks"].get("Notification").is_none());
+        assert_eq!(settings["permissions"]["allow"][0], "Bash(npm test)");
+        assert!(changes.contains(&"Removed empty Notification list".to_string()));
+    }
+
+    #[test]
+    fn test_remove_keeps_other_commands_in_shared_entry() {
+        let mut settings = json!({
+            "hooks": {
+                "PermissionRequest": [{
+                    "matcher": "*",
+                    "hooks": [
+                        {"type": "command", "command": "/usr/bin/audit-log"},
+                        {"type": "command", "command": "/usr/local/bin/claude-afk"}
+                    ]
+                }]
+            }
+        });
+
+        let changes = ClaudeSettings::remove_claude_afk_hooks(&mut settings);
+
+        assert_eq!(
+            settings["hooks"]["PermissionRequest"],
+            json!([{
+                "matcher": "*",
+                "hooks": [{"type": "command", "command": "/usr/bin/audit-log"}]
+            }])
+        );
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].contains("keeping its other commands"));
+    }
+
+    #[test]
+    fn test_install_keeps_other_commands_in_shared_entry() {
+        let mut settings = json!({
+            "hooks": {
+                "Notification": [{
+                    "matcher": "idle_prompt",
+                    "hooks": [
+                        {"type": "command", "command": "/usr/bin/notify-send hi"},
+                        {"type": "command", "command": "/usr/local/bin/claude-afk"}
+                    ]
+                }]
+            }
+        });
+
+        ClaudeSettings::install_claude_afk_hook(
+            &mut settings,
+            HookEvent::Notification,
+            "idle_prompt",
+            "/usr/local/bin/claude-afk --tagged",
+        )
+        .unwrap();
+
+        let entries = settings["hooks"]["Notification"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0]["hooks"],
+            json!([{"type": "command", "command": "/usr/bin/notify-send hi"}])
+        );
+        assert_eq!(
+            entries[1]["hooks"][0]["command"],
+            "/usr/local/bin/claude-afk --tagged"
+        );
+    }
+
+    #[test]
+    fn test_remove_drops_empty_hooks_object() {
+        let mut settings = json!({
+            "hooks": {
+                "PermissionRequest": [claude_afk_entry("*")],
+                "Notification": [claude_afk_entry("idle_prompt")]
+            }
+        });
+
+        let changes = ClaudeSettings::remove_claude_afk_hooks(&mut settings);
+
+        assert_eq!(settings, json!({}));
+        assert_eq!(changes.len(), 5);
+    }
+
+    #[test]
+    fn test_remove_leaves_unrelated_empty_arrays() {
+        let mut settings = json!({"hooks": {"Stop": []}});
+
+        let changes = ClaudeSettings::remove_claude_afk_hooks(&mut settings);
+
+        assert!(changes.is_empty());
+        assert_eq!(settings, json!({"hooks": {"Stop": []}}));
+    }
+
+    #[test]
+    fn test_has_claude_afk_hook() {
+        let settings = json!({"hooks": {"PermissionRequest": [claude_afk_entry("*")]}});
+
+        assert!(ClaudeSettings::has_claude_afk_hook(
+            &settings,
+            "PermissionRequest"
+        ));
+        assert!(!ClaudeSettings::has_claude_afk_hook(
+            &settings,
+            "Notification"
+        ));
+    }
+
+    #[test]
+    fn test_install_hook_with_tool_matcher() {
+        let mut settings = json!({
+            "permissions": {"allow": ["Bash(npm test)"]},
+            "hooks": {"PermissionRequest": [claude_afk_entry("*")]}
+        });
+
+        ClaudeSettings::install_claude_afk_hook(
+            &mut settings,
+            HookEvent::PermissionRequest,
+            "Bash|Write|Edit",
+            "/usr/local/bin/claude-afk",
+        )
+        .unwrap();
+
+        let entries = settings["hooks"]["PermissionRequest"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["matcher"], "Bash|Write|Edit");
+        assert!(settings["hooks"].get("Notification").is_none());
+        assert_eq!(settings["permissions"]["allow"][0], "Bash(npm test)");
+    }
+
+    #[test]
+    fn test_install_hook_rejects_non_object_settings() {
+        let mut settings = json!([]);
+        let result = ClaudeSettings::install_claude_afk_hook(
+            &mut settings,
+            HookEvent::Notification,
+            "idle_prompt",
+            "claude-afk",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_diff_shows_added_lines() {
+        let diff =
+            ClaudeSettings::diff("settings.json", "{\n  \"a\": 1\n}\n", "{\n  \"a\": 2\n}\n");
+
+        assert!(diff.starts_with("--- settings.json\n+++ settings.json\n"));
+        assert!(diff.contains("-  \"a\": 1"));
+        assert!(diff.contains("+  \"a\": 2"));
+    }
+
+    #[test]
+    fn test_diff_empty_when_unchanged() {
+        assert!(ClaudeSettings::diff("settings.json", "{}", "{}").is_empty());
+    }
+
+    #[test]
+    fn test_hook_command_is_tagged() {
+        let command = ClaudeSettings::hook_command(Path::new("/opt/my tools/claude-afk"));
+
+        assert_eq!(
+            command,
+            "'/opt/my tools/claude-afk' notify --hook-id claude-afk"
+        );
+        assert_eq!(ClaudeSettings::hook_kind(&command), HookKind::Tagged);
+        assert_eq!(
+            ClaudeSettings::hook_executable(&command),
+            PathBuf::from("/opt/my tools/claude-afk")
+        );
+    }
+
+    #[test]
+    fn test_hook_kind_tagged_renamed_binary() {
+        assert_eq!(
+            ClaudeSettings::hook_kind("/usr/local/bin/afk notify --hook-id=claude-afk"),
+            HookKind::Tagged
+        );
+    }
+
+    #[test]
+    fn test_hook_kind_legacy_and_foreign() {
+        assert_eq!(
+            ClaudeSettings::hook_kind("/usr/local/bin/claude-afk"),
+            HookKind::Legacy
+        );
+        assert_eq!(
+            ClaudeSettings::hook_kind("/home/me/bin/claude-afk-audit.sh"),
+            HookKind::Foreign
+        );
+        assert_eq!(
+            ClaudeSettings::hook_kind("echo claude-afk >> /tmp/log"),
+            HookKind::Foreign
+        );
+    }
+
+    #[test]
+    fn test_split_command_quotes() {
+        assert_eq!(
+            ClaudeSettings::split_command(r#"'/a b/c' "d \"e\"" f\ g"#),
+            vec!["/a b/c", "d \"e\"", "f g"]
+        );
+    }
+
+    #[test]
+    fn test_migrate_hooks_rewrites_legacy_and_stale() {
+        let mut settings = json!({
+            "hooks": {
+                "PermissionRequest": [
+                    claude_afk_entry("Bash"),
+                    {"matcher": "*", "hooks": [{"type": "command", "command": "/usr/bin/other-tool"}]}
+                ],
+                "Notification": [{
+                    "matcher": "idle_prompt",
+                    "hooks": [{"type": "command", "command": "/gone/afk notify --hook-id claude-afk"}]
+                }]
+            }
+        });
+        let command = "/usr/bin/claude-afk notify --hook-id claude-afk";
+
+        let changes = ClaudeSettings::migrate_hooks(&mut settings, command);
+
+        assert_eq!(changes.len(), 2);
+        let permission = &settings["hooks"]["PermissionRequest"];
+        assert_eq!(permission[0]["matcher"], "Bash");
+        assert_eq!(permission[0]["hooks"][0]["command"], command);
+        assert_eq!(permission[1]["hooks"][0]["command"], "/usr/bin/other-tool");
+        assert_eq!(
+            settings["hooks"]["Notification"][0]["hooks"][0]["command"],
+            command
+        );
+    }
+
+    #[test]
+    fn test_outdated_hook_commands() {
+        let settings = json!({"hooks": {"PermissionRequest": [claude_afk_entry("*")]}});
+
+        let outdated = ClaudeSettings::outdated_hook_commands(&settings);
+
+        assert_eq!(
+            outdated,
+            vec![(
+                "PermissionRequest".to_string(),
+                "/usr/local/bin/claude-afk".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_project_root_from_finds_git_dir() {
+        let root = std::env::temp_dir().join(format!("claude-afk-root-{}", std::process::id()));
+        let nested = root.join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+
+        assert_eq!(HookScope::project_root_from(&nested), root);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_project_root_from_outside_repo() {
+        let dir = Path::new("/");
+        assert_eq!(HookScope::project_root_from(dir), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn test_parse_accepts_comments_and_trailing_commas() {
+        let content = r#"{
+  // Team permissions
+  "permissions": {
+    "allow": ["Bash(npm test)", "Read(//tmp/**)",], /* trailing */
+  },
+  "url": "https://example.com/*not a comment*/",
+}"#;
+
+        let settings = ClaudeSettings::parse(Path::new("settings.json"), content).unwrap();
+
+        assert_eq!(settings["permissions"]["allow"][1], "Read(//tmp/**)");
+        assert_eq!(settings["url"], "https://example.com/*not a comment*/");
+        assert!(ClaudeSettings::has_jsonc_syntax(content));
+    }
+
+    #[test]
+    fn test_parse_error_reports_location() {
+        let content = "{\n  \"permissions\": {\n    \"allow\": [\"Bash\"\n  }\n}";
+
+        let err = ClaudeSettings::parse(Path::new("settings.json"), content)
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("settings.json at line 4, column 3"), "{}", err);
+        assert!(err.contains("left unchanged"));
+    }
+
+    #[test]
+    fn test_parse_empty_file() {
+        let settings = ClaudeSettings::parse(Path::new("settings.json"), "  \n").unwrap();
+        assert_eq!(settings, json!({}));
+    }
+
+    #[test]
+    fn test_plain_json_has_no_jsonc_syntax() {
+        assert!(!ClaudeSettings::has_jsonc_syntax(
+            r#"{"a": "//", "b": [1, 2], "c": "\",]"}"#
+        ));
+    }
+
+    #[test]
+    fn test_save_preserves_key_order_and_backs_up() {
+        let dir = std::env::temp_dir().join(format!("claude-afk-save-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        fs::write(&path, r#"{"model": "opus", "env": {}, "hooks": {}}"#).unwrap();
+
+        let mut settings = ClaudeSettings::read(&path).unwrap().unwrap();
+        ClaudeSettings::install_claude_afk_hook(
+            &mut settings,
+            HookEvent::Notification,
+            "idle_prompt",
+            "claude-afk",
+        )
+        .unwrap();
+        let backup = ClaudeSettings::save(&path, &settings).unwrap().unwrap();
+
+        let written = fs::read_to_string(&path).unwrap();
+        let keys: Vec<usize> = ["\"model\"", "\"env\"", "\"hooks\""]
+            .iter()
+            .map(|k| written.find(k).unwrap())
+            .collect();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(fs::read_to_string(backup).unwrap().contains("opus"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_backup_copies_file() {
+        let dir = std::env::temp_dir().join(format!("claude-afk-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        fs::write(&path, "{}").unwrap();
+
+        let backup = ClaudeSettings::backup(&path).unwrap();
+        let name = backup.file_name().unwrap().to_string_lossy().to_string();
+
+        assert!(name.starts_with("settings.json."));
+        assert!(name.ends_with(".bak"));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "{}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_backups_in_the_same_second_are_kept() {
+        let dir = std::env::temp_dir().join(format!("claude-afk-backups-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+
+        fs::write(&path, "original").unwrap();
+        let first = ClaudeSettings::backup(&path).unwrap();
+        fs::write(&path, "modified").unwrap();
+        let second = ClaudeSettings::backup(&path).unwrap();
+
+        let first_content = fs::read_to_string(&first).unwrap();
+        let second_content = fs::read_to_string(&second).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first_content, "original");
+        assert_eq!(second_content, "modified");
+    }
+
+    #[test]
+    fn test_backup_order_puts_counters_after_their_second() {
+        let prefix = "settings.json.";
+        let mut names = vec![
+            "settings.json.20240101-093000.10.bak",
+            "settings.json.20240101-093001.bak",
+            "settings.json.20240101-093000.2.bak",
+            "settings.json.20240101-093000.bak",
+        ];
+        names.sort_by_key(|name| ClaudeSettings::backup_order(name, prefix));
+
+        assert_eq!(
+            names,
+            [
+                "settings.json.20240101-093000.bak",
+                "settings.json.20240101-093000.2.bak",
+                "settings.json.20240101-093000.10.bak",
+                "settings.json.20240101-093001.bak",
+            ]
+        );
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    models::{NotificationInput, PermissionRequestInput},
+    session::SessionInfo,
+};
+
+/// Fields a permission request template can use
+pub const PERMISSION_FIELDS: [&str; 10] = [
+    "tool",
+    "command",
+    "description",
+    "file_path",
+    "input",
+    "project",
+    "branch",
+    "host",
+    "session",
+    "cwd",
+];
+
+/// Fields an idle notification template can use
+pub const IDLE_FIELDS: [&str; 6] = ["message", "project", "branch", "host", "session", "cwd"];
+
+/// Raw tool input is cut to this many characters in `{input}`
+const INPUT_FIELD_CHARS: usize = 200;
+
+/// User-defined notification titles and messages, replacing the built-in ones
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(try_from = "RawTemplates")]
+pub struct Templates {
+    /// PermissionRequest notifications by tool name, with "*" for any tool
+    /// without its own entry
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub permission: BTreeMap<String, Template>,
+    /// The Notification hook's "Claude is waiting" notification
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle: Option<Template>,
+}
+
+#[derive(Deserialize)]
+struct RawTemplates {
+    #[serde(default)]
+    permission: BTreeMap<String, Template>,
+    #[serde(default)]
+    idle: Option<Template>,
+}
+
+/// Fields must exist for the event, so a typo shows up when the config is
+/// loaded rather than as an empty notification
+impl TryFrom<RawTemplates> for Templates {
+    type Error = String;
+
+    fn try_from(raw: RawTemplates) -> Result<Self, Self::Error> {
+        for (tool, template) in &raw.permission {
+            template.check_fields(&PERMISSION_FIELDS, &format!("permission.{}", tool))?;
+        }
+        if let Some(template) = &raw.idle {
+            template.check_fields(&IDLE_FIELDS, "idle")?;
+        }
+        Ok(Templates {
+            permission: raw.permission,
+            idle: raw.idle,
+        })
+    }
+}
+
+/// A title and message template; either falls back to the built-in text
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<TemplateText>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<TemplateText>,
+}
+
+impl Template {
+    fn check_fields(&self, allowed: &[&str], name: &str) -> Result<(), String> {
+        for text in [&self.title, &self.message].into_iter().flatten() {
+            if let Some(field) = text.fields().find(|field| !allowed.contains(field)) {
+                return Err(format!(
+                    "Template {} can't use {{{}}}; available: {}",
+                    name,
+                    field,
+                    Self::field_list(allowed)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// "{tool}, {command}, …"
+    pub fn field_list(fields: &[&str]) -> String {
+        fields
+            .iter()
+            .map(|field| format!("{{{}}}", field))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The rendered title and message, using `default` for parts the template
+    /// doesn't set
+    pub fn render(
+        &self,
+        fields: &BTreeMap<&str, String>,
+        default: (String, String),
+    ) -> (String, String) {
+        (
+            self.title
+                .as_ref()
+                .map(|text| text.render(fields))
+                .unwrap_or(default.0),
+            self.message
+                .as_ref()
+                .map(|text| text.render(fields))
+                .unwrap_or(default.1),
+        )
+    }
+}
+
+impl Templates {
+    /// The template for a tool: its own entry (matched case-insensitively),
+    /// else the "*" entry
+    pub fn for_tool(&self, tool: &str) -> Option<(&str, &Template)> {
+        self.permission
+            .get_key_value(tool)
+            .or_else(|| {
+                self.permission
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(tool))
+            })
+            .or_else(|| self.permission.get_key_value("*"))
+            .map(|(name, template)| (name.as_str(), template))
+    }
+
+    /// Values for a permission request template
+    pub fn permission_fields(
+        request: &PermissionRequestInput,
+        session: &SessionInfo,
+    ) -> BTreeMap<&'static str, String> {
+        let input = |key: &str| {
+            request
+                .tool_input
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let raw_input = request.tool_input.to_string();
+        let raw_input = if raw_input.chars().count() > INPUT_FIELD_CHARS {
+            format!(
+                "{}...",
+                raw_input
+                    .chars()
+                    .take(INPUT_FIELD_CHARS)
+                    .collect::<String>()
+            )
+        } else {
+            raw_input
+        };
+        let file_path = Some(input("file_path"))
+            .filter(|path| !path.is_empty())
+            .unwrap_or_else(|| input("path"));
+
+        let mut fields = Self::session_fields(session, &request.cwd);
+        fields.insert("tool", request.tool_name.clone());
+        fields.insert("command", input("command"));
+        fields.insert("description", input("description"));
+        fields.insert("file_path", file_path);
+        fields.insert("input", raw_input);
+        fields
+    }
+
+    /// Values for an idle notification template
+    pub fn idle_fields(
+        notification: &NotificationInput,
+        session: &SessionInfo,
+    ) -> BTreeMap<&'static str, String> {
+        let mut fields = Self::session_fields(session, &notification.cwd);
+        fields.insert("message", notification.message.clone());
+        fields
+    }
+
+    fn session_fields(session: &SessionInfo, cwd: &str) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("project", session.project.clone()),
+            ("branch", session.branch.clone().unwrap_or_default()),
+            ("host", session.host.clone().unwrap_or_default()),
+            ("session", session.label.clone()),
+            ("cwd", cwd.to_string()),
+        ])
+    }
+}
+
+/// Text with `{field}` placeholders. `{{` and `}}` stand for literal braces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TemplateText {
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(String),
+}
+
+impl TemplateText {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid template \"{}\": {}", source, reason);
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) if c.is_ascii_alphanumeric() || c == '_' => name.push(c),
+                            Some(_) => return Err(invalid("field names are letters and _")),
+                            None => return Err(invalid("unclosed {")),
+                        }
+                    }
+                    if name.is_empty() {
+                        return Err(invalid("empty {}"));
+                    }
+                    if !PERMISSION_FIELDS.contains(&name.as_str())
+                        && !IDLE_FIELDS.contains(&name.as_str())
+                    {
+                        return Err(invalid(&format!("unknown field {{{}}}", name)));
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(name));
+                }
+                '}' => return Err(invalid("unmatched }, write }} for a literal brace")),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(TemplateText {
+            source: source.to_string(),
+            parts,
+        })
+    }
+
+    fn fields(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Field(name) => Some(name.as_str()),
+            Part::Text(_) => None,
+        })
+    }
+
+    /// Fill in the placeholders; fields without a value are left empty
+    pub fn render(&self, fields: &BTreeMap<&str, String>) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.as_str(),
+                Part::Field(name) => fields.get(name.as_str()).map_or("", String::as_str),
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<String> for TemplateText {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::parse(&source)
+    }
+}
+
+impl From<TemplateText> for String {
+    fn from(text: TemplateText) -> Self {
+        text.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields(pairs: &[(&'static str, &str)]) -> BTreeMap<&'static str, String> {
+        pairs.iter().map(|(k, v)| (*k, v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_render_fields_and_escapes() {
+        let text = TemplateText::parse("{project} ({branch}): run {{{command}}}?").unwrap();
+
+        assert_eq!(
+            text.render(&fields(&[
+                ("project", "webapp"),
+                ("branch", "main"),
+                ("command", "npm test")
+            ])),
+            "webapp (main): run {npm test}?"
+        );
+        // Missing values render empty
+        assert_eq!(text.render(&BTreeMap::new()), " (): run {}?");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (source, reason) in [
+            ("Run {command", "unclosed {"),
+            ("Run {}", "empty {}"),
+            ("Run {com mand}", "field names are letters and _"),
+            ("Run }", "unmatched }"),
+            ("Run {cmd}", "unknown field {cmd}"),
+        ] {
+            let err = TemplateText::parse(source).unwrap_err();
+            assert!(err.contains(reason), "{}: {}", source, err);
+        }
+    }
+
+    #[test]
+    fn test_templates_validated_per_event() {
+        let templates: Templates = toml::from_str(
+            r#"
+            [permission.Bash]
+            title = "{project}: {command}"
+            [idle]
+            message = "{session}: {message}"
+            "#,
+        )
+        .unwrap();
+        assert!(templates.idle.is_some());
+
+        let err = toml::from_str::<Templates>("[idle]\ntitle = \"Run {command}\"")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Template idle can't use {command}; available: {message}"));
+
+        let err = toml::from_str::<Templates>("[permission.Bash]\nbody = \"x\"")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown field `body`"));
+    }
+
+    #[test]
+    fn test_for_tool_falls_back_to_wildcard() {
+        let templates: Templates = toml::from_str(
+            "[permission.Bash]\ntitle = \"bash\"\n[permission.\"*\"]\ntitle = \"other\"",
+        )
+        .unwrap();
+
+        assert_eq!(templates.for_tool("Bash").unwrap().0, "Bash");
+        assert_eq!(templates.for_tool("bash").unwrap().0, "Bash");
+        assert_eq!(
+            templates.for_tool("mcp__github__create_issue").unwrap().0,
+            "*"
+        );
+        assert_eq!(Templates::default().for_tool("Bash"), None);
+    }
+
+    #[test]
+    fn test_partial_template_keeps_builtin_parts() {
+        let template = Template {
+            title: Some(TemplateText::parse("[{project}] {tool}").unwrap()),
+            message: None,
+        };
+
+        let (title, message) = template.render(
+            &fields(&[("project", "api"), ("tool", "Bash")]),
+            ("Run bash command? 🐚".to_string(), "ls".to_string()),
+        );
+
+        assert_eq!(title, "[api] Bash");
+        assert_eq!(message, "ls");
+    }
+
+    #[test]
+    fn test_permission_fields() {
+        let request: PermissionRequestInput = serde_json::from_value(json!({
+            "session_id": "s",
+            "transcript_path": "/tmp/t.jsonl",
+            "cwd": "/code/webapp",
+            "permission_mode": "default",
+            "hook_event_name": "PermissionRequest",
+            "tool_name": "Grep",
+            "tool_input": {"pattern": "TODO", "path": "src"}
+        }))
+        .unwrap();
+        let session = SessionInfo {
+            id: "s".to_string(),
+            label: "webapp (main)".to_string(),
+            project: "webapp".to_string(),
+            branch: Some("main".to_string()),
+            host: None,
+        };
+
+        let fields = Templates::permission_fields(&request, &session);
+
+        assert_eq!(fields["tool"], "Grep");
+        assert_eq!(fields["file_path"], "src");
+        assert_eq!(fields["command"], "");
+        assert_eq!(fields["input"], r#"{"pattern":"TODO","path":"src"}"#);
+        assert_eq!(fields["branch"], "main");
+        assert_eq!(fields["host"], "");
+        assert_eq!(fields.len(), PERMISSION_FIELDS.len());
+    }
+
+    #[test]
+    fn test_round_trips_through_toml() {
+        let source = "[permission.Bash]\ntitle = \"{{{project}}} {command}\"\n";
+        let templates: Templates = toml::from_str(source).unwrap();
+
+        assert_eq!(toml::to_string(&templates).unwrap(), source);
+    }
+}
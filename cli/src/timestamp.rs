@@ -0,0 +1,21 @@
+use jiff::Timestamp;
+
+/// Compact UTC timestamp safe for file names, e.g. "20240101-093000"
+pub fn file_timestamp() -> String {
+    format_file_timestamp(Timestamp::now())
+}
+
+fn format_file_timestamp(at: Timestamp) -> String {
+    at.strftime("%Y%m%d-%H%M%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_timestamp_is_utc_and_sortable() {
+        let at: Timestamp = "2024-01-01T09:30:00+02:00".parse().unwrap();
+        assert_eq!(format_file_timestamp(at), "20240101-073000");
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use serde_json::Value;
+
+use crate::{
+    constants::{
+        CONTEXT_PROMPT_CHARS, CONTEXT_REASONING_CHARS, CONTEXT_SUMMARY_CHARS, TRANSCRIPT_TAIL_BYTES,
+    },
+    models::NotifyContext,
+};
+
+/// Prefixes of user messages Claude Code writes itself (slash commands,
+/// interruptions) rather than the user typing them
+const SYNTHETIC_PROMPT_PREFIXES: [&str; 4] = [
+    "<command-",
+    "<local-command-",
+    "<system-reminder>",
+    "[Request interrupted",
+];
+
+/// Reads conversation context from a Claude Code session transcript (JSONL)
+pub struct Transcript;
+
+impl Transcript {
+    /// The last user prompt and the assistant text that led to the pending tool
+    /// call. Best-effort: any read or parse problem just means no context.
+    pub fn context(path: &Path) -> Option<NotifyContext> {
+        let tail = Self::read_tail(path, TRANSCRIPT_TAIL_BYTES).ok()?;
+        Self::context_from_lines(&tail)
+    }
+
+    /// Read at most `max_bytes` from the end of the file, dropping the first
+    /// (probably partial) line when the file was cut
+    fn read_tail(path: &Path, max_bytes: u64) -> std::io::Result<String> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let start = len.saturating_sub(max_bytes);
+        file.seek(SeekFrom::Start(start))?;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let text = String::from_utf8_lossy(&bytes).to_string();
+
+        if start == 0 {
+            return Ok(text);
+        }
+        Ok(text
+            .split_once('\n')
+            .map(|(_, rest)| rest.to_string())
+            .unwrap_or_default())
+    }
+
+    fn context_from_lines(content: &str) -> Option<NotifyContext> {
+        let mut prompt: Option<String> = None;
+        let mut reasoning: Option<String> = None;
+        let mut reasoning_message_id: Option<String> = None;
+
+        for line in content.lines() {
+            let Ok(entry) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            if entry.get("isMeta").and_then(Value::as_bool) == Some(true)
+                || entry.get("isSidechain").and_then(Value::as_bool) == Some(true)
+            {
+                continue;
+            }
+            let Some(message) = entry.get("message") else {
+                continue;
+            };
+
+            match entry.get("type").and_then(Value::as_str) {
+                Some("user") => {
+                    if let Some(text) = Self::user_prompt(message) {
+                        // A new prompt starts a new turn, so earlier reasoning no longer applies
+                        prompt = Some(text);
+                        reasoning = None;
+                        reasoning_message_id = None;
+                    }
+                }
+                Some("assistant") => {
+                    let text = Self::text_blocks(message);
+                    if text.is_empty() {
+                        continue;
+                    }
+                    // Claude Code writes one line per content block, so text from
+                    // the same message is joined rather than replaced
+                    let id = message.get("id").and_then(Value::as_str).map(String::from);
+                    match (&mut reasoning, id.is_some() && id == reasoning_message_id) {
+                        (Some(existing), true) => {
+                            existing.push(' ');
+                            existing.push_str(&text);
+                        }
+                        _ => reasoning = Some(text),
+                    }
+                    reasoning_message_id = id;
+                }
+                _ => {}
+            }
+        }
+
+        let prompt = prompt.map(|p| Self::truncate(&p, CONTEXT_PROMPT_CHARS));
+        let reasoning = reasoning.map(|r| Self::truncate(&r, CONTEXT_REASONING_CHARS));
+        let summary = match (&reasoning, &prompt) {
+            (Some(reasoning), _) => Self::truncate(reasoning, CONTEXT_SUMMARY_CHARS),
+            (None, Some(prompt)) => {
+                Self::truncate(&format!("You asked: {}", prompt), CONTEXT_SUMMARY_CHARS)
+            }
+            (None, None) => return None,
+        };
+
+        Some(NotifyContext {
+            summary,
+            prompt,
+            reasoning,
+        })
+    }
+
+    /// Text the user typed, or None for tool results and messages Claude Code
+    /// injects on its own
+    fn user_prompt(message: &Value) -> Option<String> {
+        let text = match message.get("content")? {
+            Value::String(s) => s.clone(),
+            Value::Array(_) => Self::text_blocks(message),
+            _ => return None,
+        };
+        let trimmed = text.trim();
+        if trimmed.is_empty()
+            || SYNTHETIC_PROMPT_PREFIXES
+                .iter()
+                .any(|prefix| trimmed.starts_with(prefix))
+        {
+            return None;
+        }
+        Some(trimmed.to_string())
+    }
+
+    /// Concatenated `text` blocks of a message, ignoring thinking and tool blocks
+    fn text_blocks(message: &Value) -> String {
+        message
+            .get("content")
+            .and_then(Value::as_array)
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter(|b| b.get("type").and_then(Value::as_str) == Some("text"))
+                    .filter_map(|b| b.get("text").and_then(Value::as_str))
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default()
+    }
+
+    /// Collapse whitespace to single spaces and cut to `max_chars`, marking the
+    /// cut with an ellipsis
+    fn truncate(text: &str, max_chars: usize) -> String {
+        let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.chars().count() <= max_chars {
+            return collapsed;
+        }
+        let cut: String = collapsed
+            .chars()
+            .take(max_chars.saturating_sub(1))
+            .collect();
+        format!("{}…", cut.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn jsonl(entries: &[Value]) -> String {
+        entries
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn user(content: Value) -> Value {
+        json!({"type": "user", "message": {"role": "user", "content": content}})
+    }
+
+    fn assistant(id: &str, content: Value) -> Value {
+        json!({"type": "assistant", "message": {"id": id, "role": "assistant", "content": content}})
+    }
+
+    #[test]
+    fn test_context_uses_last_prompt_and_latest_reasoning() {
+        let content = jsonl(&[
+            user(json!("first question")),
+            assistant("m1", json!([{"type": "text", "text": "old answer"}])),
+            user(json!("Why are the tests failing?")),
+            assistant(
+                "m2",
+                json!([{"type": "thinking", "thinking": "private"}, {"type": "text", "text": "Let me run the test suite."}]),
+            ),
+            assistant(
+                "m2",
+                json!([{"type": "tool_use", "id": "t1", "name": "Bash", "input": {}}]),
+            ),
+        ]);
+
+        let context = Transcript::context_from_lines(&content).unwrap();
+
+        assert_eq!(
+            context.prompt.as_deref(),
+            Some("Why are the tests failing?")
+        );
+        assert_eq!(
+            context.reasoning.as_deref(),
+            Some("Let me run the test suite.")
+        );
+        assert_eq!(context.summary, "Let me run the test suite.");
+    }
+
+    #[test]
+    fn test_context_skips_tool_results_and_synthetic_messages() {
+        let content = jsonl(&[
+            user(json!("Refactor the parser")),
+            user(json!([{"type": "tool_result", "tool_use_id": "t1", "content": "ok"}])),
+            user(json!("<command-name>/clear</command-name>")),
+            json!({"type": "user", "isMeta": true, "message": {"role": "user", "content": "Caveat"}}),
+        ]);
+
+        let context = Transcript::context_from_lines(&content).unwrap();
+
+        assert_eq!(context.prompt.as_deref(), Some("Refactor the parser"));
+        assert_eq!(context.reasoning, None);
+        assert_eq!(context.summary, "You asked: Refactor the parser");
+    }
+
+    #[test]
+    fn test_context_joins_text_blocks_of_the_same_message() {
+        let content = jsonl(&[
+            user(json!("go")),
+            assistant("m1", json!([{"type": "text", "text": "First,"}])),
+            assistant("m1", json!([{"type": "text", "text": "then this."}])),
+        ]);
+
+        let context = Transcript::context_from_lines(&content).unwrap();
+
+        assert_eq!(context.reasoning.as_deref(), Some("First, then this."));
+    }
+
+    #[test]
+    fn test_reasoning_before_new_prompt_is_dropped() {
+        let content = jsonl(&[
+            assistant("m1", json!([{"type": "text", "text": "stale"}])),
+            user(json!("new request")),
+        ]);
+
+        let context = Transcript::context_from_lines(&content).unwrap();
+
+        assert_eq!(context.reasoning, None);
+    }
+
+    #[test]
+    fn test_context_none_without_prompt_or_reasoning() {
+        assert_eq!(Transcript::context_from_lines(""), None);
+        assert_eq!(Transcript::context_from_lines("not json\n{}"), None);
+    }
+
+    #[test]
+    fn test_context_respects_size_budget() {
+        let long = "word ".repeat(500);
+        let content = jsonl(&[
+            user(json!(long)),
+            assistant("m1", json!([{"type": "text", "text": long}])),
+        ]);
+
+        let context = Transcript::context_from_lines(&content).unwrap();
+
+        assert_eq!(
+            context.prompt.unwrap().chars().count(),
+            CONTEXT_PROMPT_CHARS
+        );
+        assert!(context.reasoning.unwrap().chars().count() <= CONTEXT_REASONING_CHARS);
+        assert!(context.summary.chars().count() <= CONTEXT_SUMMARY_CHARS);
+        assert!(context.summary.ends_with('…'));
+    }
+
+    #[test]
+    fn test_truncate_collapses_whitespace_and_is_char_safe() {
+        assert_eq!(Transcript::truncate("a\n\n  b\tc", 10), "a b c");
+        assert_eq!(Transcript::truncate("héllo wörld", 6), "héllo…");
+    }
+
+    #[test]
+    fn test_read_tail_drops_partial_first_line() {
+        let path = std::env::temp_dir().join(format!(
+            "claude-afk-transcript-{}.jsonl",
+            std::process::id()
+        ));
+        std::fs::write(&path, "first line\nsecond line\nthird\n").unwrap();
+
+        let tail = Transcript::read_tail(&path, 15).unwrap();
+        let whole = Transcript::read_tail(&path, 1024).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(tail, "third\n");
+        assert_eq!(whole, "first line\nsecond line\nthird\n");
+    }
+
+    #[test]
+    fn test_context_missing_file() {
+        assert_eq!(
+            Transcript::context(Path::new("/nonexistent/transcript.jsonl")),
+            None
+        );
+    }
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use std::{
+    fs,
+    io::Write,
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+    process::{Child, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{fixture, stderr, stdout, CliEnv, MockBackend};
+use serde_json::{json, Value};
+
+/// A PATH holding only a fake `loginctl` that prints `properties`, so the idle
+/// and lock state doesn't depend on the machine running the tests
+fn fake_logind(env: &CliEnv, properties: Option<&str>) -> PathBuf {
+    let bin = env.home().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    if let Some(properties) = properties {
+        let script = bin.join("loginctl");
+        fs::write(
+            &script,
+            format!("#!/bin/sh\nprintf '{}'\n", properties.replace('\n', "\\n")),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    bin
+}
+
+fn auto_env(backend: &MockBackend) -> CliEnv {
+    let env = CliEnv::new(&backend.url);
+    env.write_config(&format!(
+        "device_token = \"device-token-1\"\nbackend_url = \"{}\"\nactive = \"auto\"\n",
+        backend.url
+    ));
+    env
+}
+
+fn spawn_hook(env: &CliEnv, path: &PathBuf, input: &str) -> Child {
+    let mut child = env
+        .command(&["notify"])
+        .env("PATH", path)
+        .env("CLAUDE_AFK_DECISION_TIMEOUT_MS", "10000")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child
+}
+
+fn run_hook(env: &CliEnv, path: &PathBuf) -> Output {
+    spawn_hook(env, path, &fixture("permission_bash.json"))
+        .wait_with_output()
+        .unwrap()
+}
+
+#[test]
+fn test_auto_notifies_while_locked() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = auto_env(&backend);
+    let path = fake_logind(&env, Some("LockedHint=yes\nIdleHint=no\nIdleSinceHint=0\n"));
+
+    let output = run_hook(&env, &path);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(backend.requests_to("/api/notify").len(), 1);
+    assert!(stdout(&output).contains("\"behavior\":\"allow\""));
+}
+
+#[test]
+fn test_auto_stays_quiet_while_present() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = auto_env(&backend);
+    let path = fake_logind(&env, Some("LockedHint=no\nIdleHint=no\nIdleSinceHint=0\n"));
+
+    let output = run_hook(&env, &path);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+    assert!(backend.requests().is_empty());
+
+    let status = env
+        .command(&["status", "--json"])
+        .env("PATH", &path)
+        .output()
+        .unwrap();
+    let report: Value = serde_json::from_str(&stdout(&status)).unwrap();
+    assert_eq!(report["mode"], "auto");
+    assert_eq!(report["active"], false);
+    assert_eq!(report["presence"]["locked"], false);
+    assert_eq!(report["presence"]["source"], "logind");
+}
+
+#[test]
+fn test_terminal_answer_after_returning_retracts_prompt() {
+    let backend = MockBackend::cancellable();
+    let env = auto_env(&backend);
+    let path = fake_logind(&env, Some("LockedHint=yes\nIdleHint=no\nIdleSinceHint=0\n"));
+
+    // Sent while away; the hook keeps polling
+    let child = spawn_hook(&env, &path, &fixture("permission_bash.json"));
+    let start = Instant::now();
+    while backend.requests_to("/api/decision/dec-1/status").is_empty() {
+        assert!(
+            start.elapsed() < Duration::from_secs(2),
+            "prompt never reached the phone"
+        );
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    // Back at the keyboard, so auto mode is now quiet, and the prompt is
+    // answered at the terminal
+    fake_logind(&env, Some("LockedHint=no\nIdleHint=no\nIdleSinceHint=0\n"));
+    let output = spawn_hook(&env, &path, &fixture("post_tool_use_bash.json"))
+        .wait_with_output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(backend.requests_to("/api/decision/dec-1/cancel").len(), 1);
+
+    let output = child.wait_with_output().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn test_auto_with_unknown_idle_state_does_not_notify() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = auto_env(&backend);
+    let path = fake_logind(&env, None);
+
+    let output = run_hook(&env, &path);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(backend.requests().is_empty());
+
+    let status = env
+        .command(&["status"])
+        .env("PATH", &path)
+        .output()
+        .unwrap();
+    assert!(stdout(&status).contains("Auto · idle state unknown"));
+}
+
+#[test]
+fn test_auto_command_sets_mode_and_threshold() {
+    let env = CliEnv::paired("http://127.0.0.1:9");
+    let path = fake_logind(&env, Some("LockedHint=yes\n"));
+
+    let output = env
+        .command(&["auto", "--idle-after", "10m"])
+        .env("PATH", &path)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Right now: away (screen locked)"));
+    let config = env.config();
+    assert_eq!(config["active"].as_str(), Some("auto"));
+    assert_eq!(config["idle_threshold"].as_integer(), Some(600));
+
+    let output = env.run(&["auto", "--idle-after", "soon"]);
+    assert!(!output.status.success());
+}
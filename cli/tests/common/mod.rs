@@ -0,0 +1,345 @@
+//! In-process mock of the claude-afk backend and helpers to run the CLI
+//! binary against it with an isolated config directory.
+
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use serde_json::Value;
+
+/// A request received by the mock backend
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).expect("request body is JSON")
+    }
+}
+
+/// A canned response from the mock backend
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(value: Value) -> Self {
+        Response {
+            status: 200,
+            body: value.to_string(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Response {
+            status,
+            body: r#"{"message":"error"}"#.to_string(),
+        }
+    }
+
+    pub fn raw(status: u16, body: &str) -> Self {
+        Response {
+            status,
+            body: body.to_string(),
+        }
+    }
+}
+
+type Handler = dyn Fn(&Request) -> Response + Send + Sync;
+
+/// HTTP server on a random local port answering every request with `handler`
+pub struct MockBackend {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockBackend {
+    pub fn start(handler: impl Fn(&Request) -> Response + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock backend");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let recorded = Arc::clone(&recorded);
+                let handler = Arc::clone(&handler);
+                thread::spawn(move || Self::serve(stream, &recorded, handler.as_ref()));
+            }
+        });
+
+        MockBackend { url, requests }
+    }
+
+    /// Answer decision polls with a fixed status after `pending_polls`
+    /// "pending" responses, and accept notifications with a fixed decision id
+    pub fn with_decision(pending_polls: usize, status: Value) -> Self {
+        let polls = AtomicUsize::new(0);
+        Self::start(move |req| match (req.method.as_str(), req.path.as_str()) {
+            ("POST", "/api/notify") => Response::json(serde_json::json!({
+                "success": true,
+                "decisionId": "dec-1"
+            })),
+            ("GET", "/api/decision/dec-1/status") => {
+                if polls.fetch_add(1, Ordering::SeqCst) < pending_polls {
+                    Response::json(serde_json::json!({ "status": "pending" }))
+                } else {
+                    Response::json(status.clone())
+                }
+            }
+            _ => Response::status(404),
+        })
+    }
+
+    /// Keep decision `dec-1` pending until it is cancelled
+    pub fn cancellable() -> Self {
+        let cancelled = AtomicBool::new(false);
+        Self::start(move |req| match (req.method.as_str(), req.path.as_str()) {
+            ("POST", "/api/notify") => Response::json(serde_json::json!({
+                "success": true,
+                "decisionId": "dec-1"
+            })),
+            ("GET", "/api/decision/dec-1/status") if cancelled.load(Ordering::SeqCst) => {
+                Response::json(serde_json::json!({ "status": "cancelled", "decision": null }))
+            }
+            ("GET", "/api/decision/dec-1/status") => {
+                Response::json(serde_json::json!({ "status": "pending" }))
+            }
+            ("POST", "/api/decision/dec-1/cancel") => {
+                cancelled.store(true, Ordering::SeqCst);
+                Response::json(serde_json::json!({ "success": true, "cancelled": true }))
+            }
+            _ => Response::status(404),
+        })
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn requests_to(&self, path: &str) -> Vec<Request> {
+        self.requests()
+            .into_iter()
+            .filter(|r| r.path == path)
+            .collect()
+    }
+
+    fn serve(stream: TcpStream, recorded: &Mutex<Vec<Request>>, handler: &Handler) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let Some(request) = Self::read_request(&mut reader) else {
+            return;
+        };
+        let response = handler(&request);
+        recorded.lock().unwrap().push(request);
+
+        let mut stream = stream;
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response.status,
+            response.body.len(),
+            response.body
+        );
+        let _ = stream.flush();
+    }
+
+    fn read_request(reader: &mut BufReader<TcpStream>) -> Option<Request> {
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).ok()?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next()?.to_string();
+        let path = parts.next()?.to_string();
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).ok()?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let mut body = Vec::new();
+        if let Some(len) = headers.get("content-length") {
+            body.resize(len.parse().ok()?, 0);
+            reader.read_exact(&mut body).ok()?;
+        } else if headers
+            .get("transfer-encoding")
+            .is_some_and(|v| v.eq_ignore_ascii_case("chunked"))
+        {
+            loop {
+                let mut size = String::new();
+                reader.read_line(&mut size).ok()?;
+                let size = usize::from_str_radix(size.trim(), 16).ok()?;
+                let mut chunk = vec![0; size + 2];
+                reader.read_exact(&mut chunk).ok()?;
+                if size == 0 {
+                    break;
+                }
+                body.extend_from_slice(&chunk[..size]);
+            }
+        }
+
+        Some(Request {
+            method,
+            path,
+            headers,
+            body: String::from_utf8_lossy(&body).to_string(),
+        })
+    }
+}
+
+/// An isolated home and config directory for one CLI invocation sequence
+pub struct CliEnv {
+    home: PathBuf,
+    backend_url: String,
+}
+
+impl CliEnv {
+    pub fn new(backend_url: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let home = std::env::temp_dir().join(format!(
+            "claude-afk-it-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        CliEnv {
+            home,
+            backend_url: backend_url.to_string(),
+        }
+    }
+
+    /// A paired install with notifications switched on
+    pub fn paired(backend_url: &str) -> Self {
+        let env = Self::new(backend_url);
+        env.write_config(&format!(
+            "device_token = \"device-token-1\"\nbackend_url = \"{}\"\nactive = true\n",
+            backend_url
+        ));
+        env
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.home
+            .join(".config")
+            .join("claude-afk")
+            .join("default-config.toml")
+    }
+
+    pub fn write_config(&self, toml: &str) {
+        let path = self.config_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, toml).unwrap();
+    }
+
+    pub fn config(&self) -> toml::Value {
+        toml::from_str(&fs::read_to_string(self.config_path()).unwrap()).unwrap()
+    }
+
+    pub fn home(&self) -> &Path {
+        &self.home
+    }
+
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_claude-afk"));
+        command
+            .args(args)
+            .env("HOME", &self.home)
+            .env("XDG_CONFIG_HOME", self.home.join(".config"))
+            .env("CLAUDE_AFK_API_URL", &self.backend_url)
+            .env("CLAUDE_AFK_POLL_INTERVAL_MS", "10")
+            .env("CLAUDE_AFK_DECISION_TIMEOUT_MS", "500")
+            .env("CLAUDE_AFK_SETUP_TIMEOUT_MS", "500")
+            .env("NO_COLOR", "1");
+        command
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command(args)
+            .stdin(Stdio::null())
+            .output()
+            .expect("run claude-afk")
+    }
+
+    /// Run with `input` piped to stdin, as Claude Code runs hooks
+    pub fn run_hook(&self, input: &str) -> Output {
+        let mut child = self
+            .command(&["notify", "--hook-id", "claude-afk"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("spawn claude-afk");
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().expect("wait for claude-afk")
+    }
+}
+
+impl Drop for CliEnv {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.home);
+    }
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+/// A recorded hook payload from tests/fixtures/hooks
+pub fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("hooks")
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|_| panic!("missing fixture {}", path.display()))
+}
+
+/// Path to a session transcript from tests/fixtures/transcripts
+pub fn transcript_fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("transcripts")
+        .join(name)
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{fixture, stderr, stdout, CliEnv, MockBackend};
+use serde_json::{json, Value};
+
+fn history(env: &CliEnv, filters: &[&str]) -> Vec<Value> {
+    let mut args = vec!["history", "--json"];
+    args.extend_from_slice(filters);
+    let output = env.run(&args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    stdout(&output)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("history --json prints JSON lines"))
+        .collect()
+}
+
+#[test]
+fn test_remote_decision_is_audited() {
+    let backend = MockBackend::with_decision(
+        1,
+        json!({
+            "status": "decided",
+            "decision": "allow",
+            "source": "notification",
+            "device": "Chrome on Android"
+        }),
+    );
+    let env = CliEnv::paired(&backend.url);
+
+    env.run_hook(&fixture("permission_bash.json"));
+
+    let entries = history(&env, &[]);
+    assert_eq!(entries.len(), 1);
+    let entry = &entries[0];
+    assert_eq!(entry["session_id"], "5f0c8a52-2d4b-4c1e-9a53-1b2f4f6d7e80");
+    assert_eq!(entry["tool"], "Bash");
+    assert_eq!(entry["input"]["command"], "npm test -- --coverage");
+    assert_eq!(entry["decision"], "allow");
+    assert_eq!(entry["source"], "notification");
+    assert_eq!(entry["device"], "Chrome on Android");
+    assert_eq!(entry["transport"], "web-push");
+    assert_eq!(entry["tool_use_id"], "toolu_01ABCDefGhijKlmnOPqrStuv");
+    assert!(entry["time"].as_str().unwrap().ends_with('Z'));
+    assert!(entry["latency_ms"].as_u64().is_some());
+}
+
+#[test]
+fn test_timeouts_and_secrets() {
+    let backend = MockBackend::with_decision(usize::MAX, json!({}));
+    let env = CliEnv::paired(&backend.url);
+    let mut input: Value = serde_json::from_str(&fixture("permission_bash.json")).unwrap();
+    input["tool_input"]["command"] = json!("GITHUB_TOKEN=ghp_secret gh release create");
+
+    env.run_hook(&input.to_string());
+
+    let entries = history(&env, &["--decision", "timeout"]);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        entries[0]["input"]["command"],
+        "GITHUB_TOKEN=[REDACTED] gh release create"
+    );
+    assert!(entries[0].get("device").is_none());
+}
+
+#[test]
+fn test_filters() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "deny"}));
+    let env = CliEnv::paired(&backend.url);
+
+    env.run_hook(&fixture("permission_bash.json"));
+    env.run_hook(&fixture("permission_write.json"));
+
+    assert_eq!(history(&env, &[]).len(), 2);
+    assert_eq!(history(&env, &["--tool", "write"]).len(), 1);
+    assert_eq!(history(&env, &["--decision", "allow"]).len(), 0);
+    assert_eq!(history(&env, &["--session", "5f0c8a52"]).len(), 2);
+    assert_eq!(history(&env, &["--session", "nope"]).len(), 0);
+    assert_eq!(history(&env, &["--since", "1h"]).len(), 2);
+    assert_eq!(history(&env, &["--since", "2999-01-01"]).len(), 0);
+
+    let text = stdout(&env.run(&["history", "--tool", "Bash"]));
+    assert!(text.contains("deny"));
+    assert!(text.contains("npm test -- --coverage"));
+}
+
+#[test]
+fn test_invalid_since() {
+    let backend = MockBackend::with_decision(0, json!({}));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run(&["history", "--since", "last tuesday"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Invalid --since value"));
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use std::process::Output;
+
+use common::{stderr, stdout, CliEnv};
+use serde_json::{json, Value};
+
+/// Run in the isolated home, so project settings of the checkout running the
+/// tests don't count
+fn run_in_home(env: &CliEnv, args: &[&str]) -> Output {
+    env.command(args).current_dir(env.home()).output().unwrap()
+}
+
+fn status(env: &CliEnv) -> (Value, String) {
+    let report = stdout(&run_in_home(env, &["status", "--json"]));
+    let text = stdout(&run_in_home(env, &["status"]));
+    (serde_json::from_str(&report).unwrap(), text)
+}
+
+#[test]
+fn test_status_reports_partial_install() {
+    let env = CliEnv::paired("http://127.0.0.1:9");
+    let output = run_in_home(&env, &["install-hooks", "--event", "post-tool-use"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let (report, text) = status(&env);
+
+    assert_eq!(report["hooks_installed"], false);
+    assert_eq!(report["hook_scopes"], json!(["user"]));
+    assert_eq!(report["hook_events"], json!(["PostToolUse"]));
+    assert!(text.contains("Partial (user) · PostToolUse only"));
+}
+
+#[test]
+fn test_status_reports_install_across_scopes() {
+    let env = CliEnv::paired("http://127.0.0.1:9");
+    let output = run_in_home(
+        &env,
+        &[
+            "install-hooks",
+            "--event",
+            "permission-request,post-tool-use",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let (report, _) = status(&env);
+    assert_eq!(report["hooks_installed"], false);
+
+    // Claude Code merges scopes, so Notification may come from another one
+    std::fs::create_dir_all(env.home().join(".git")).unwrap();
+    let output = run_in_home(
+        &env,
+        &[
+            "install-hooks",
+            "--scope",
+            "local",
+            "--event",
+            "notification",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let (report, text) = status(&env);
+    assert_eq!(report["hooks_installed"], true);
+    assert_eq!(report["hook_scopes"], json!(["user", "local"]));
+    assert_eq!(
+        report["hook_events"],
+        json!(["PermissionRequest", "Notification", "PostToolUse"])
+    );
+    assert!(text.contains("Installed (user, local) · PermissionRequest, Notification, PostToolUse"));
+}
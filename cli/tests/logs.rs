@@ -0,0 +1,105 @@
+mod common;
+
+use std::{io::Write, process::Stdio};
+
+use common::{fixture, stderr, stdout, CliEnv, MockBackend};
+use serde_json::{json, Value};
+
+/// Run the hook with `CLAUDE_AFK_LOG` set to `level`, or unset for `None`
+fn run_hook_with_log(env: &CliEnv, level: Option<&str>) {
+    let mut command = env.command(&["notify"]);
+    match level {
+        Some(level) => command.env("CLAUDE_AFK_LOG", level),
+        None => command.env_remove("CLAUDE_AFK_LOG"),
+    };
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(fixture("permission_bash.json").as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn test_level_and_json_format_from_config() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = CliEnv::paired(&backend.url);
+    let config = std::fs::read_to_string(env.config_path()).unwrap();
+    env.write_config(&format!(
+        "{}\n[log]\nlevel = \"info\"\nformat = \"json\"\n",
+        config
+    ));
+
+    // Without the env var, the config file decides
+    run_hook_with_log(&env, None);
+
+    let output = env.run(&["logs", "--lines", "10"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let entries: Vec<Value> = stdout(&output)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("JSON log line"))
+        .collect();
+
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e["level"] == "info"));
+    assert!(entries[0]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Permission request "));
+    assert!(entries[1]["message"]
+        .as_str()
+        .unwrap()
+        .ends_with("answered: Allow"));
+}
+
+#[test]
+fn test_env_level_overrides_config() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = CliEnv::paired(&backend.url);
+
+    run_hook_with_log(&env, Some("off"));
+    let log = env.config_path().with_file_name("debug.log");
+    assert!(!log.exists());
+
+    run_hook_with_log(&env, Some("debug"));
+    let content = std::fs::read_to_string(&log).unwrap();
+    assert!(content.contains("] DEBUG - PermissionRequest hook for session 5f0c8a52"));
+    assert!(content.contains("] INFO - Permission request "));
+}
+
+#[test]
+fn test_path_and_clear() {
+    let env = CliEnv::paired("http://127.0.0.1:9");
+    let log = env.config_path().with_file_name("debug.log");
+
+    let output = env.run(&["logs", "--path"]);
+    assert_eq!(stdout(&output).trim(), log.display().to_string());
+
+    std::fs::write(&log, "[2026-03-01 10:00:00] INFO - old\n").unwrap();
+    std::fs::write(log.with_file_name("debug.log.1"), "older\n").unwrap();
+
+    let output = env.run(&["logs", "--lines", "1"]);
+    assert_eq!(stdout(&output), "[2026-03-01 10:00:00] INFO - old\n");
+
+    let output = env.run(&["logs", "--clear"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Logs cleared"));
+    assert!(!log.exists());
+    assert!(!log.with_file_name("debug.log.1").exists());
+
+    let output = env
+        .command(&["logs"])
+        .env("CLAUDE_AFK_LOG", "off")
+        .output()
+        .unwrap();
+    assert!(stdout(&output).contains("No log entries yet"));
+    assert!(stdout(&output).contains("Logging is off"));
+}
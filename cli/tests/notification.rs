@@ -0,0 +1,66 @@
+mod common;
+
+use common::{fixture, stderr, stdout, CliEnv, MockBackend, Response};
+use serde_json::json;
+
+#[test]
+fn test_idle_prompt_sends_simple_notification() {
+    let backend = MockBackend::start(|_| Response::json(json!({"success": true})));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("notification_idle_prompt.json"));
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).is_empty());
+    let requests = backend.requests_to("/api/notify/simple");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].header("authorization"),
+        Some("Bearer device-token-1")
+    );
+    assert_eq!(requests[0].json()["title"], "Claude is waiting");
+    assert_eq!(
+        requests[0].json()["message"],
+        "Claude is waiting for your input"
+    );
+    let session = &requests[0].json()["session"];
+    assert_eq!(session["id"], "5f0c8a52-2d4b-4c1e-9a53-1b2f4f6d7e80");
+    assert_eq!(session["project"], "project");
+}
+
+#[test]
+fn test_other_notification_types_are_ignored() {
+    let backend = MockBackend::start(|_| Response::json(json!({"success": true})));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("notification_permission_prompt.json"));
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(backend.requests().is_empty());
+}
+
+#[test]
+fn test_revoked_token_is_reported_without_blocking() {
+    let backend = MockBackend::start(|_| Response::status(401));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("notification_idle_prompt.json"));
+
+    // Exit 1 is a non-blocking error whose stderr Claude Code shows
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+    assert!(stderr(&output).contains("Device pairing has been revoked or has expired"));
+    assert!(stderr(&output).contains("claude-afk pair"));
+}
+
+#[test]
+fn test_unknown_hook_event() {
+    let backend = MockBackend::start(|_| Response::status(500));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("unknown_event.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Unknown hook event: SessionStart"));
+    assert!(backend.requests().is_empty());
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{stdout, CliEnv, MockBackend, Response};
+use serde_json::{json, Value};
+
+fn json_line(output: &std::process::Output) -> Value {
+    serde_json::from_str(stdout(output).trim()).expect("stdout is a JSON line")
+}
+
+fn pairing_backend(pending_polls: usize) -> MockBackend {
+    let polls = AtomicUsize::new(0);
+    MockBackend::start(move |req| match (req.method.as_str(), req.path.as_str()) {
+        ("POST", "/api/pairing/initiate") => Response::json(json!({
+            "pairingId": "pair-1",
+            "pairingToken": "tok-1",
+            "pairingCode": "ABCDEFGH"
+        })),
+        ("GET", "/api/pairing/pair-1/status") => {
+            if polls.fetch_add(1, Ordering::SeqCst) < pending_polls {
+                Response::json(json!({ "complete": false }))
+            } else {
+                Response::json(json!({
+                    "complete": true,
+                    "deviceToken": "device-token-new",
+                    "expiresAt": 1900000000,
+                    "rotateAfter": 1800000000
+                }))
+            }
+        }
+        _ => Response::status(404),
+    })
+}
+
+#[test]
+fn test_pair_json_then_wait_stores_token() {
+    let backend = pairing_backend(2);
+    let env = CliEnv::new(&backend.url);
+
+    let started = env.run(&["pair", "--json"]);
+    assert!(started.status.success());
+    let line = json_line(&started);
+    assert_eq!(line["status"], "pending");
+    assert_eq!(line["pairing_id"], "pair-1");
+    assert_eq!(line["pairing_url"], format!("{}/pair/tok-1", backend.url));
+    assert_eq!(line["pairing_code"], "ABCDEFGH");
+
+    let finished = env.run(&["pair", "--wait", "pair-1", "--json"]);
+    assert!(finished.status.success());
+    assert_eq!(json_line(&finished)["status"], "paired");
+
+    let config = env.config();
+    assert_eq!(config["device_token"].as_str(), Some("device-token-new"));
+    assert_eq!(config["active"].as_str(), Some("on"));
+    assert_eq!(config["token_expires_at"].as_integer(), Some(1900000000));
+    assert_eq!(config["backend_url"].as_str(), Some(backend.url.as_str()));
+}
+
+#[test]
+fn test_interactive_pair_completes() {
+    let backend = pairing_backend(1);
+    let env = CliEnv::new(&backend.url);
+
+    let output = env.run(&["pair", "--qr-format", "ascii"]);
+
+    assert!(output.status.success());
+    let text = stdout(&output);
+    assert!(text.contains("ABCD-EFGH"));
+    assert!(text.contains("Pairing successful!"));
+    assert_eq!(
+        env.config()["device_token"].as_str(),
+        Some("device-token-new")
+    );
+}
+
+#[test]
+fn test_pair_wait_times_out() {
+    let backend = pairing_backend(usize::MAX);
+    let env = CliEnv::new(&backend.url);
+
+    let output = env.run(&["pair", "--wait", "pair-1", "--json"]);
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(json_line(&output)["error"], "timeout");
+}
+
+#[test]
+fn test_pair_wait_rejected_pairing() {
+    let backend = MockBackend::start(|_| Response::status(404));
+    let env = CliEnv::new(&backend.url);
+
+    let output = env.run(&["pair", "--wait", "pair-1", "--json"]);
+
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(json_line(&output)["error"], "rejected");
+}
+
+#[test]
+fn test_pair_wait_after_backend_expiry_times_out() {
+    // Pending, but created long enough ago that the backend's expiry passed
+    let backend = MockBackend::start(|_| {
+        Response::json(json!({ "complete": false, "pairingExpiresAt": 1700000000 }))
+    });
+    let env = CliEnv::new(&backend.url);
+
+    let output = env
+        .command(&["pair", "--wait", "pair-1", "--json"])
+        .env("CLAUDE_AFK_SETUP_TIMEOUT_MS", "60000")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(json_line(&output)["error"], "timeout");
+    assert_eq!(backend.requests().len(), 1);
+
+    // Once expired, the backend answers 410
+    let backend = MockBackend::start(|_| Response::status(410));
+    let output = CliEnv::new(&backend.url).run(&["pair", "--wait", "pair-1", "--json"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(json_line(&output)["error"], "timeout");
+}
+
+#[test]
+fn test_pair_server_error() {
+    let backend = MockBackend::start(|_| Response::status(500));
+    let env = CliEnv::new(&backend.url);
+
+    let output = env.run(&["pair", "--json"]);
+
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(json_line(&output)["error"], "network");
+}
+
+#[test]
+fn test_pair_malformed_response() {
+    let backend = MockBackend::start(|_| Response::raw(200, "not json"));
+    let env = CliEnv::new(&backend.url);
+
+    let output = env.run(&["pair", "--json"]);
+
+    assert_eq!(output.status.code(), Some(5));
+}
@@ -0,0 +1,282 @@
+mod common;
+
+use common::{fixture, stderr, stdout, transcript_fixture, CliEnv, MockBackend, Response};
+use serde_json::{json, Value};
+
+fn hook_output(output: &std::process::Output) -> Value {
+    serde_json::from_str(stdout(output).trim()).expect("stdout is a HookOutput")
+}
+
+#[test]
+fn test_allow_prints_allow_decision() {
+    let backend = MockBackend::with_decision(2, json!({"status": "decided", "decision": "allow"}));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let hook = hook_output(&output);
+    assert_eq!(
+        hook["hookSpecificOutput"]["hookEventName"],
+        "PermissionRequest"
+    );
+    assert_eq!(hook["hookSpecificOutput"]["decision"]["behavior"], "allow");
+    assert_eq!(backend.requests_to("/api/decision/dec-1/status").len(), 3);
+}
+
+#[test]
+fn test_notification_payload_and_auth() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = CliEnv::paired(&backend.url);
+
+    env.run_hook(&fixture("permission_bash.json"));
+
+    let notify = &backend.requests_to("/api/notify")[0];
+    assert_eq!(
+        notify.header("authorization"),
+        Some("Bearer device-token-1")
+    );
+    let payload = notify.json();
+    assert_eq!(payload["title"], "Run bash command? 🐚");
+    assert_eq!(payload["tool_use_id"], "toolu_01ABCDefGhijKlmnOPqrStuv");
+    // CLAUDE_AFK_DECISION_TIMEOUT_MS=500, rounded up
+    assert_eq!(payload["timeout"], 1);
+    assert!(payload["message"]
+        .as_str()
+        .unwrap()
+        .contains("npm test -- --coverage"));
+
+    let poll = &backend.requests_to("/api/decision/dec-1/status")[0];
+    assert_eq!(poll.header("authorization"), Some("Bearer device-token-1"));
+}
+
+#[test]
+fn test_notification_carries_session_label() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = CliEnv::paired(&backend.url);
+    let repo = env.home().join("work").join("billing");
+    std::fs::create_dir_all(repo.join(".git")).unwrap();
+    std::fs::write(repo.join(".git").join("HEAD"), "ref: refs/heads/release\n").unwrap();
+    let mut input: Value = serde_json::from_str(&fixture("permission_bash.json")).unwrap();
+    input["cwd"] = json!(repo.join("src"));
+
+    env.run_hook(&input.to_string());
+    env.write_config(&format!(
+        "device_token = \"device-token-1\"\nbackend_url = \"{}\"\nactive = true\n\n[labels]\nbilling = \"Billing (prod)\"\n",
+        backend.url
+    ));
+    env.run_hook(&input.to_string());
+
+    let notifies = backend.requests_to("/api/notify");
+    let detected = &notifies[0].json()["session"];
+    assert_eq!(detected["id"], "5f0c8a52-2d4b-4c1e-9a53-1b2f4f6d7e80");
+    assert_eq!(detected["project"], "billing");
+    assert_eq!(detected["branch"], "release");
+    assert!(detected["label"]
+        .as_str()
+        .unwrap()
+        .starts_with("billing (release)"));
+    let overridden = &notifies[1].json()["session"];
+    assert_eq!(overridden["label"], "Billing (prod)");
+    assert_eq!(overridden["branch"], "release");
+}
+
+#[test]
+fn test_notification_includes_transcript_context() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = CliEnv::paired(&backend.url);
+    let mut input: Value = serde_json::from_str(&fixture("permission_bash.json")).unwrap();
+    input["transcript_path"] = json!(transcript_fixture("session.jsonl"));
+
+    let output = env.run_hook(&input.to_string());
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let context = &backend.requests_to("/api/notify")[0].json()["context"];
+    assert_eq!(
+        context["prompt"],
+        "The coverage report is missing the parser module, can you check why?"
+    );
+    assert_eq!(
+        context["reasoning"],
+        "I'll run the test suite with coverage to see which files are included."
+    );
+    assert!(context["summary"]
+        .as_str()
+        .unwrap()
+        .starts_with("I'll run the test suite"));
+}
+
+#[test]
+fn test_notification_without_transcript_has_no_context() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = CliEnv::paired(&backend.url);
+
+    env.run_hook(&fixture("permission_bash.json"));
+
+    assert!(backend.requests_to("/api/notify")[0]
+        .json()
+        .get("context")
+        .is_none());
+}
+
+#[test]
+fn test_deny_prints_deny_decision() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "deny"}));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_write.json"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let hook = hook_output(&output);
+    assert_eq!(hook["hookSpecificOutput"]["decision"]["behavior"], "deny");
+    assert_eq!(hook["hookSpecificOutput"]["decision"]["interrupt"], true);
+}
+
+#[test]
+fn test_dismiss_falls_back_silently() {
+    let backend =
+        MockBackend::with_decision(0, json!({"status": "decided", "decision": "dismiss"}));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_edit.json"));
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn test_timeout_falls_back_with_error() {
+    let backend = MockBackend::with_decision(usize::MAX, json!({}));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+    assert!(stderr(&output).contains("Decision timed out"));
+}
+
+#[test]
+fn test_expired_decision_falls_back_with_error() {
+    let backend = MockBackend::with_decision(0, json!({"status": "expired"}));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+    assert!(stderr(&output).contains("Decision timed out"));
+}
+
+#[test]
+fn test_unknown_decision_falls_back_with_error() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "maybe"}));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Unknown decision"));
+}
+
+#[test]
+fn test_revoked_token_reports_pairing_hint() {
+    let backend = MockBackend::start(|_| Response::status(401));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+    assert!(stderr(&output).contains("claude-afk pair"));
+}
+
+#[test]
+fn test_revoked_token_while_polling() {
+    let backend = MockBackend::start(|req| match req.path.as_str() {
+        "/api/notify" => Response::json(json!({"success": true, "decisionId": "dec-1"})),
+        _ => Response::status(401),
+    });
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("claude-afk pair"));
+}
+
+#[test]
+fn test_server_error_falls_back_with_error() {
+    let backend = MockBackend::start(|_| Response::status(500));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+    assert!(stderr(&output).contains("Failed to send notification"));
+}
+
+#[test]
+fn test_malformed_notify_response() {
+    let backend = MockBackend::start(|_| Response::raw(200, "<html>Bad gateway</html>"));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Failed to parse notify response"));
+}
+
+#[test]
+fn test_malformed_decision_status() {
+    let backend = MockBackend::start(|req| match req.path.as_str() {
+        "/api/notify" => Response::json(json!({"success": true, "decisionId": "dec-1"})),
+        _ => Response::json(json!({"state": "done"})),
+    });
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Failed to parse decision status"));
+}
+
+#[test]
+fn test_malformed_hook_input() {
+    let backend = MockBackend::start(|_| Response::status(500));
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("malformed_missing_tool_input.json"));
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Failed to parse PermissionRequest input"));
+    assert!(backend.requests().is_empty());
+}
+
+#[test]
+fn test_inactive_install_does_not_contact_backend() {
+    let backend = MockBackend::start(|_| Response::status(500));
+    let env = CliEnv::new(&backend.url);
+    env.write_config(&format!(
+        "device_token = \"device-token-1\"\nbackend_url = \"{}\"\nactive = false\n",
+        backend.url
+    ));
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).is_empty());
+    assert!(backend.requests().is_empty());
+}
+
+#[test]
+fn test_unpaired_install_does_not_contact_backend() {
+    let backend = MockBackend::start(|_| Response::status(500));
+    let env = CliEnv::new(&backend.url);
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(backend.requests().is_empty());
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Output, Stdio},
+};
+
+use common::{fixture, stderr, stdout, CliEnv, MockBackend, Response};
+use serde_json::{json, Value};
+
+/// A config with a default device on `personal` and a `work` profile with its
+/// own backend and device for everything under ~/work
+fn profiles_env(personal: &MockBackend, work: &MockBackend) -> CliEnv {
+    let env = CliEnv::new(&personal.url);
+    env.write_config(&format!(
+        r#"device_token = "personal-token"
+backend_url = "{}"
+active = "on"
+
+[profiles.work]
+paths = ["~/work/*"]
+backend_url = "{}"
+device_token = "work-token"
+"#,
+        personal.url, work.url
+    ));
+    env
+}
+
+fn project_dir(env: &CliEnv, path: &str) -> PathBuf {
+    let dir = env.home().join(path);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run the permission hook for a session in `cwd`, with the backend coming
+/// from the config rather than CLAUDE_AFK_API_URL
+fn run_hook_in(env: &CliEnv, cwd: &Path, default_backend: Option<&str>) -> Output {
+    let mut input: Value = serde_json::from_str(&fixture("permission_bash.json")).unwrap();
+    input["cwd"] = json!(cwd);
+    let mut command = env.command(&["notify"]);
+    match default_backend {
+        Some(url) => command.env("CLAUDE_AFK_API_URL", url),
+        None => command.env_remove("CLAUDE_AFK_API_URL"),
+    };
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.to_string().as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn allow() -> Value {
+    json!({"status": "decided", "decision": "allow"})
+}
+
+#[test]
+fn test_profile_selected_by_path_uses_its_backend_and_device() {
+    let personal = MockBackend::with_decision(0, allow());
+    let work = MockBackend::with_decision(0, allow());
+    let env = profiles_env(&personal, &work);
+    let cwd = project_dir(&env, "work/api/src");
+
+    let output = run_hook_in(&env, &cwd, None);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let sent = work.requests_to("/api/notify");
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].header("authorization"), Some("Bearer work-token"));
+    assert!(personal.requests().is_empty());
+}
+
+#[test]
+fn test_other_projects_keep_the_default_device() {
+    let personal = MockBackend::with_decision(0, allow());
+    let work = MockBackend::with_decision(0, allow());
+    let env = profiles_env(&personal, &work);
+    let cwd = project_dir(&env, "code/side-project");
+
+    let output = run_hook_in(&env, &cwd, Some(&personal.url));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let sent = personal.requests_to("/api/notify");
+    assert_eq!(sent.len(), 1);
+    assert_eq!(
+        sent[0].header("authorization"),
+        Some("Bearer personal-token")
+    );
+    assert!(work.requests().is_empty());
+}
+
+#[test]
+fn test_project_file_picks_profile_and_can_switch_off() {
+    let personal = MockBackend::with_decision(0, allow());
+    let work = MockBackend::with_decision(0, allow());
+    let env = profiles_env(&personal, &work);
+
+    let repo = project_dir(&env, "clients/acme");
+    fs::write(repo.join(".claude-afk.toml"), "profile = \"work\"\n").unwrap();
+    let output = run_hook_in(&env, &repo.join("lib"), None);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(work.requests_to("/api/notify").len(), 1);
+
+    let quiet = project_dir(&env, "code/dotfiles");
+    fs::write(quiet.join(".claude-afk.toml"), "active = \"off\"\n").unwrap();
+    let output = run_hook_in(&env, &quiet, Some(&personal.url));
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(personal.requests().is_empty());
+}
+
+#[test]
+fn test_unknown_profile_falls_back_to_terminal() {
+    let personal = MockBackend::with_decision(0, allow());
+    let work = MockBackend::with_decision(0, allow());
+    let env = profiles_env(&personal, &work);
+    let repo = project_dir(&env, "code/typo");
+    fs::write(repo.join(".claude-afk.toml"), "profile = \"wrok\"\n").unwrap();
+
+    let output = run_hook_in(&env, &repo, Some(&personal.url));
+
+    assert!(output.status.success());
+    assert!(stderr(&output).contains("Profile 'wrok'"));
+    assert!(personal.requests().is_empty());
+    assert!(work.requests().is_empty());
+}
+
+#[test]
+fn test_rotation_under_shared_profile_updates_default_token() {
+    let backend = MockBackend::start(|req| match (req.method.as_str(), req.path.as_str()) {
+        ("POST", "/api/device/rotate") => Response::json(json!({
+            "deviceToken": "rotated-token",
+            "expiresAt": 1900000000,
+            "rotateAfter": 1800000000
+        })),
+        ("POST", "/api/notify") => Response::json(json!({"success": true, "decisionId": "dec-1"})),
+        ("GET", "/api/decision/dec-1/status") => Response::json(allow()),
+        _ => Response::status(404),
+    });
+    let env = CliEnv::new(&backend.url);
+    env.write_config(&format!(
+        r#"device_token = "personal-token"
+backend_url = "{}"
+active = "on"
+token_rotate_after = 1
+
+[profiles.work]
+paths = ["~/work/*"]
+
+[profiles.work.schedule]
+timezone = "UTC"
+"#,
+        backend.url
+    ));
+    let cwd = project_dir(&env, "work/api");
+
+    let output = run_hook_in(&env, &cwd, Some(&backend.url));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let notify = &backend.requests_to("/api/notify")[0];
+    assert_eq!(notify.header("authorization"), Some("Bearer rotated-token"));
+    let config = env.config();
+    assert_eq!(config["device_token"].as_str(), Some("rotated-token"));
+    assert_eq!(config["token_rotate_after"].as_integer(), Some(1800000000));
+    assert!(config["profiles"]["work"].get("device_token").is_none());
+}
+
+#[test]
+fn test_status_shows_profile_for_current_directory() {
+    let personal = MockBackend::with_decision(0, allow());
+    let work = MockBackend::with_decision(0, allow());
+    let env = profiles_env(&personal, &work);
+    let cwd = project_dir(&env, "work/api");
+
+    let output = env
+        .command(&["status", "--json"])
+        .env_remove("CLAUDE_AFK_API_URL")
+        .current_dir(&cwd)
+        .output()
+        .unwrap();
+
+    let report: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(report["profile"], "work");
+    assert_eq!(report["backend_url"], work.url);
+
+    let output = env.command(&["status"]).current_dir(&cwd).output().unwrap();
+    assert!(stdout(&output).contains("Profile         work (from ~/work/*)"));
+}
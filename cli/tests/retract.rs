@@ -0,0 +1,143 @@
+mod common;
+
+use std::{
+    io::Write,
+    process::{Child, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{fixture, stderr, stdout, CliEnv, MockBackend};
+use serde_json::Value;
+
+fn sessions(env: &CliEnv) -> Vec<Value> {
+    serde_json::from_str(&stdout(&env.run(&["sessions", "--json"]))).unwrap()
+}
+
+/// A fixture with its tool_use_id removed, as some Claude Code versions send it
+fn without_tool_use_id(name: &str) -> String {
+    let mut input: Value = serde_json::from_str(&fixture(name)).unwrap();
+    input.as_object_mut().unwrap().remove("tool_use_id");
+    input.to_string()
+}
+
+/// Start a PermissionRequest hook and wait until its prompt is on the phone
+fn spawn_blocked_prompt(env: &CliEnv, input: &str) -> (Child, Instant) {
+    let mut child = env
+        .command(&["notify"])
+        .env("CLAUDE_AFK_DECISION_TIMEOUT_MS", "10000")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    let start = Instant::now();
+    while !sessions(env).first().is_some_and(|s| s["blocked"] == true) {
+        assert!(
+            start.elapsed() < Duration::from_secs(2),
+            "prompt never reached the phone"
+        );
+        thread::sleep(Duration::from_millis(20));
+    }
+    (child, start)
+}
+
+/// The phone prompt was cancelled and the blocked hook gave up quietly,
+/// well before its timeout
+fn assert_retracted(backend: &MockBackend, env: &CliEnv, child: Child, start: Instant) {
+    let cancels = backend.requests_to("/api/decision/dec-1/cancel");
+    assert_eq!(cancels.len(), 1);
+    assert_eq!(
+        cancels[0].header("authorization"),
+        Some("Bearer device-token-1")
+    );
+
+    let output = child.wait_with_output().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(stderr(&output), "");
+
+    let session = &sessions(env)[0];
+    assert_eq!(session["blocked"], false);
+    assert_eq!(session["terminal"], 1);
+    assert_eq!(session["unanswered"], 0);
+}
+
+#[test]
+fn test_terminal_answer_retracts_phone_prompt() {
+    let backend = MockBackend::cancellable();
+    let env = CliEnv::paired(&backend.url);
+    let (child, start) = spawn_blocked_prompt(&env, &fixture("permission_bash.json"));
+
+    // The prompt is answered at the terminal and the tool runs
+    let output = env.run_hook(&fixture("post_tool_use_bash.json"));
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+
+    assert_retracted(&backend, &env, child, start);
+
+    let history = stdout(&env.run(&["history", "--json"]));
+    let entry: Value = serde_json::from_str(history.lines().next().unwrap()).unwrap();
+    assert_eq!(entry["decision"], "terminal");
+}
+
+#[test]
+fn test_failed_tool_call_retracts_phone_prompt() {
+    let backend = MockBackend::cancellable();
+    let env = CliEnv::paired(&backend.url);
+    let (child, start) = spawn_blocked_prompt(&env, &fixture("permission_bash.json"));
+
+    // Allowed at the terminal, but the command fails
+    let output = env.run_hook(&fixture("post_tool_use_failure_bash.json"));
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+
+    assert_retracted(&backend, &env, child, start);
+}
+
+#[test]
+fn test_prompt_without_tool_use_id_is_retracted_by_tool_input() {
+    let backend = MockBackend::cancellable();
+    let env = CliEnv::paired(&backend.url);
+    let (child, start) = spawn_blocked_prompt(&env, &without_tool_use_id("permission_bash.json"));
+
+    let output = env.run_hook(&fixture("post_tool_use_bash.json"));
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    assert_retracted(&backend, &env, child, start);
+}
+
+#[test]
+fn test_next_prompt_retracts_prompt_denied_at_terminal() {
+    let backend = MockBackend::cancellable();
+    let env = CliEnv::paired(&backend.url);
+    let (child, start) = spawn_blocked_prompt(&env, &fixture("permission_bash.json"));
+
+    // Denied at the terminal, so no tool hook runs; the user types instead
+    let output = env.run_hook(&fixture("user_prompt_submit.json"));
+    assert!(output.status.success(), "{}", stderr(&output));
+    // Anything printed here would be added to the user's prompt
+    assert_eq!(stdout(&output), "");
+
+    assert_retracted(&backend, &env, child, start);
+}
+
+#[test]
+fn test_post_tool_use_without_pending_prompt_does_nothing() {
+    let backend = MockBackend::cancellable();
+    let env = CliEnv::paired(&backend.url);
+
+    let output = env.run_hook(&fixture("post_tool_use_bash.json"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+    assert!(backend.requests().is_empty());
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::{fixture, stderr, stdout, CliEnv, MockBackend};
+use serde_json::{json, Value};
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+fn scheduled_env(backend: &MockBackend, extra: &str) -> CliEnv {
+    let env = CliEnv::new(&backend.url);
+    env.write_config(&format!(
+        "device_token = \"device-token-1\"\nbackend_url = \"{}\"\n{}",
+        backend.url, extra
+    ));
+    env
+}
+
+#[test]
+fn test_quiet_hours_fall_back_to_terminal() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = scheduled_env(
+        &backend,
+        "active = \"on\"\n[schedule]\ntimezone = \"UTC\"\nquiet = [\"00:00-24:00\"]\n",
+    );
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+    assert!(backend.requests().is_empty());
+
+    let status = env.run(&["status", "--json"]);
+    let report: Value = serde_json::from_str(&stdout(&status)).unwrap();
+    assert_eq!(report["active"], false);
+    assert_eq!(report["schedule_hold"], "quiet hours (00:00-24:00)");
+    assert!(stdout(&env.run(&["status"])).contains("Quiet hours (00:00-24:00)"));
+}
+
+#[test]
+fn test_working_hours_allow_notifications() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = scheduled_env(
+        &backend,
+        "active = \"on\"\n[schedule]\nhours = [\"daily 00:00-24:00\"]\n",
+    );
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(backend.requests_to("/api/notify").len(), 1);
+}
+
+#[test]
+fn test_afk_for_turns_itself_off() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = scheduled_env(&backend, "active = \"on\"\n");
+
+    let output = env.run(&["afk", "--for", "2h"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Back to off afterwards"));
+    let config = env.config();
+    assert_eq!(config["active"].as_str(), Some("off"));
+    let until = config["afk_until"].as_integer().unwrap();
+    assert!((until - unix_now() - 7200).abs() < 60);
+
+    let report: Value = serde_json::from_str(&stdout(&env.run(&["status", "--json"]))).unwrap();
+    assert_eq!(report["active"], true);
+    assert_eq!(report["afk_until"], until);
+
+    // `back` ends the timed afk early
+    assert!(env.run(&["back"]).status.success());
+    assert!(env.config().get("afk_until").is_none());
+}
+
+#[test]
+fn test_expired_afk_does_not_notify() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = scheduled_env(
+        &backend,
+        &format!("active = \"off\"\nafk_until = {}\n", unix_now() - 60),
+    );
+
+    let output = env.run_hook(&fixture("permission_bash.json"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(backend.requests().is_empty());
+}
+
+#[test]
+fn test_afk_until_rejects_bad_times() {
+    let env = CliEnv::paired("http://127.0.0.1:9");
+
+    let output = env.run(&["afk", "--until", "teatime"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Invalid --until value 'teatime'"));
+
+    let output = env.run(&["afk", "--for", "2h", "--until", "17:30"]);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use std::{
+    io::Write,
+    process::Stdio,
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{fixture, stderr, stdout, CliEnv, MockBackend};
+use serde_json::{json, Value};
+
+fn sessions(env: &CliEnv) -> Vec<Value> {
+    let output = env.run(&["sessions", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    serde_json::from_str(&stdout(&output)).expect("sessions --json prints an array")
+}
+
+#[test]
+fn test_answered_request_is_counted() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = CliEnv::paired(&backend.url);
+
+    env.run_hook(&fixture("permission_bash.json"));
+
+    let sessions = sessions(&env);
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0]["id"], "5f0c8a52-2d4b-4c1e-9a53-1b2f4f6d7e80");
+    assert_eq!(sessions[0]["cwd"], "~/project");
+    assert_eq!(sessions[0]["allowed"], 1);
+    assert_eq!(sessions[0]["blocked"], false);
+    assert_eq!(sessions[0]["pending"], json!([]));
+}
+
+#[test]
+fn test_waiting_request_shows_as_blocked() {
+    let backend = MockBackend::with_decision(usize::MAX, json!({}));
+    let env = CliEnv::paired(&backend.url);
+
+    let mut child = env
+        .command(&["notify"])
+        .env("CLAUDE_AFK_DECISION_TIMEOUT_MS", "3000")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(fixture("permission_bash.json").as_bytes())
+        .unwrap();
+
+    let start = Instant::now();
+    let blocked = loop {
+        let listed = sessions(&env);
+        if listed.first().is_some_and(|s| s["blocked"] == true) {
+            break listed[0].clone();
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(2),
+            "session never showed as blocked"
+        );
+        thread::sleep(Duration::from_millis(20));
+    };
+    assert_eq!(blocked["pending"][0]["decision_id"], "dec-1");
+    assert_eq!(blocked["pending"][0]["title"], "Run bash command? 🐚");
+
+    let text = stdout(&env.run(&["sessions"]));
+    assert!(text.contains("waiting on you"));
+    assert!(text.contains("Run bash command?"));
+
+    child.wait().unwrap();
+    let after = sessions(&env);
+    assert_eq!(after[0]["blocked"], false);
+    assert_eq!(after[0]["unanswered"], 1);
+}
+
+#[test]
+fn test_sessions_are_tracked_while_inactive() {
+    let backend = MockBackend::with_decision(0, json!({}));
+    let env = CliEnv::new(&backend.url);
+
+    env.run_hook(&fixture("notification_idle_prompt.json"));
+
+    assert_eq!(sessions(&env).len(), 1);
+    assert!(backend.requests().is_empty());
+}
+
+#[test]
+fn test_no_sessions() {
+    let backend = MockBackend::with_decision(0, json!({}));
+    let env = CliEnv::new(&backend.url);
+
+    let output = env.run(&["sessions"]);
+
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("No active sessions"));
+    assert_eq!(sessions(&env), Vec::<Value>::new());
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use std::fs;
+
+use common::{fixture, stderr, stdout, CliEnv, MockBackend};
+use serde_json::{json, Value};
+
+const TEMPLATES: &str = r#"
+[templates.permission.Bash]
+title = "[{project}] {command}"
+
+[templates.permission."*"]
+title = "{tool} wants to run"
+message = "{input}"
+
+[templates.idle]
+title = "{project} is waiting"
+"#;
+
+fn templated_env(backend: &MockBackend) -> CliEnv {
+    let env = CliEnv::new(&backend.url);
+    env.write_config(&format!(
+        "device_token = \"device-token-1\"\nbackend_url = \"{}\"\nactive = \"on\"\n{}",
+        backend.url, TEMPLATES
+    ));
+    env
+}
+
+fn fixture_path(name: &str) -> String {
+    format!(
+        "{}/tests/fixtures/hooks/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    )
+}
+
+/// A hook payload with its working directory moved to `~/webapp`
+fn in_webapp(env: &CliEnv, name: &str) -> String {
+    let cwd = env.home().join("webapp");
+    fs::create_dir_all(&cwd).unwrap();
+    let mut input: Value = serde_json::from_str(&fixture(name)).unwrap();
+    input["cwd"] = json!(cwd);
+    input.to_string()
+}
+
+#[test]
+fn test_permission_template_renders_fields() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = templated_env(&backend);
+
+    let output = env.run_hook(&in_webapp(&env, "permission_bash.json"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let sent = backend.requests_to("/api/notify")[0].json();
+    assert_eq!(sent["title"], "[webapp] npm test -- --coverage");
+    // The template sets no message, so the built-in one is kept
+    assert!(sent["message"]
+        .as_str()
+        .unwrap()
+        .contains("npm test -- --coverage"));
+}
+
+#[test]
+fn test_wildcard_template_covers_other_tools() {
+    let backend = MockBackend::with_decision(0, json!({"status": "decided", "decision": "allow"}));
+    let env = templated_env(&backend);
+
+    let output = env.run_hook(&in_webapp(&env, "permission_mcp_tool.json"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let sent = backend.requests_to("/api/notify")[0].json();
+    assert!(sent["title"].as_str().unwrap().ends_with(" wants to run"));
+    assert!(sent["message"].as_str().unwrap().starts_with('{'));
+}
+
+#[test]
+fn test_idle_template() {
+    let backend = MockBackend::start(|_| common::Response::json(json!({"success": true})));
+    let env = templated_env(&backend);
+
+    let output = env.run_hook(&in_webapp(&env, "notification_idle_prompt.json"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let sent = backend.requests_to("/api/notify/simple")[0].json();
+    assert_eq!(sent["title"], "webapp is waiting");
+}
+
+#[test]
+fn test_preview_shows_rendered_text_and_fields() {
+    let env = templated_env(&MockBackend::start(|_| common::Response::status(500)));
+    let cwd = env.home().join("webapp");
+    fs::create_dir_all(&cwd).unwrap();
+
+    let output = env
+        .command(&["preview", "--tool", "bash"])
+        .current_dir(&cwd)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("Bash permission request ([templates.permission.\"Bash\"])"));
+    assert!(text.contains("[webapp] echo \"Hello from claude-afk\""));
+    assert!(text.contains("{file_path}"));
+
+    let output = env
+        .command(&["preview", "--tool", "write"])
+        .current_dir(&cwd)
+        .output()
+        .unwrap();
+    assert!(stdout(&output).contains("Write wants to run"));
+
+    let output = env.run(&["preview", &fixture_path("notification_idle_prompt.json")]);
+    assert!(stdout(&output).contains("Idle notification ([templates.idle])"));
+}
+
+#[test]
+fn test_invalid_template_is_reported_at_load() {
+    let env = CliEnv::new("http://127.0.0.1:9");
+    env.write_config(
+        "backend_url = \"\"\nactive = \"on\"\n[templates.permission.Bash]\ntitle = \"Run {cmd}\"\n",
+    );
+
+    let output = env.run(&["preview"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Invalid template \"Run {cmd}\": unknown field {cmd}"));
+}
@@ -0,0 +1,4 @@
+ALTER TABLE `pairing_sessions` ADD `token_expires_at` integer;--> statement-breakpoint
+ALTER TABLE `pairing_sessions` ADD `previous_device_token` text;--> statement-breakpoint
+ALTER TABLE `pairing_sessions` ADD `previous_token_expires_at` integer;--> statement-breakpoint
+UPDATE `pairing_sessions` SET `token_expires_at` = `completed_at` + 7776000 WHERE `completed_at` IS NOT NULL;
\ No newline at end of file
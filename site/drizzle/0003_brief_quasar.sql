@@ -0,0 +1,2 @@
+ALTER TABLE `pairing_sessions` ADD `pairing_code` text;--> statement-breakpoint
+CREATE UNIQUE INDEX `pairing_sessions_pairing_code_unique` ON `pairing_sessions` (`pairing_code`);
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `pairing_sessions` ADD `subscription_expired_at` integer;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `pending_decisions` ADD `context` text;
\ No newline at end of file
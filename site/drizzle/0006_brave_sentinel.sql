@@ -0,0 +1 @@
+ALTER TABLE `pending_decisions` ADD `session_label` text;
\ No newline at end of file
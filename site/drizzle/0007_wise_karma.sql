@@ -0,0 +1,2 @@
+ALTER TABLE `pending_decisions` ADD `decision_source` text;--> statement-breakpoint
+ALTER TABLE `pending_decisions` ADD `decided_by` text;
\ No newline at end of file
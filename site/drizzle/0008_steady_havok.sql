@@ -0,0 +1,3 @@
+ALTER TABLE `pending_decisions` ADD `pairing_session_id` text;--> statement-breakpoint
+UPDATE `pending_decisions` SET `pairing_session_id` = (SELECT `id` FROM `pairing_sessions` WHERE `pairing_sessions`.`device_token` = `pending_decisions`.`device_token` OR `pairing_sessions`.`previous_device_token` = `pending_decisions`.`device_token`);--> statement-breakpoint
+ALTER TABLE `pending_decisions` DROP COLUMN `device_token`;
\ No newline at end of file
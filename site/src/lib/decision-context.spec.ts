@@ -0,0 +1,46 @@
+import { describe, it, expect } from 'vitest';
+import {
+	CONTEXT_LIMITS,
+	notificationBody,
+	parseStoredContext,
+	sanitizeContext
+} from './decision-context';
+
+describe('decision context', () => {
+	it('keeps known string fields', () => {
+		expect(
+			sanitizeContext({ summary: ' Running tests ', prompt: 'Fix CI', reasoning: 42, extra: 'x' })
+		).toEqual({ summary: 'Running tests', prompt: 'Fix CI' });
+	});
+
+	it('rejects contexts without a summary', () => {
+		expect(sanitizeContext(null)).toBeNull();
+		expect(sanitizeContext('text')).toBeNull();
+		expect(sanitizeContext([])).toBeNull();
+		expect(sanitizeContext({ prompt: 'Fix CI' })).toBeNull();
+		expect(sanitizeContext({ summary: '   ' })).toBeNull();
+	});
+
+	it('caps each field', () => {
+		const context = sanitizeContext({
+			summary: 'é'.repeat(1000),
+			reasoning: 'r'.repeat(1000)
+		});
+		expect(Array.from(context!.summary)).toHaveLength(CONTEXT_LIMITS.summary);
+		expect(context!.summary.endsWith('…')).toBe(true);
+		expect(context!.reasoning).toHaveLength(CONTEXT_LIMITS.reasoning);
+	});
+
+	it('parses stored context and ignores bad rows', () => {
+		expect(parseStoredContext('{"summary":"s"}')).toEqual({ summary: 's' });
+		expect(parseStoredContext('not json')).toBeNull();
+		expect(parseStoredContext(null)).toBeNull();
+	});
+
+	it('appends the summary to the notification body', () => {
+		expect(notificationBody('npm test', { summary: 'Checking coverage' })).toBe(
+			'npm test\n\n💬 Checking coverage'
+		);
+		expect(notificationBody('npm test', null)).toBe('npm test');
+	});
+});
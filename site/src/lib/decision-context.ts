@@ -0,0 +1,65 @@
+/**
+ * Conversation context the CLI attaches to a permission request: a one-line
+ * summary for the notification body, plus the prompt and reasoning shown on
+ * the decision details page.
+ */
+export interface DecisionContext {
+	summary: string;
+	prompt?: string;
+	reasoning?: string;
+}
+
+// Server-side caps, slightly above the CLI's own budget so well-behaved
+// clients are never cut, while push payloads stay well under 4 KB
+export const CONTEXT_LIMITS = {
+	summary: 160,
+	prompt: 400,
+	reasoning: 600
+} as const;
+
+function clip(value: unknown, max: number): string | undefined {
+	if (typeof value !== 'string') {
+		return undefined;
+	}
+	const trimmed = value.trim();
+	if (!trimmed) {
+		return undefined;
+	}
+	const chars = Array.from(trimmed);
+	return chars.length > max ? chars.slice(0, max - 1).join('') + '…' : trimmed;
+}
+
+/** Validate and cap an untrusted context object; anything unusable becomes null */
+export function sanitizeContext(raw: unknown): DecisionContext | null {
+	if (!raw || typeof raw !== 'object' || Array.isArray(raw)) {
+		return null;
+	}
+	const value = raw as Record<string, unknown>;
+	const summary = clip(value.summary, CONTEXT_LIMITS.summary);
+	if (!summary) {
+		return null;
+	}
+	const context: DecisionContext = { summary };
+	const prompt = clip(value.prompt, CONTEXT_LIMITS.prompt);
+	const reasoning = clip(value.reasoning, CONTEXT_LIMITS.reasoning);
+	if (prompt) context.prompt = prompt;
+	if (reasoning) context.reasoning = reasoning;
+	return context;
+}
+
+/** Parse a context stored in the database, tolerating old or bad rows */
+export function parseStoredContext(stored: string | null): DecisionContext | null {
+	if (!stored) {
+		return null;
+	}
+	try {
+		return sanitizeContext(JSON.parse(stored));
+	} catch {
+		return null;
+	}
+}
+
+/** Notification body with the context summary on its own line */
+export function notificationBody(message: string, context: DecisionContext | null): string {
+	return context ? `${message}\n\n💬 ${context.summary}` : message;
+}
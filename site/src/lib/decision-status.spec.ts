@@ -0,0 +1,27 @@
+import { describe, it, expect } from 'vitest';
+import { CANCELLED_DECISION, decisionStatus } from './decision-status';
+
+describe('decision status', () => {
+	const now = new Date('2026-03-01T10:00:00Z');
+	const later = new Date('2026-03-01T10:05:00Z');
+	const earlier = new Date('2026-03-01T09:55:00Z');
+
+	it('is pending until decided or expired', () => {
+		expect(decisionStatus({ decision: null, expiresAt: later }, now)).toBe('pending');
+		expect(decisionStatus({ decision: null, expiresAt: earlier }, now)).toBe('expired');
+	});
+
+	it('reports phone decisions as decided, even after expiry', () => {
+		expect(decisionStatus({ decision: 'allow', expiresAt: later }, now)).toBe('decided');
+		expect(decisionStatus({ decision: 'dismiss', expiresAt: earlier }, now)).toBe('decided');
+	});
+
+	it('reports prompts answered at the terminal as cancelled', () => {
+		expect(decisionStatus({ decision: CANCELLED_DECISION, expiresAt: later }, now)).toBe(
+			'cancelled'
+		);
+		expect(decisionStatus({ decision: CANCELLED_DECISION, expiresAt: earlier }, now)).toBe(
+			'cancelled'
+		);
+	});
+});
@@ -0,0 +1,25 @@
+/**
+ * Stored in `pending_decisions.decision` when the prompt was answered at the
+ * terminal before the phone. The CLI retracts the phone prompt via
+ * POST /api/decision/[id]/cancel.
+ */
+export const CANCELLED_DECISION = 'cancelled';
+
+export type DecisionStatus = 'pending' | 'decided' | 'cancelled' | 'expired';
+
+/**
+ * Where a permission request stands. A cancelled request stays cancelled
+ * after it expires, so a late poll still lets the hook exit quietly.
+ */
+export function decisionStatus(
+	record: { decision: string | null; expiresAt: Date },
+	now: Date = new Date()
+): DecisionStatus {
+	if (record.decision === CANCELLED_DECISION) {
+		return 'cancelled';
+	}
+	if (record.decision) {
+		return 'decided';
+	}
+	return record.expiresAt < now ? 'expired' : 'pending';
+}
@@ -0,0 +1,34 @@
+import { describe, it, expect } from 'vitest';
+import {
+	formatPairingCode,
+	generatePairingCode,
+	isValidPairingCode,
+	normalizePairingCode,
+	PAIRING_CODE_LENGTH
+} from './pairing-code';
+
+describe('pairing codes', () => {
+	it('generates valid codes of the expected length', () => {
+		for (let i = 0; i < 100; i++) {
+			const code = generatePairingCode();
+			expect(code).toHaveLength(PAIRING_CODE_LENGTH);
+			expect(isValidPairingCode(code)).toBe(true);
+		}
+	});
+
+	it('rejects a single substituted character', () => {
+		const code = generatePairingCode();
+		const replacement = code[2] === 'A' ? 'B' : 'A';
+		const typo = code.slice(0, 2) + replacement + code.slice(3);
+		expect(isValidPairingCode(typo)).toBe(false);
+	});
+
+	it('normalizes separators, case and confusable letters', () => {
+		expect(normalizePairingCode('ab1o-il2z')).toBe('AB10112Z');
+		expect(normalizePairingCode(' abcd efgh ')).toBe('ABCDEFGH');
+	});
+
+	it('formats codes with a separator in the middle', () => {
+		expect(formatPairingCode('ABCDEFGH')).toBe('ABCD-EFGH');
+	});
+});
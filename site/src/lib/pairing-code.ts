@@ -0,0 +1,67 @@
+/**
+ * Short, human-typeable pairing codes.
+ *
+ * Codes use the Crockford base32 alphabet (no I, L, O or U) and end with a
+ * Luhn mod 32 check character, so typos are caught before a lookup attempt
+ * is spent against the rate limit.
+ */
+
+const ALPHABET = '0123456789ABCDEFGHJKMNPQRSTVWXYZ';
+const BASE = ALPHABET.length;
+
+export const PAIRING_CODE_LENGTH = 8;
+
+function checkCharacter(payload: string): string {
+	let factor = 2;
+	let sum = 0;
+
+	for (let i = payload.length - 1; i >= 0; i--) {
+		let addend = factor * ALPHABET.indexOf(payload[i]);
+		factor = factor === 2 ? 1 : 2;
+		addend = Math.floor(addend / BASE) + (addend % BASE);
+		sum += addend;
+	}
+
+	return ALPHABET[(BASE - (sum % BASE)) % BASE];
+}
+
+/**
+ * Generate a new random pairing code (without separators)
+ */
+export function generatePairingCode(): string {
+	const bytes = new Uint8Array(PAIRING_CODE_LENGTH - 1);
+	crypto.getRandomValues(bytes);
+
+	const payload = Array.from(bytes, (b) => ALPHABET[b % BASE]).join('');
+	return payload + checkCharacter(payload);
+}
+
+/**
+ * Normalize user input: uppercase, drop separators and map
+ * commonly confused letters onto their digits
+ */
+export function normalizePairingCode(input: string): string {
+	return input
+		.toUpperCase()
+		.replace(/[\s-]/g, '')
+		.replace(/O/g, '0')
+		.replace(/[IL]/g, '1');
+}
+
+/**
+ * Check the length, alphabet and check character of a normalized code
+ */
+export function isValidPairingCode(code: string): boolean {
+	if (code.length !== PAIRING_CODE_LENGTH) return false;
+	if (![...code].every((c) => ALPHABET.includes(c))) return false;
+
+	return checkCharacter(code.slice(0, -1)) === code[code.length - 1];
+}
+
+/**
+ * Format a code for display, e.g. "ABCD-EFGH"
+ */
+export function formatPairingCode(code: string): string {
+	const half = Math.ceil(code.length / 2);
+	return `${code.slice(0, half)}-${code.slice(half)}`;
+}
@@ -0,0 +1,73 @@
+import { error } from '@sveltejs/kit';
+import { eq, or } from 'drizzle-orm';
+import { db } from '$lib/server/db';
+import { pairingSessions } from '$lib/server/db/schema';
+
+// Device tokens are valid for 90 days and should be rotated after 30
+export const DEVICE_TOKEN_TTL_MS = 90 * 24 * 60 * 60 * 1000;
+export const DEVICE_TOKEN_ROTATE_AFTER_MS = 30 * 24 * 60 * 60 * 1000;
+
+// How long the previous token keeps working after a rotation, so hook
+// processes that were already running can finish their requests
+export const PREVIOUS_TOKEN_GRACE_MS = 10 * 60 * 1000;
+
+export type PairingSession = typeof pairingSessions.$inferSelect;
+
+/**
+ * Resolve the pairing session for the bearer token on the request.
+ * Fails with 401 when the token is missing, unknown, revoked or expired.
+ */
+export async function authenticateDevice(
+	request: Request
+): Promise<{ session: PairingSession; deviceToken: string }> {
+	const authHeader = request.headers.get('Authorization');
+
+	if (!authHeader?.startsWith('Bearer ')) {
+		error(401, 'Missing or invalid authorization header');
+	}
+
+	const deviceToken = authHeader.slice(7);
+
+	const session = await db
+		.select()
+		.from(pairingSessions)
+		.where(
+			or(
+				eq(pairingSessions.deviceToken, deviceToken),
+				eq(pairingSessions.previousDeviceToken, deviceToken)
+			)
+		)
+		.get();
+
+	if (!session) {
+		error(401, 'Invalid device token');
+	}
+
+	const now = new Date();
+	const expiresAt =
+		session.deviceToken === deviceToken ? session.tokenExpiresAt : session.previousTokenExpiresAt;
+
+	if (!expiresAt || expiresAt < now) {
+		error(401, 'Device token expired');
+	}
+
+	return { session, deviceToken };
+}
+
+export function newTokenExpiry(issuedAt: Date): Date {
+	return new Date(issuedAt.getTime() + DEVICE_TOKEN_TTL_MS);
+}
+
+/**
+ * Expiry metadata returned to the CLI alongside a device token, as Unix
+ * timestamps in seconds. The CLI rotates its token once rotateAfter has passed.
+ */
+export function tokenExpiryJson(tokenExpiresAt: Date): { expiresAt: number; rotateAfter: number } {
+	const rotateAfter =
+		tokenExpiresAt.getTime() - (DEVICE_TOKEN_TTL_MS - DEVICE_TOKEN_ROTATE_AFTER_MS);
+
+	return {
+		expiresAt: Math.floor(tokenExpiresAt.getTime() / 1000),
+		rotateAfter: Math.floor(rotateAfter / 1000)
+	};
+}
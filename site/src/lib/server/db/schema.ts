@@ -3,20 +3,33 @@ import { sqliteTable, text, integer } from 'drizzle-orm/sqlite-core';
 export const pairingSessions = sqliteTable('pairing_sessions', {
 	id: text('id').primaryKey(),
 	pairingToken: text('pairing_token').notNull().unique(),
+	// Short typeable alternative to pairingToken, see $lib/pairing-code
+	pairingCode: text('pairing_code').unique(),
 	deviceToken: text('device_token').unique(),
 	pushSubscription: text('push_subscription'),
 	createdAt: integer('created_at', { mode: 'timestamp' }).notNull(),
-	completedAt: integer('completed_at', { mode: 'timestamp' })
+	completedAt: integer('completed_at', { mode: 'timestamp' }),
+	tokenExpiresAt: integer('token_expires_at', { mode: 'timestamp' }),
+	// Token replaced by the last rotation, accepted until previousTokenExpiresAt
+	previousDeviceToken: text('previous_device_token'),
+	previousTokenExpiresAt: integer('previous_token_expires_at', { mode: 'timestamp' }),
+	// Set when the push service reports the subscription as gone (404/410)
+	subscriptionExpiredAt: integer('subscription_expired_at', { mode: 'timestamp' })
 });
 
 export const pendingDecisions = sqliteTable('pending_decisions', {
 	id: text('id').primaryKey(), // nanoid(21)
-	deviceToken: text('device_token').notNull(),
+	// The pairing, not the token, so rotating the token doesn't orphan decisions
+	pairingSessionId: text('pairing_session_id'),
 	toolUseId: text('tool_use_id').notNull(),
 	claudeSessionId: text('claude_session_id').notNull(),
 	title: text('title').notNull(),
 	message: text('message').notNull(),
-	decision: text('decision'), // null=pending, 'allow', 'dismiss'
+	context: text('context'), // JSON DecisionContext from the session transcript, if any
+	sessionLabel: text('session_label'), // e.g. 'claude-afk (main) · laptop'
+	decision: text('decision'), // null=pending, 'allow', 'dismiss', 'cancelled' (answered at the terminal)
+	decisionSource: text('decision_source'), // 'notification', 'details' or 'terminal'
+	decidedBy: text('decided_by'), // device description from the User-Agent, e.g. 'Chrome on Android'
 	createdAt: integer('created_at', { mode: 'timestamp' }).notNull(),
 	decidedAt: integer('decided_at', { mode: 'timestamp' }),
 	expiresAt: integer('expires_at', { mode: 'timestamp' }).notNull()
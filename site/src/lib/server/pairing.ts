@@ -0,0 +1,16 @@
+import type { pairingSessions } from '$lib/server/db/schema';
+
+// Matches the CLI's SETUP_TIMEOUT
+export const PAIRING_EXPIRY_MS = 5 * 60 * 1000;
+
+export function isPairingExpired(session: typeof pairingSessions.$inferSelect): boolean {
+	return session.createdAt.getTime() + PAIRING_EXPIRY_MS < Date.now();
+}
+
+/**
+ * When an unfinished pairing expires, as a Unix timestamp in seconds, so a CLI
+ * resuming it with `pair --wait` knows how long it has left
+ */
+export function pairingExpiresAt(createdAt: Date): number {
+	return Math.floor((createdAt.getTime() + PAIRING_EXPIRY_MS) / 1000);
+}
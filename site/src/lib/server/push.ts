@@ -1,5 +1,8 @@
 import webpush from 'web-push';
+import { eq } from 'drizzle-orm';
 import { env } from '$env/dynamic/private';
+import { db } from '$lib/server/db';
+import { pairingSessions } from '$lib/server/db/schema';
 
 export interface NotificationPayload {
 	title: string;
@@ -34,4 +37,34 @@ export async function sendPushNotification(
 	await webpush.sendNotification(subscription, JSON.stringify(payload));
 }
 
+/**
+ * Ask the service worker to close the notification with `tag` without showing
+ * anything, e.g. once a permission prompt was answered at the terminal
+ */
+export async function sendRetraction(
+	subscription: webpush.PushSubscription,
+	tag: string
+): Promise<void> {
+	ensureVapidInitialized();
+	await webpush.sendNotification(subscription, JSON.stringify({ type: 'retract', tag }));
+}
+
+/**
+ * Whether a push failure means the subscription no longer exists,
+ * e.g. the user revoked notification permission or cleared site data
+ */
+export function isSubscriptionGone(err: unknown): boolean {
+	return err instanceof webpush.WebPushError && (err.statusCode === 404 || err.statusCode === 410);
+}
+
+/**
+ * Record that a session's push subscription is gone so status checks can report it
+ */
+export async function markSubscriptionExpired(sessionId: string): Promise<void> {
+	await db
+		.update(pairingSessions)
+		.set({ subscriptionExpiredAt: new Date() })
+		.where(eq(pairingSessions.id, sessionId));
+}
+
 export { webpush };
@@ -0,0 +1,30 @@
+interface Window {
+	count: number;
+	resetAt: number;
+}
+
+/**
+ * Fixed-window in-memory rate limiter. Good enough for a single
+ * node process; state is lost on restart.
+ */
+export function createRateLimiter(limit: number, windowMs: number) {
+	const windows = new Map<string, Window>();
+
+	return {
+		/** Record an attempt for key, returning false once the limit is exceeded */
+		attempt(key: string): boolean {
+			const now = Date.now();
+
+			// Drop expired windows so the map doesn't grow without bound
+			for (const [k, w] of windows) {
+				if (w.resetAt <= now) windows.delete(k);
+			}
+
+			const window = windows.get(key) ?? { count: 0, resetAt: now + windowMs };
+			window.count += 1;
+			windows.set(key, window);
+
+			return window.count <= limit;
+		}
+	};
+}
@@ -0,0 +1,36 @@
+import { describe, it, expect } from 'vitest';
+import { labelledBody, sanitizeSession } from './session';
+
+describe('session info', () => {
+	it('keeps known fields', () => {
+		expect(
+			sanitizeSession({
+				id: 's1',
+				label: 'app (main) · laptop',
+				project: 'app',
+				branch: 'main',
+				host: 42,
+				extra: true
+			})
+		).toEqual({ id: 's1', label: 'app (main) · laptop', project: 'app', branch: 'main' });
+	});
+
+	it('drops sessions without id or label', () => {
+		expect(sanitizeSession(undefined)).toBeNull();
+		expect(sanitizeSession({ id: 's1' })).toBeNull();
+		expect(sanitizeSession({ label: 'app' })).toBeNull();
+		expect(sanitizeSession(['s1'])).toBeNull();
+	});
+
+	it('caps field length', () => {
+		const session = sanitizeSession({ id: 's1', label: 'x'.repeat(500) });
+		expect(session!.label).toHaveLength(100);
+	});
+
+	it('prefixes the body with the label', () => {
+		expect(labelledBody('npm test', { id: 's1', label: 'app (main)' })).toBe(
+			'📂 app (main)\nnpm test'
+		);
+		expect(labelledBody('npm test', null)).toBe('npm test');
+	});
+});
@@ -0,0 +1,45 @@
+/**
+ * The Claude Code session a notification comes from, as sent by the CLI.
+ * `label` is what the phone shows; the other fields allow grouping.
+ */
+export interface SessionInfo {
+	id: string;
+	label: string;
+	project?: string;
+	branch?: string;
+	host?: string;
+}
+
+const MAX_FIELD_LENGTH = 100;
+
+function field(value: unknown): string | undefined {
+	if (typeof value !== 'string') {
+		return undefined;
+	}
+	const trimmed = value.trim().slice(0, MAX_FIELD_LENGTH);
+	return trimmed || undefined;
+}
+
+/** Validate an untrusted session object; without an id and label it is dropped */
+export function sanitizeSession(raw: unknown): SessionInfo | null {
+	if (!raw || typeof raw !== 'object' || Array.isArray(raw)) {
+		return null;
+	}
+	const value = raw as Record<string, unknown>;
+	const id = field(value.id);
+	const label = field(value.label);
+	if (!id || !label) {
+		return null;
+	}
+	const session: SessionInfo = { id, label };
+	for (const key of ['project', 'branch', 'host'] as const) {
+		const v = field(value[key]);
+		if (v) session[key] = v;
+	}
+	return session;
+}
+
+/** Put the session label on the first line of a notification body */
+export function labelledBody(body: string, session: SessionInfo | null): string {
+	return session ? `📂 ${session.label}\n${body}` : body;
+}
@@ -0,0 +1,33 @@
+import { describe, it, expect } from 'vitest';
+import { describeUserAgent } from './user-agent';
+
+describe('describeUserAgent', () => {
+	it('recognizes common mobile browsers', () => {
+		expect(
+			describeUserAgent(
+				'Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Mobile Safari/537.36'
+			)
+		).toBe('Chrome on Android');
+		expect(
+			describeUserAgent(
+				'Mozilla/5.0 (iPhone; CPU iPhone OS 18_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.0 Mobile/15E148 Safari/604.1'
+			)
+		).toBe('Safari on iOS');
+		expect(
+			describeUserAgent('Mozilla/5.0 (Android 14; Mobile; rv:131.0) Gecko/131.0 Firefox/131.0')
+		).toBe('Firefox on Android');
+	});
+
+	it('prefers Edge over Chrome', () => {
+		expect(
+			describeUserAgent(
+				'Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36 Edg/129.0.0.0'
+			)
+		).toBe('Edge on Windows');
+	});
+
+	it('handles missing or unknown agents', () => {
+		expect(describeUserAgent(null)).toBeNull();
+		expect(describeUserAgent('curl/8.5.0')).toBe('Unknown device');
+	});
+});
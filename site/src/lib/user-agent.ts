@@ -0,0 +1,39 @@
+/**
+ * Short description of the device that sent a request, e.g. "Chrome on Android",
+ * recorded with each decision for the CLI's audit log
+ */
+export function describeUserAgent(userAgent: string | null): string | null {
+	if (!userAgent) {
+		return null;
+	}
+
+	// Order matters: Edge and Opera include "Chrome", Chrome includes "Safari"
+	const browser = /Edg\//.test(userAgent)
+		? 'Edge'
+		: /OPR\/|Opera/.test(userAgent)
+			? 'Opera'
+			: /SamsungBrowser/.test(userAgent)
+				? 'Samsung Internet'
+				: /Firefox\/|FxiOS/.test(userAgent)
+					? 'Firefox'
+					: /Chrome\/|CriOS/.test(userAgent)
+						? 'Chrome'
+						: /Safari\//.test(userAgent)
+							? 'Safari'
+							: null;
+
+	const os = /Android/.test(userAgent)
+		? 'Android'
+		: /iPhone|iPad|iPod/.test(userAgent)
+			? 'iOS'
+			: /Mac OS X|Macintosh/.test(userAgent)
+				? 'macOS'
+				: /Windows/.test(userAgent)
+					? 'Windows'
+					: /Linux|X11/.test(userAgent)
+						? 'Linux'
+						: null;
+
+	if (browser && os) return `${browser} on ${os}`;
+	return browser ?? os ?? 'Unknown device';
+}
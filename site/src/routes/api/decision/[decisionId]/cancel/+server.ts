@@ -0,0 +1,63 @@
+import { json, error } from '@sveltejs/kit';
+import { eq, and, isNull } from 'drizzle-orm';
+import { db } from '$lib/server/db';
+import { pendingDecisions } from '$lib/server/db/schema';
+import { authenticateDevice } from '$lib/server/auth';
+import { CANCELLED_DECISION } from '$lib/decision-status';
+import { isSubscriptionGone, markSubscriptionExpired, sendRetraction } from '$lib/server/push';
+import type { RequestHandler } from './$types';
+
+/**
+ * Called by the CLI when a permission prompt was answered at the terminal.
+ * Marks the decision cancelled, so the waiting hook exits, and closes the
+ * notification on the phone.
+ */
+export const POST: RequestHandler = async ({ params, request }) => {
+	const { session } = await authenticateDevice(request);
+	const { decisionId } = params;
+
+	const decision = await db
+		.select()
+		.from(pendingDecisions)
+		.where(
+			and(eq(pendingDecisions.id, decisionId), eq(pendingDecisions.pairingSessionId, session.id))
+		)
+		.get();
+
+	if (!decision) {
+		error(404, 'Decision not found');
+	}
+
+	// The phone got there first
+	if (decision.decision) {
+		return json({
+			success: true,
+			cancelled: decision.decision === CANCELLED_DECISION,
+			decision: decision.decision
+		});
+	}
+
+	// Only cancel if still undecided, so a tap racing this request wins
+	await db
+		.update(pendingDecisions)
+		.set({
+			decision: CANCELLED_DECISION,
+			decidedAt: new Date(),
+			decisionSource: 'terminal'
+		})
+		.where(and(eq(pendingDecisions.id, decisionId), isNull(pendingDecisions.decision)));
+
+	if (session.pushSubscription) {
+		try {
+			await sendRetraction(JSON.parse(session.pushSubscription), decision.toolUseId);
+		} catch (err) {
+			// The decision is cancelled either way; the notification just lingers
+			console.error('Push retraction failed:', err);
+			if (isSubscriptionGone(err)) {
+				await markSubscriptionExpired(session.id);
+			}
+		}
+	}
+
+	return json({ success: true, cancelled: true });
+};
@@ -2,47 +2,40 @@ import { json, error } from '@sveltejs/kit';
 import { eq, and } from 'drizzle-orm';
 import { db } from '$lib/server/db';
 import { pendingDecisions } from '$lib/server/db/schema';
+import { authenticateDevice } from '$lib/server/auth';
+import { decisionStatus } from '$lib/decision-status';
 import type { RequestHandler } from './$types';
 
 export const GET: RequestHandler = async ({ params, request }) => {
-	const authHeader = request.headers.get('Authorization');
-
-	if (!authHeader?.startsWith('Bearer ')) {
-		error(401, 'Missing or invalid authorization header');
-	}
-
-	const deviceToken = authHeader.slice(7);
+	const { session } = await authenticateDevice(request);
 	const { decisionId } = params;
 
 	const decision = await db
 		.select()
 		.from(pendingDecisions)
-		.where(and(eq(pendingDecisions.id, decisionId), eq(pendingDecisions.deviceToken, deviceToken)))
+		.where(
+			and(eq(pendingDecisions.id, decisionId), eq(pendingDecisions.pairingSessionId, session.id))
+		)
 		.get();
 
 	if (!decision) {
 		error(404, 'Decision not found');
 	}
 
-	// Check if expired
-	const now = new Date();
-	if (decision.expiresAt < now) {
-		return json({
-			status: 'expired',
-			decision: null
-		});
-	}
+	const status = decisionStatus(decision);
 
-	// Check if decided
-	if (decision.decision) {
+	if (status === 'decided') {
 		return json({
-			status: 'decided',
-			decision: decision.decision
+			status,
+			decision: decision.decision,
+			source: decision.decisionSource,
+			device: decision.decidedBy
 		});
 	}
 
+	// 'pending', 'expired', or 'cancelled' when the prompt was answered at the terminal
 	return json({
-		status: 'pending',
+		status,
 		decision: null
 	});
 };
@@ -2,11 +2,13 @@ import { json, error } from '@sveltejs/kit';
 import { eq } from 'drizzle-orm';
 import { db } from '$lib/server/db';
 import { pendingDecisions } from '$lib/server/db/schema';
+import { describeUserAgent } from '$lib/user-agent';
+import { CANCELLED_DECISION } from '$lib/decision-status';
 import type { RequestHandler } from './$types';
 
 export const POST: RequestHandler = async ({ params, request }) => {
 	const { decisionId } = params;
-	const { decision, toolUseId } = await request.json();
+	const { decision, toolUseId, source } = await request.json();
 
 	if (!decision || !['allow', 'dismiss'].includes(decision)) {
 		error(400, 'Invalid decision - must be "allow" or "dismiss"');
@@ -32,6 +34,14 @@ export const POST: RequestHandler = async ({ params, request }) => {
 		error(403, 'Tool use ID mismatch');
 	}
 
+	// Answered at the terminal first; the phone prompt was retracted
+	if (pendingDecision.decision === CANCELLED_DECISION) {
+		return json({
+			success: false,
+			message: 'Answered at the terminal'
+		});
+	}
+
 	// Check if already decided
 	if (pendingDecision.decision) {
 		return json({
@@ -55,7 +65,9 @@ export const POST: RequestHandler = async ({ params, request }) => {
 		.update(pendingDecisions)
 		.set({
 			decision,
-			decidedAt: now
+			decidedAt: now,
+			decisionSource: source === 'details' ? 'details' : 'notification',
+			decidedBy: describeUserAgent(request.headers.get('user-agent'))
 		})
 		.where(eq(pendingDecisions.id, decisionId));
 
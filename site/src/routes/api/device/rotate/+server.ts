@@ -0,0 +1,45 @@
+import { json, error } from '@sveltejs/kit';
+import { eq } from 'drizzle-orm';
+import { nanoid } from 'nanoid';
+import { db } from '$lib/server/db';
+import { pairingSessions } from '$lib/server/db/schema';
+import {
+	authenticateDevice,
+	newTokenExpiry,
+	PREVIOUS_TOKEN_GRACE_MS,
+	tokenExpiryJson
+} from '$lib/server/auth';
+import type { RequestHandler } from './$types';
+
+/**
+ * Issue a new device token for an already paired device.
+ * The replaced token stays valid for a short grace period.
+ */
+export const POST: RequestHandler = async ({ request }) => {
+	const { session, deviceToken } = await authenticateDevice(request);
+
+	// Rotating with the previous token would revive a token that was already replaced
+	if (deviceToken !== session.deviceToken) {
+		error(409, 'Device token already rotated');
+	}
+
+	const newDeviceToken = nanoid(32);
+	const now = new Date();
+	const tokenExpiresAt = newTokenExpiry(now);
+
+	await db
+		.update(pairingSessions)
+		.set({
+			deviceToken: newDeviceToken,
+			tokenExpiresAt,
+			previousDeviceToken: deviceToken,
+			previousTokenExpiresAt: new Date(now.getTime() + PREVIOUS_TOKEN_GRACE_MS)
+		})
+		.where(eq(pairingSessions.id, session.id));
+
+	return json({
+		success: true,
+		deviceToken: newDeviceToken,
+		...tokenExpiryJson(tokenExpiresAt)
+	});
+};
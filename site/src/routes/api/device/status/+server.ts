@@ -0,0 +1,29 @@
+import { json } from '@sveltejs/kit';
+import { authenticateDevice, tokenExpiryJson } from '$lib/server/auth';
+import type { RequestHandler } from './$types';
+
+/**
+ * Health check for a paired device. Authentication failures surface as 401
+ * from authenticateDevice, everything else is reported in the body.
+ */
+export const GET: RequestHandler = async ({ request }) => {
+	const { session, deviceToken } = await authenticateDevice(request);
+
+	let subscription: 'active' | 'missing' | 'expired' = 'active';
+	if (!session.pushSubscription) {
+		subscription = 'missing';
+	} else if (session.subscriptionExpiredAt) {
+		subscription = 'expired';
+	}
+
+	// A previous token inside its grace period is valid but should be replaced
+	const expiresAt =
+		deviceToken === session.deviceToken ? session.tokenExpiresAt : session.previousTokenExpiresAt;
+
+	return json({
+		valid: true,
+		subscription,
+		rotated: deviceToken !== session.deviceToken,
+		...(expiresAt ? tokenExpiryJson(expiresAt) : {})
+	});
+};
@@ -1,38 +1,52 @@
 import { json, error } from '@sveltejs/kit';
-import { eq } from 'drizzle-orm';
 import { nanoid } from 'nanoid';
 import { db } from '$lib/server/db';
-import { pairingSessions, pendingDecisions } from '$lib/server/db/schema';
-import { sendPushNotification } from '$lib/server/push';
+import { pendingDecisions } from '$lib/server/db/schema';
+import { authenticateDevice, PREVIOUS_TOKEN_GRACE_MS } from '$lib/server/auth';
+import { notificationBody, sanitizeContext } from '$lib/decision-context';
+import { labelledBody, sanitizeSession } from '$lib/session';
+import {
+	isSubscriptionGone,
+	markSubscriptionExpired,
+	sendPushNotification
+} from '$lib/server/push';
 import type { RequestHandler } from './$types';
 
-// Decision expiry time in milliseconds (5 minutes)
+// Decision expiry when the CLI doesn't say how long it waits (5 minutes)
 const DECISION_EXPIRY_MS = 5 * 60 * 1000;
 
-export const POST: RequestHandler = async ({ request }) => {
-	const authHeader = request.headers.get('Authorization');
-
-	if (!authHeader?.startsWith('Bearer ')) {
-		error(401, 'Missing or invalid authorization header');
+// Longest a CLI may wait. No longer than the grace period of a rotated token,
+// so a hook that started polling before another hook rotated the token can
+// still poll until the decision expires.
+const MAX_DECISION_EXPIRY_MS = PREVIOUS_TOKEN_GRACE_MS;
+
+/**
+ * Expiry for a decision from the CLI's `timeout` (seconds), clamped to
+ * MAX_DECISION_EXPIRY_MS
+ */
+function decisionExpiryMs(timeout: unknown): number {
+	if (typeof timeout !== 'number' || !Number.isFinite(timeout) || timeout <= 0) {
+		return DECISION_EXPIRY_MS;
 	}
+	return Math.min(timeout * 1000, MAX_DECISION_EXPIRY_MS);
+}
 
-	const deviceToken = authHeader.slice(7);
-
-	const session = await db
-		.select()
-		.from(pairingSessions)
-		.where(eq(pairingSessions.deviceToken, deviceToken))
-		.get();
-
-	if (!session) {
-		error(401, 'Invalid device token');
-	}
+export const POST: RequestHandler = async ({ request }) => {
+	const { session } = await authenticateDevice(request);
 
 	if (!session.pushSubscription) {
 		error(400, 'No push subscription found');
 	}
 
-	const { title, message, tool_use_id, session_id } = await request.json();
+	const {
+		title,
+		message,
+		tool_use_id,
+		session_id,
+		session: rawClaudeSession,
+		context: rawContext,
+		timeout
+	} = await request.json();
 
 	if (!message) {
 		error(400, 'Message is required');
@@ -47,19 +61,23 @@ export const POST: RequestHandler = async ({ request }) => {
 	}
 
 	const subscription = JSON.parse(session.pushSubscription);
+	const context = sanitizeContext(rawContext);
+	const claudeSession = sanitizeSession(rawClaudeSession);
 
 	// Create pending decision record
 	const decisionId = nanoid(21);
 	const now = new Date();
-	const expiresAt = new Date(now.getTime() + DECISION_EXPIRY_MS);
+	const expiresAt = new Date(now.getTime() + decisionExpiryMs(timeout));
 
 	await db.insert(pendingDecisions).values({
 		id: decisionId,
-		deviceToken,
+		pairingSessionId: session.id,
 		toolUseId: tool_use_id,
 		claudeSessionId: session_id,
 		title: title || 'Claude Code',
 		message,
+		context: context ? JSON.stringify(context) : null,
+		sessionLabel: claudeSession?.label ?? null,
 		createdAt: now,
 		expiresAt
 	});
@@ -67,7 +85,7 @@ export const POST: RequestHandler = async ({ request }) => {
 	try {
 		await sendPushNotification(subscription, {
 			title: title || 'Claude Code',
-			body: message,
+			body: labelledBody(notificationBody(message, context), claudeSession),
 			icon: '/icon-192.png',
 			badge: '/badge-72.png',
 			tag: tool_use_id,
@@ -79,20 +97,34 @@ export const POST: RequestHandler = async ({ request }) => {
 					title: 'Allow',
 					icon: '/icon-192.png'
 				},
-				{
-					action: 'dismiss',
-					title: 'Dismiss',
-					icon: '/badge-72.png'
-				}
+				// Most platforms show only two actions. With context, the second one opens
+				// the details page instead; closing the notification still dismisses.
+				context
+					? {
+							action: 'details',
+							title: 'Details',
+							icon: '/badge-72.png'
+						}
+					: {
+							action: 'dismiss',
+							title: 'Dismiss',
+							icon: '/badge-72.png'
+						}
 			],
 			data: {
 				decisionId,
 				toolUseId: tool_use_id,
-				type: 'decision'
+				type: 'decision',
+				session: claudeSession,
+				url: `/decision/${decisionId}?toolUseId=${encodeURIComponent(tool_use_id)}`
 			}
 		});
 	} catch (err) {
 		console.error('Push notification failed:', err);
+		if (isSubscriptionGone(err)) {
+			await markSubscriptionExpired(session.id);
+			error(410, 'Push subscription expired - pair the device again');
+		}
 		error(500, 'Failed to send push notification');
 	}
 
@@ -1,8 +1,11 @@
 import { json, error } from '@sveltejs/kit';
-import { eq } from 'drizzle-orm';
-import { db } from '$lib/server/db';
-import { pairingSessions } from '$lib/server/db/schema';
-import { sendPushNotification } from '$lib/server/push';
+import { authenticateDevice } from '$lib/server/auth';
+import { labelledBody, sanitizeSession } from '$lib/session';
+import {
+	isSubscriptionGone,
+	markSubscriptionExpired,
+	sendPushNotification
+} from '$lib/server/push';
 import type { RequestHandler } from './$types';
 
 /**
@@ -11,51 +14,42 @@ import type { RequestHandler } from './$types';
  * No decision record is created and no action buttons are shown.
  */
 export const POST: RequestHandler = async ({ request }) => {
-	const authHeader = request.headers.get('Authorization');
-
-	if (!authHeader?.startsWith('Bearer ')) {
-		error(401, 'Missing or invalid authorization header');
-	}
-
-	const deviceToken = authHeader.slice(7);
-
-	const session = await db
-		.select()
-		.from(pairingSessions)
-		.where(eq(pairingSessions.deviceToken, deviceToken))
-		.get();
-
-	if (!session) {
-		error(401, 'Invalid device token');
-	}
+	const { session } = await authenticateDevice(request);
 
 	if (!session.pushSubscription) {
 		error(400, 'No push subscription found');
 	}
 
-	const { title, message } = await request.json();
+	const { title, message, session: rawClaudeSession } = await request.json();
 
 	if (!message) {
 		error(400, 'Message is required');
 	}
 
 	const subscription = JSON.parse(session.pushSubscription);
+	const claudeSession = sanitizeSession(rawClaudeSession);
 
 	try {
 		await sendPushNotification(subscription, {
 			title: title || 'Claude Code',
-			body: message,
+			body: labelledBody(message, claudeSession),
 			icon: '/icon-192.png',
 			badge: '/badge-72.png',
-			tag: 'idle-notification',
+			// One idle notification per Claude session, so parallel sessions don't replace each other
+			tag: claudeSession ? `idle-${claudeSession.id}` : 'idle-notification',
 			renotify: true,
 			requireInteraction: false,
 			data: {
-				type: 'notification'
+				type: 'notification',
+				session: claudeSession
 			}
 		});
 	} catch (err) {
 		console.error('Push notification failed:', err);
+		if (isSubscriptionGone(err)) {
+			await markSubscriptionExpired(session.id);
+			error(410, 'Push subscription expired - pair the device again');
+		}
 		error(500, 'Failed to send push notification');
 	}
 
@@ -2,6 +2,8 @@ import { json, error } from '@sveltejs/kit';
 import { eq } from 'drizzle-orm';
 import { db } from '$lib/server/db';
 import { pairingSessions } from '$lib/server/db/schema';
+import { tokenExpiryJson } from '$lib/server/auth';
+import { isPairingExpired, pairingExpiresAt } from '$lib/server/pairing';
 import type { RequestHandler } from './$types';
 
 export const GET: RequestHandler = async ({ params }) => {
@@ -17,8 +19,21 @@ export const GET: RequestHandler = async ({ params }) => {
 
 	const complete = session.completedAt !== null;
 
+	if (!complete && isPairingExpired(session)) {
+		error(410, 'Pairing session expired');
+	}
+
+	if (!complete) {
+		return json({
+			complete,
+			deviceToken: null,
+			pairingExpiresAt: pairingExpiresAt(session.createdAt)
+		});
+	}
+
 	return json({
 		complete,
-		deviceToken: complete ? session.deviceToken : null
+		deviceToken: session.deviceToken,
+		...(session.tokenExpiresAt ? tokenExpiryJson(session.tokenExpiresAt) : {})
 	});
 };
@@ -3,6 +3,8 @@ import { eq } from 'drizzle-orm';
 import { nanoid } from 'nanoid';
 import { db } from '$lib/server/db';
 import { pairingSessions } from '$lib/server/db/schema';
+import { isPairingExpired } from '$lib/server/pairing';
+import { newTokenExpiry } from '$lib/server/auth';
 import type { RequestHandler } from './$types';
 
 export const POST: RequestHandler = async ({ params, request }) => {
@@ -20,6 +22,10 @@ export const POST: RequestHandler = async ({ params, request }) => {
 		error(400, 'Pairing session already completed');
 	}
 
+	if (isPairingExpired(session)) {
+		error(410, 'Pairing session expired');
+	}
+
 	const { subscription } = await request.json();
 
 	if (!subscription || !subscription.endpoint) {
@@ -27,13 +33,15 @@ export const POST: RequestHandler = async ({ params, request }) => {
 	}
 
 	const deviceToken = nanoid(32);
+	const now = new Date();
 
 	await db
 		.update(pairingSessions)
 		.set({
 			pushSubscription: JSON.stringify(subscription),
 			deviceToken,
-			completedAt: new Date()
+			completedAt: now,
+			tokenExpiresAt: newTokenExpiry(now)
 		})
 		.where(eq(pairingSessions.pairingToken, params.pairingToken));
 
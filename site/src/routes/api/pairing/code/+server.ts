@@ -0,0 +1,39 @@
+import { json, error } from '@sveltejs/kit';
+import { eq } from 'drizzle-orm';
+import { db } from '$lib/server/db';
+import { pairingSessions } from '$lib/server/db/schema';
+import { isPairingExpired } from '$lib/server/pairing';
+import { createRateLimiter } from '$lib/server/rate-limit';
+import { isValidPairingCode, normalizePairingCode } from '$lib/pairing-code';
+import type { RequestHandler } from './$types';
+
+// 5 lookups per client per 10 minutes keeps brute-forcing the code space impractical
+const limiter = createRateLimiter(5, 10 * 60 * 1000);
+
+/**
+ * Exchange a short pairing code for the pairing token used by /pair/[pairingToken]
+ */
+export const POST: RequestHandler = async ({ request, getClientAddress }) => {
+	if (!limiter.attempt(getClientAddress())) {
+		error(429, 'Too many attempts - please wait a few minutes and try again');
+	}
+
+	const { code } = await request.json();
+	const normalized = normalizePairingCode(String(code ?? ''));
+
+	if (!isValidPairingCode(normalized)) {
+		error(400, 'Invalid pairing code');
+	}
+
+	const session = await db
+		.select()
+		.from(pairingSessions)
+		.where(eq(pairingSessions.pairingCode, normalized))
+		.get();
+
+	if (!session || session.completedAt || isPairingExpired(session)) {
+		error(404, 'Pairing code not found or expired');
+	}
+
+	return json({ pairingToken: session.pairingToken });
+};
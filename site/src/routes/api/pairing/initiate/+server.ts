@@ -2,20 +2,27 @@ import { json } from '@sveltejs/kit';
 import { nanoid } from 'nanoid';
 import { db } from '$lib/server/db';
 import { pairingSessions } from '$lib/server/db/schema';
+import { generatePairingCode } from '$lib/pairing-code';
+import { pairingExpiresAt } from '$lib/server/pairing';
 import type { RequestHandler } from './$types';
 
 export const POST: RequestHandler = async () => {
 	const id = nanoid(21);
 	const pairingToken = nanoid(32);
+	const pairingCode = generatePairingCode();
+	const createdAt = new Date();
 
 	await db.insert(pairingSessions).values({
 		id,
 		pairingToken,
-		createdAt: new Date()
+		pairingCode,
+		createdAt
 	});
 
 	return json({
 		pairingId: id,
-		pairingToken
+		pairingToken,
+		pairingCode,
+		pairingExpiresAt: pairingExpiresAt(createdAt)
 	});
 };
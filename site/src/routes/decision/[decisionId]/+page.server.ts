@@ -0,0 +1,36 @@
+import { error } from '@sveltejs/kit';
+import { eq } from 'drizzle-orm';
+import { db } from '$lib/server/db';
+import { pendingDecisions } from '$lib/server/db/schema';
+import { parseStoredContext } from '$lib/decision-context';
+import { decisionStatus } from '$lib/decision-status';
+import type { PageServerLoad } from './$types';
+
+export const load: PageServerLoad = async ({ params, url }) => {
+	const toolUseId = url.searchParams.get('toolUseId');
+
+	const decision = db
+		.select()
+		.from(pendingDecisions)
+		.where(eq(pendingDecisions.id, params.decisionId))
+		.get();
+
+	// The toolUseId from the notification acts as the capability, as in the submit endpoint
+	if (!decision || !toolUseId || decision.toolUseId !== toolUseId) {
+		console.debug('Decision not found', params.decisionId);
+		error(404, 'Decision not found');
+	}
+
+	const status = decisionStatus(decision);
+
+	return {
+		decisionId: decision.id,
+		toolUseId,
+		title: decision.title,
+		sessionLabel: decision.sessionLabel,
+		message: decision.message,
+		context: parseStoredContext(decision.context),
+		status,
+		decision: decision.decision
+	};
+};
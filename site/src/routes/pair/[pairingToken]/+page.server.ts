@@ -2,6 +2,7 @@ import { error } from '@sveltejs/kit';
 import { eq } from 'drizzle-orm';
 import { db } from '$lib/server/db';
 import { pairingSessions } from '$lib/server/db/schema';
+import { isPairingExpired } from '$lib/server/pairing';
 import { env } from '$env/dynamic/private';
 import type { PageServerLoad } from './$types';
 
@@ -22,6 +23,11 @@ export const load: PageServerLoad = async ({ params }) => {
 		error(400, 'Pairing session already completed');
 	}
 
+	if (isPairingExpired(session)) {
+		console.debug('Pairing session expired', params.pairingToken);
+		error(410, 'Pairing session expired');
+	}
+
 	if (!env.VAPID_PUBLIC_KEY) {
 		console.debug('VAPID public key not found', env.VAPID_PUBLIC_KEY);
 		error(500, 'Server not configured for push notifications');
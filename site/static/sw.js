@@ -19,7 +19,7 @@ async function submitDecision(decisionId, toolUseId, decision) {
             headers: {
                 'Content-Type': 'application/json'
             },
-            body: JSON.stringify({ decision, toolUseId })
+            body: JSON.stringify({ decision, toolUseId, source: 'notification' })
         });
         if (!response.ok) {
             console.error('Failed to submit decision:', response.status);
@@ -30,9 +30,18 @@ async function submitDecision(decisionId, toolUseId, decision) {
 }
 
 sw.addEventListener('push', (event) => {
-    /** @type {{ title?: string; body?: string; icon?: string; badge?: string; data?: object; tag?: string; renotify?: boolean; requireInteraction?: boolean; actions?: Array<{action: string; title: string; icon?: string}> } | null} */
+    /** @type {{ type?: string; title?: string; body?: string; icon?: string; badge?: string; data?: object; tag?: string; renotify?: boolean; requireInteraction?: boolean; actions?: Array<{action: string; title: string; icon?: string}> } | null} */
     const data = event.data?.json() ?? {};
 
+    // The prompt was answered at the terminal: close its notification. Closing it
+    // programmatically doesn't fire notificationclose, so nothing is submitted.
+    if (data?.type === 'retract') {
+        if (data.tag) {
+            event.waitUntil(closeNotifications(data.tag));
+        }
+        return;
+    }
+
     const title = data?.title || 'Claude Code';
     const options = {
         body: data?.body || '',
@@ -70,6 +79,15 @@ sw.addEventListener('notificationclick', (event) => {
         return;
     }
 
+    // "Details" opens the decision page, where the user can read the context and decide.
+    // Closing the notification programmatically doesn't fire notificationclose,
+    // so the decision stays pending until the page submits it.
+    if (action === 'details' && notificationData.type === 'decision' && notificationData.url) {
+        event.notification.close();
+        event.waitUntil(openUrl(notificationData.url));
+        return;
+    }
+
     // If this is a decision notification and user clicked the notification body
     // (not an action button) - treat as "allow" since Android doesn't show action buttons
     if (!action && notificationData.type === 'decision' && notificationData.decisionId) {
@@ -97,6 +115,32 @@ sw.addEventListener('notificationclick', (event) => {
     );
 });
 
+/**
+ * Close the notifications shown with `tag`
+ * @param {string} tag
+ */
+async function closeNotifications(tag) {
+    const notifications = await sw.registration.getNotifications({ tag });
+    for (const notification of notifications) {
+        notification.close();
+    }
+}
+
+/**
+ * Open a page of this site, reusing an existing window when there is one
+ * @param {string} url
+ */
+async function openUrl(url) {
+    const clientList = await sw.clients.matchAll({ type: 'window' });
+    for (const client of clientList) {
+        if ('navigate' in client && 'focus' in client) {
+            await client.focus();
+            return client.navigate(url);
+        }
+    }
+    return sw.clients.openWindow(url);
+}
+
 sw.addEventListener('notificationclose', (event) => {
     const notificationData = event.notification.data || {};
 